@@ -1,9 +1,14 @@
 DROP TABLE IF EXISTS users CASCADE;
+DROP TABLE IF EXISTS notification_preferences CASCADE;
 
 DROP FUNCTION IF EXISTS create_user;
 DROP FUNCTION IF EXISTS update_user;
 DROP FUNCTION IF EXISTS delete_user;
 DROP FUNCTION IF EXISTS delete_user_by_email;
+DROP FUNCTION IF EXISTS set_notification_preferences;
+
+DROP TYPE IF EXISTS digest_frequency;
+DROP TYPE IF EXISTS notification_channel CASCADE;
 
 CREATE TABLE users (
     id SERIAL PRIMARY KEY,
@@ -88,3 +93,71 @@ BEGIN
     RETURN QUERY DELETE FROM users WHERE users.email = in_email RETURNING *;
 END;
 $$;
+
+CREATE TYPE digest_frequency AS ENUM (
+    'instant',
+    'hourly',
+    'daily',
+    'weekly'
+);
+
+CREATE TYPE notification_channel AS ENUM ('email', 'webhook');
+
+-- users without a row here get the defaults
+CREATE TABLE notification_preferences (
+    id_user INT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    frequency digest_frequency NOT NULL DEFAULT 'instant',
+    delivery_time TIME NOT NULL DEFAULT '08:00',
+    quiet_hours_start TIME,
+    quiet_hours_end TIME,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'Europe/Bucharest',
+    channels notification_channel[] NOT NULL DEFAULT '{email,webhook}'
+);
+
+CREATE OR REPLACE FUNCTION set_notification_preferences(
+    IN in_id_user INT,
+    IN in_frequency digest_frequency,
+    IN in_delivery_time TIME,
+    IN in_quiet_hours_start TIME,
+    IN in_quiet_hours_end TIME,
+    IN in_timezone VARCHAR(64),
+    IN in_channels notification_channel[]
+) RETURNS TABLE (
+    id_user INT,
+    frequency digest_frequency,
+    delivery_time TIME,
+    quiet_hours_start TIME,
+    quiet_hours_end TIME,
+    timezone VARCHAR(64),
+    channels notification_channel[]
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY INSERT INTO notification_preferences (
+        id_user,
+        frequency,
+        delivery_time,
+        quiet_hours_start,
+        quiet_hours_end,
+        timezone,
+        channels
+    ) VALUES (
+        in_id_user,
+        in_frequency,
+        in_delivery_time,
+        in_quiet_hours_start,
+        in_quiet_hours_end,
+        in_timezone,
+        in_channels
+    )
+    ON CONFLICT ON CONSTRAINT notification_preferences_pkey DO UPDATE SET
+        frequency = EXCLUDED.frequency,
+        delivery_time = EXCLUDED.delivery_time,
+        quiet_hours_start = EXCLUDED.quiet_hours_start,
+        quiet_hours_end = EXCLUDED.quiet_hours_end,
+        timezone = EXCLUDED.timezone,
+        channels = EXCLUDED.channels
+    RETURNING *;
+END;
+$$;
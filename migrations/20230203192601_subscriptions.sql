@@ -2,17 +2,47 @@ DROP TABLE IF EXISTS subscriptions CASCADE;
 DROP TABLE IF EXISTS title_keywords; 
 DROP TABLE IF EXISTS desc_keywords; 
 DROP TABLE IF EXISTS additional_info_keywords; 
+DROP TABLE IF EXISTS cpv_codes;
+DROP TABLE IF EXISTS counties;
+DROP TABLE IF EXISTS included_authorities;
+DROP TABLE IF EXISTS excluded_authorities;
+DROP TABLE IF EXISTS authority_aliases;
+DROP TABLE IF EXISTS title_exclude_keywords;
+DROP TABLE IF EXISTS desc_exclude_keywords;
+DROP TABLE IF EXISTS additional_info_exclude_keywords;
+DROP TABLE IF EXISTS procedure_types;
 
 DROP FUNCTION IF EXISTS create_subscription;
 DROP FUNCTION IF EXISTS update_subscription;
 DROP FUNCTION IF EXISTS get_subscriptions;
 DROP FUNCTION IF EXISTS delete_subscription;
+DROP FUNCTION IF EXISTS subscription_notifies;
+
+DROP TYPE IF EXISTS keyword_match_mode;
+DROP TYPE IF EXISTS procedure_type;
+
+CREATE TYPE keyword_match_mode AS ENUM ('substring', 'whole_word', 'stemmed');
+
+CREATE TYPE procedure_type AS ENUM (
+    'open_tender',
+    'simplified_procedure',
+    'negotiation',
+    'direct_purchase'
+);
 
 CREATE TABLE subscriptions (
     id SERIAL PRIMARY KEY,
     id_user INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
     min_price INT,
-    max_price INT
+    max_price INT,
+    title_match_mode keyword_match_mode NOT NULL DEFAULT 'substring',
+    desc_match_mode keyword_match_mode NOT NULL DEFAULT 'substring',
+    additional_info_match_mode keyword_match_mode NOT NULL DEFAULT 'substring',
+    query TEXT,
+    min_days_to_deadline INT,
+    min_score INT,
+    channels notification_channel[],
+    paused_until TIMESTAMPTZ
 );
 
 CREATE TABLE title_keywords (
@@ -33,13 +63,84 @@ CREATE TABLE additional_info_keywords (
     PRIMARY KEY (id_subscription, keyword)
 );
 
+CREATE TABLE cpv_codes (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    code VARCHAR(10),
+    PRIMARY KEY (id_subscription, code)
+);
+
+CREATE TABLE counties (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    code VARCHAR(2),
+    PRIMARY KEY (id_subscription, code)
+);
+
+CREATE TABLE included_authorities (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    cui VARCHAR(12),
+    PRIMARY KEY (id_subscription, cui)
+);
+
+CREATE TABLE excluded_authorities (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    cui VARCHAR(12),
+    PRIMARY KEY (id_subscription, cui)
+);
+
+CREATE TABLE authority_aliases (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    cui VARCHAR(12),
+    alias VARCHAR(255),
+    PRIMARY KEY (id_subscription, cui, alias)
+);
+
+CREATE TABLE title_exclude_keywords (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    keyword VARCHAR(50),
+    PRIMARY KEY (id_subscription, keyword)
+);
+
+CREATE TABLE desc_exclude_keywords (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    keyword VARCHAR(50),
+    PRIMARY KEY (id_subscription, keyword)
+);
+
+CREATE TABLE additional_info_exclude_keywords (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    keyword VARCHAR(50),
+    PRIMARY KEY (id_subscription, keyword)
+);
+
+CREATE TABLE procedure_types (
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    procedure_type procedure_type,
+    PRIMARY KEY (id_subscription, procedure_type)
+);
+
 CREATE OR REPLACE FUNCTION create_subscription(
     IN in_id_user INT,
     IN in_min_price INT,
     IN in_max_price INT,
     IN in_title_keywords TEXT[],
     IN in_desc_keywords TEXT[],
-    IN in_additional_info_keywords TEXT[]
+    IN in_additional_info_keywords TEXT[],
+    IN in_title_match_mode keyword_match_mode,
+    IN in_desc_match_mode keyword_match_mode,
+    IN in_additional_info_match_mode keyword_match_mode,
+    IN in_query TEXT,
+    IN in_cpv_codes TEXT[],
+    IN in_counties TEXT[],
+    IN in_included_authorities JSONB,
+    IN in_excluded_authorities JSONB,
+    IN in_title_exclude_keywords TEXT[],
+    IN in_desc_exclude_keywords TEXT[],
+    IN in_additional_info_exclude_keywords TEXT[],
+    IN in_min_days_to_deadline INT,
+    IN in_procedure_types procedure_type[],
+    IN in_min_score INT,
+    IN in_channels notification_channel[],
+    IN in_paused_until TIMESTAMPTZ
 ) RETURNS TABLE (
     id INT,
     id_user INT,
@@ -47,12 +148,52 @@ CREATE OR REPLACE FUNCTION create_subscription(
     max_price INT,
     title_keywords VARCHAR[],
     desc_keywords VARCHAR[],
-    additional_info_keywords VARCHAR[]
+    additional_info_keywords VARCHAR[],
+    title_match_mode keyword_match_mode,
+    desc_match_mode keyword_match_mode,
+    additional_info_match_mode keyword_match_mode,
+    query TEXT,
+    cpv_codes VARCHAR[],
+    counties VARCHAR[],
+    included_authorities JSONB,
+    excluded_authorities JSONB,
+    title_exclude_keywords VARCHAR[],
+    desc_exclude_keywords VARCHAR[],
+    additional_info_exclude_keywords VARCHAR[],
+    min_days_to_deadline INT,
+    procedure_types procedure_type[],
+    min_score INT,
+    channels notification_channel[],
+    paused_until TIMESTAMPTZ
 )
 LANGUAGE plpgsql
 AS $$
 BEGIN
-    INSERT INTO subscriptions (id_user, min_price, max_price) VALUES (in_id_user, in_min_price, in_max_price);
+    INSERT INTO subscriptions (
+        id_user,
+        min_price,
+        max_price,
+        title_match_mode,
+        desc_match_mode,
+        additional_info_match_mode,
+        query,
+        min_days_to_deadline,
+        min_score,
+        channels,
+        paused_until
+    ) VALUES (
+        in_id_user,
+        in_min_price,
+        in_max_price,
+        COALESCE(in_title_match_mode, 'substring'),
+        COALESCE(in_desc_match_mode, 'substring'),
+        COALESCE(in_additional_info_match_mode, 'substring'),
+        in_query,
+        in_min_days_to_deadline,
+        in_min_score,
+        in_channels,
+        in_paused_until
+    );
 
     INSERT INTO title_keywords (id_subscription, keyword) 
         SELECT currval('subscriptions_id_seq'), unnest(in_title_keywords);
@@ -63,6 +204,36 @@ BEGIN
     INSERT INTO additional_info_keywords (id_subscription, keyword) 
         SELECT currval('subscriptions_id_seq'), unnest(in_additional_info_keywords);
 
+    INSERT INTO cpv_codes (id_subscription, code) 
+        SELECT currval('subscriptions_id_seq'), unnest(in_cpv_codes);
+
+    INSERT INTO counties (id_subscription, code) 
+        SELECT currval('subscriptions_id_seq'), unnest(in_counties);
+
+    INSERT INTO included_authorities (id_subscription, cui) 
+        SELECT currval('subscriptions_id_seq'), a.cui FROM jsonb_to_recordset(in_included_authorities) AS a(cui TEXT);
+
+    INSERT INTO excluded_authorities (id_subscription, cui) 
+        SELECT currval('subscriptions_id_seq'), a.cui FROM jsonb_to_recordset(in_excluded_authorities) AS a(cui TEXT);
+
+    INSERT INTO authority_aliases (id_subscription, cui, alias) 
+        SELECT DISTINCT currval('subscriptions_id_seq'), a.cui, jsonb_array_elements_text(COALESCE(a.aliases, '[]'))
+        FROM jsonb_to_recordset(
+            COALESCE(in_included_authorities, '[]') || COALESCE(in_excluded_authorities, '[]')
+        ) AS a(cui TEXT, aliases JSONB);
+
+    INSERT INTO title_exclude_keywords (id_subscription, keyword) 
+        SELECT currval('subscriptions_id_seq'), unnest(in_title_exclude_keywords);
+
+    INSERT INTO desc_exclude_keywords (id_subscription, keyword) 
+        SELECT currval('subscriptions_id_seq'), unnest(in_desc_exclude_keywords);
+
+    INSERT INTO additional_info_exclude_keywords (id_subscription, keyword) 
+        SELECT currval('subscriptions_id_seq'), unnest(in_additional_info_exclude_keywords);
+
+    INSERT INTO procedure_types (id_subscription, procedure_type) 
+        SELECT currval('subscriptions_id_seq'), unnest(in_procedure_types);
+
     RETURN QUERY 
     SELECT * FROM get_subscriptions() AS T WHERE T.id=currval('subscriptions_id_seq');
 END;
@@ -75,7 +246,23 @@ CREATE OR REPLACE FUNCTION update_subscription(
     IN in_max_price INT,
     IN in_title_keywords TEXT[],
     IN in_desc_keywords TEXT[],
-    IN in_additional_info_keywords TEXT[]
+    IN in_additional_info_keywords TEXT[],
+    IN in_title_match_mode keyword_match_mode,
+    IN in_desc_match_mode keyword_match_mode,
+    IN in_additional_info_match_mode keyword_match_mode,
+    IN in_query TEXT,
+    IN in_cpv_codes TEXT[],
+    IN in_counties TEXT[],
+    IN in_included_authorities JSONB,
+    IN in_excluded_authorities JSONB,
+    IN in_title_exclude_keywords TEXT[],
+    IN in_desc_exclude_keywords TEXT[],
+    IN in_additional_info_exclude_keywords TEXT[],
+    IN in_min_days_to_deadline INT,
+    IN in_procedure_types procedure_type[],
+    IN in_min_score INT,
+    IN in_channels notification_channel[],
+    IN in_paused_until TIMESTAMPTZ
 ) RETURNS TABLE (
     id INT,
     id_user INT,
@@ -83,7 +270,23 @@ CREATE OR REPLACE FUNCTION update_subscription(
     max_price INT,
     title_keywords VARCHAR[],
     desc_keywords VARCHAR[],
-    additional_info_keywords VARCHAR[]
+    additional_info_keywords VARCHAR[],
+    title_match_mode keyword_match_mode,
+    desc_match_mode keyword_match_mode,
+    additional_info_match_mode keyword_match_mode,
+    query TEXT,
+    cpv_codes VARCHAR[],
+    counties VARCHAR[],
+    included_authorities JSONB,
+    excluded_authorities JSONB,
+    title_exclude_keywords VARCHAR[],
+    desc_exclude_keywords VARCHAR[],
+    additional_info_exclude_keywords VARCHAR[],
+    min_days_to_deadline INT,
+    procedure_types procedure_type[],
+    min_score INT,
+    channels notification_channel[],
+    paused_until TIMESTAMPTZ
 )
 LANGUAGE plpgsql
 AS $$
@@ -91,12 +294,29 @@ BEGIN
     UPDATE subscriptions SET 
         id_user = in_id_user,
         min_price = in_min_price,
-        max_price = in_max_price
+        max_price = in_max_price,
+        title_match_mode = COALESCE(in_title_match_mode, 'substring'),
+        desc_match_mode = COALESCE(in_desc_match_mode, 'substring'),
+        additional_info_match_mode = COALESCE(in_additional_info_match_mode, 'substring'),
+        query = in_query,
+        min_days_to_deadline = in_min_days_to_deadline,
+        min_score = in_min_score,
+        channels = in_channels,
+        paused_until = in_paused_until
     WHERE subscriptions.id = in_id;
 
     DELETE FROM title_keywords WHERE title_keywords.id_subscription = in_id;
     DELETE FROM desc_keywords WHERE desc_keywords.id_subscription = in_id;
     DELETE FROM additional_info_keywords WHERE additional_info_keywords.id_subscription = in_id;
+    DELETE FROM cpv_codes WHERE cpv_codes.id_subscription = in_id;
+    DELETE FROM counties WHERE counties.id_subscription = in_id;
+    DELETE FROM included_authorities WHERE included_authorities.id_subscription = in_id;
+    DELETE FROM excluded_authorities WHERE excluded_authorities.id_subscription = in_id;
+    DELETE FROM authority_aliases WHERE authority_aliases.id_subscription = in_id;
+    DELETE FROM title_exclude_keywords WHERE title_exclude_keywords.id_subscription = in_id;
+    DELETE FROM desc_exclude_keywords WHERE desc_exclude_keywords.id_subscription = in_id;
+    DELETE FROM additional_info_exclude_keywords WHERE additional_info_exclude_keywords.id_subscription = in_id;
+    DELETE FROM procedure_types WHERE procedure_types.id_subscription = in_id;
 
     INSERT INTO title_keywords (id_subscription, keyword) 
         SELECT in_id, unnest(in_title_keywords);
@@ -107,6 +327,36 @@ BEGIN
     INSERT INTO additional_info_keywords (id_subscription, keyword) 
         SELECT in_id, unnest(in_additional_info_keywords);
 
+    INSERT INTO cpv_codes (id_subscription, code) 
+        SELECT in_id, unnest(in_cpv_codes);
+
+    INSERT INTO counties (id_subscription, code) 
+        SELECT in_id, unnest(in_counties);
+
+    INSERT INTO included_authorities (id_subscription, cui) 
+        SELECT in_id, a.cui FROM jsonb_to_recordset(in_included_authorities) AS a(cui TEXT);
+
+    INSERT INTO excluded_authorities (id_subscription, cui) 
+        SELECT in_id, a.cui FROM jsonb_to_recordset(in_excluded_authorities) AS a(cui TEXT);
+
+    INSERT INTO authority_aliases (id_subscription, cui, alias) 
+        SELECT DISTINCT in_id, a.cui, jsonb_array_elements_text(COALESCE(a.aliases, '[]'))
+        FROM jsonb_to_recordset(
+            COALESCE(in_included_authorities, '[]') || COALESCE(in_excluded_authorities, '[]')
+        ) AS a(cui TEXT, aliases JSONB);
+
+    INSERT INTO title_exclude_keywords (id_subscription, keyword) 
+        SELECT in_id, unnest(in_title_exclude_keywords);
+
+    INSERT INTO desc_exclude_keywords (id_subscription, keyword) 
+        SELECT in_id, unnest(in_desc_exclude_keywords);
+
+    INSERT INTO additional_info_exclude_keywords (id_subscription, keyword) 
+        SELECT in_id, unnest(in_additional_info_exclude_keywords);
+
+    INSERT INTO procedure_types (id_subscription, procedure_type) 
+        SELECT in_id, unnest(in_procedure_types);
+
     RETURN QUERY 
     SELECT * FROM get_subscriptions() AS T WHERE T.id=in_id;
 END;
@@ -121,7 +371,23 @@ TABLE(
     max_price INT,
     title_keywords VARCHAR[],
     desc_keywords VARCHAR[],
-    additional_info_keywords VARCHAR[]
+    additional_info_keywords VARCHAR[],
+    title_match_mode keyword_match_mode,
+    desc_match_mode keyword_match_mode,
+    additional_info_match_mode keyword_match_mode,
+    query TEXT,
+    cpv_codes VARCHAR[],
+    counties VARCHAR[],
+    included_authorities JSONB,
+    excluded_authorities JSONB,
+    title_exclude_keywords VARCHAR[],
+    desc_exclude_keywords VARCHAR[],
+    additional_info_exclude_keywords VARCHAR[],
+    min_days_to_deadline INT,
+    procedure_types procedure_type[],
+    min_score INT,
+    channels notification_channel[],
+    paused_until TIMESTAMPTZ
 ) AS $$
 BEGIN
     RETURN QUERY
@@ -144,7 +410,73 @@ BEGIN
             THEN ARRAY(SELECT keyword FROM additional_info_keywords WHERE id_subscription = s.id)
         ELSE 
             NULL     
-        END)
+        END),
+        s.title_match_mode,
+        s.desc_match_mode,
+        s.additional_info_match_mode,
+        s.query,
+        (CASE WHEN EXISTS (SELECT 1 FROM cpv_codes WHERE id_subscription = s.id)
+            THEN ARRAY(SELECT code FROM cpv_codes WHERE id_subscription = s.id)
+        ELSE
+            NULL
+        END),
+        (CASE WHEN EXISTS (SELECT 1 FROM counties WHERE id_subscription = s.id)
+            THEN ARRAY(SELECT code FROM counties WHERE id_subscription = s.id)
+        ELSE
+            NULL
+        END),
+        (CASE WHEN EXISTS (SELECT 1 FROM included_authorities WHERE id_subscription = s.id)
+            THEN (
+                SELECT jsonb_agg(jsonb_build_object(
+                    'cui', a.cui,
+                    'aliases', ARRAY(
+                        SELECT alias FROM authority_aliases 
+                        WHERE id_subscription = s.id AND cui = a.cui
+                    )
+                ))
+                FROM included_authorities a WHERE a.id_subscription = s.id
+            )
+        ELSE
+            NULL
+        END),
+        (CASE WHEN EXISTS (SELECT 1 FROM excluded_authorities WHERE id_subscription = s.id)
+            THEN (
+                SELECT jsonb_agg(jsonb_build_object(
+                    'cui', a.cui,
+                    'aliases', ARRAY(
+                        SELECT alias FROM authority_aliases 
+                        WHERE id_subscription = s.id AND cui = a.cui
+                    )
+                ))
+                FROM excluded_authorities a WHERE a.id_subscription = s.id
+            )
+        ELSE
+            NULL
+        END),
+        (CASE WHEN EXISTS (SELECT 1 FROM title_exclude_keywords WHERE id_subscription = s.id)
+            THEN ARRAY(SELECT keyword FROM title_exclude_keywords WHERE id_subscription = s.id)
+        ELSE
+            NULL
+        END),
+        (CASE WHEN EXISTS (SELECT 1 FROM desc_exclude_keywords WHERE id_subscription = s.id)
+            THEN ARRAY(SELECT keyword FROM desc_exclude_keywords WHERE id_subscription = s.id)
+        ELSE
+            NULL
+        END),
+        (CASE WHEN EXISTS (SELECT 1 FROM additional_info_exclude_keywords WHERE id_subscription = s.id)
+            THEN ARRAY(SELECT keyword FROM additional_info_exclude_keywords WHERE id_subscription = s.id)
+        ELSE
+            NULL
+        END),
+        s.min_days_to_deadline,
+        (CASE WHEN EXISTS (SELECT 1 FROM procedure_types WHERE id_subscription = s.id)
+            THEN ARRAY(SELECT procedure_type FROM procedure_types WHERE id_subscription = s.id)
+        ELSE
+            NULL
+        END),
+        s.min_score,
+        s.channels,
+        s.paused_until
     FROM subscriptions s;
 END;
 $$ LANGUAGE plpgsql;
@@ -157,7 +489,23 @@ RETURNS TABLE (
     max_price INT,
     title_keywords VARCHAR[],
     desc_keywords VARCHAR[],
-    additional_info_keywords VARCHAR[]
+    additional_info_keywords VARCHAR[],
+    title_match_mode keyword_match_mode,
+    desc_match_mode keyword_match_mode,
+    additional_info_match_mode keyword_match_mode,
+    query TEXT,
+    cpv_codes VARCHAR[],
+    counties VARCHAR[],
+    included_authorities JSONB,
+    excluded_authorities JSONB,
+    title_exclude_keywords VARCHAR[],
+    desc_exclude_keywords VARCHAR[],
+    additional_info_exclude_keywords VARCHAR[],
+    min_days_to_deadline INT,
+    procedure_types procedure_type[],
+    min_score INT,
+    channels notification_channel[],
+    paused_until TIMESTAMPTZ
 )
 LANGUAGE plpgsql
 AS $$ 
@@ -176,12 +524,111 @@ BEGIN
     INTO additional_info_keywords
     FROM additional_info_keywords
     WHERE id_subscription = in_id;
+    
+    SELECT array_agg(code)
+    INTO cpv_codes
+    FROM cpv_codes
+    WHERE id_subscription = in_id;
+    
+    SELECT array_agg(code)
+    INTO counties
+    FROM counties
+    WHERE id_subscription = in_id;
+    
+    SELECT T.included_authorities, T.excluded_authorities
+    INTO included_authorities, excluded_authorities
+    FROM get_subscriptions() AS T
+    WHERE T.id = in_id;
+    
+    SELECT array_agg(keyword)
+    INTO title_exclude_keywords
+    FROM title_exclude_keywords
+    WHERE id_subscription = in_id;
+    
+    SELECT array_agg(keyword)
+    INTO desc_exclude_keywords
+    FROM desc_exclude_keywords
+    WHERE id_subscription = in_id;
+    
+    SELECT array_agg(keyword)
+    INTO additional_info_exclude_keywords
+    FROM additional_info_exclude_keywords
+    WHERE id_subscription = in_id;
+    
+    SELECT array_agg(procedure_type)
+    INTO procedure_types
+    FROM procedure_types
+    WHERE id_subscription = in_id;
     DELETE FROM subscriptions
 
     WHERE subscriptions.id = in_id
-    RETURNING subscriptions.id, subscriptions.id_user, subscriptions.min_price, subscriptions.max_price
-    INTO id, id_user, min_price, max_price;
+    RETURNING
+        subscriptions.id,
+        subscriptions.id_user,
+        subscriptions.min_price,
+        subscriptions.max_price,
+        subscriptions.title_match_mode,
+        subscriptions.desc_match_mode,
+        subscriptions.additional_info_match_mode,
+        subscriptions.query,
+        subscriptions.min_days_to_deadline,
+        subscriptions.min_score,
+        subscriptions.channels,
+        subscriptions.paused_until
+    INTO
+        id,
+        id_user,
+        min_price,
+        max_price,
+        title_match_mode,
+        desc_match_mode,
+        additional_info_match_mode,
+        query,
+        min_days_to_deadline,
+        min_score,
+        channels,
+        paused_until;
     
-    RETURN QUERY SELECT id, id_user, min_price, max_price, title_keywords, desc_keywords, additional_info_keywords;
+    RETURN QUERY SELECT
+        id,
+        id_user,
+        min_price,
+        max_price,
+        title_keywords,
+        desc_keywords,
+        additional_info_keywords,
+        title_match_mode,
+        desc_match_mode,
+        additional_info_match_mode,
+        query,
+        cpv_codes,
+        counties,
+        included_authorities,
+        excluded_authorities,
+        title_exclude_keywords,
+        desc_exclude_keywords,
+        additional_info_exclude_keywords,
+        min_days_to_deadline,
+        procedure_types,
+        min_score,
+        channels,
+        paused_until;
 END;
+$$;
+
+-- whether matches of the subscription are sent over the channel: it isn't paused and the channel
+-- is among its own, or else its owner's
+CREATE OR REPLACE FUNCTION subscription_notifies(
+    IN in_id_subscription INT,
+    IN in_channel notification_channel
+) RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT
+        (s.paused_until IS NULL OR s.paused_until <= NOW())
+        AND in_channel = ANY(COALESCE(s.channels, p.channels, '{email,webhook}'))
+    FROM subscriptions s
+    LEFT JOIN notification_preferences p ON p.id_user = s.id_user
+    WHERE s.id = in_id_subscription;
 $$;
\ No newline at end of file
@@ -0,0 +1,181 @@
+DROP TABLE IF EXISTS tenders CASCADE;
+
+DROP FUNCTION IF EXISTS create_tender;
+DROP FUNCTION IF EXISTS update_tender;
+DROP FUNCTION IF EXISTS delete_tender;
+
+CREATE TABLE tenders (
+    id SERIAL PRIMARY KEY,
+    notice_number VARCHAR(50) NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    description TEXT,
+    additional_info TEXT,
+    estimated_value BIGINT,
+    currency VARCHAR(3) NOT NULL DEFAULT 'RON',
+    contracting_authority VARCHAR(255),
+    cpv_code VARCHAR(10),
+    publication_date TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    submission_deadline TIMESTAMPTZ,
+    procedure_type VARCHAR(100),
+    county VARCHAR(2),
+    contracting_authority_cui VARCHAR(12),
+    clarification_deadline TIMESTAMPTZ,
+    opening_date TIMESTAMPTZ
+);
+
+CREATE OR REPLACE FUNCTION create_tender(
+    IN in_notice_number VARCHAR(50),
+    IN in_title TEXT,
+    IN in_description TEXT,
+    IN in_additional_info TEXT,
+    IN in_estimated_value BIGINT,
+    IN in_currency VARCHAR(3),
+    IN in_contracting_authority VARCHAR(255),
+    IN in_cpv_code VARCHAR(10),
+    IN in_publication_date TIMESTAMPTZ,
+    IN in_submission_deadline TIMESTAMPTZ,
+    IN in_procedure_type VARCHAR(100),
+    IN in_county VARCHAR(2),
+    IN in_contracting_authority_cui VARCHAR(12),
+    IN in_clarification_deadline TIMESTAMPTZ,
+    IN in_opening_date TIMESTAMPTZ
+) RETURNS TABLE (
+    id INT,
+    notice_number VARCHAR(50),
+    title TEXT,
+    description TEXT,
+    additional_info TEXT,
+    estimated_value BIGINT,
+    currency VARCHAR(3),
+    contracting_authority VARCHAR(255),
+    cpv_code VARCHAR(10),
+    publication_date TIMESTAMPTZ,
+    submission_deadline TIMESTAMPTZ,
+    procedure_type VARCHAR(100),
+    county VARCHAR(2),
+    contracting_authority_cui VARCHAR(12),
+    clarification_deadline TIMESTAMPTZ,
+    opening_date TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY INSERT INTO tenders (
+        notice_number,
+        title,
+        description,
+        additional_info,
+        estimated_value,
+        currency,
+        contracting_authority,
+        cpv_code,
+        publication_date,
+        submission_deadline,
+        procedure_type,
+        county,
+        contracting_authority_cui,
+        clarification_deadline,
+        opening_date
+    ) VALUES (
+        in_notice_number,
+        in_title,
+        in_description,
+        in_additional_info,
+        in_estimated_value,
+        COALESCE(in_currency, 'RON'),
+        in_contracting_authority,
+        in_cpv_code,
+        in_publication_date,
+        in_submission_deadline,
+        in_procedure_type,
+        in_county,
+        in_contracting_authority_cui,
+        in_clarification_deadline,
+        in_opening_date
+    ) RETURNING *;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION update_tender(
+    IN in_id INT,
+    IN in_notice_number VARCHAR(50),
+    IN in_title TEXT,
+    IN in_description TEXT,
+    IN in_additional_info TEXT,
+    IN in_estimated_value BIGINT,
+    IN in_currency VARCHAR(3),
+    IN in_contracting_authority VARCHAR(255),
+    IN in_cpv_code VARCHAR(10),
+    IN in_publication_date TIMESTAMPTZ,
+    IN in_submission_deadline TIMESTAMPTZ,
+    IN in_procedure_type VARCHAR(100),
+    IN in_county VARCHAR(2),
+    IN in_contracting_authority_cui VARCHAR(12),
+    IN in_clarification_deadline TIMESTAMPTZ,
+    IN in_opening_date TIMESTAMPTZ
+) RETURNS TABLE (
+    id INT,
+    notice_number VARCHAR(50),
+    title TEXT,
+    description TEXT,
+    additional_info TEXT,
+    estimated_value BIGINT,
+    currency VARCHAR(3),
+    contracting_authority VARCHAR(255),
+    cpv_code VARCHAR(10),
+    publication_date TIMESTAMPTZ,
+    submission_deadline TIMESTAMPTZ,
+    procedure_type VARCHAR(100),
+    county VARCHAR(2),
+    contracting_authority_cui VARCHAR(12),
+    clarification_deadline TIMESTAMPTZ,
+    opening_date TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY UPDATE tenders AS t SET
+        notice_number = in_notice_number,
+        title = in_title,
+        description = in_description,
+        additional_info = in_additional_info,
+        estimated_value = in_estimated_value,
+        currency = COALESCE(in_currency, t.currency),
+        contracting_authority = in_contracting_authority,
+        cpv_code = in_cpv_code,
+        publication_date = COALESCE(in_publication_date, t.publication_date),
+        submission_deadline = in_submission_deadline,
+        procedure_type = in_procedure_type,
+        county = in_county,
+        contracting_authority_cui = in_contracting_authority_cui,
+        clarification_deadline = in_clarification_deadline,
+        opening_date = in_opening_date
+    WHERE t.id = in_id RETURNING *;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION delete_tender(IN in_id INT)
+RETURNS TABLE (
+    id INT,
+    notice_number VARCHAR(50),
+    title TEXT,
+    description TEXT,
+    additional_info TEXT,
+    estimated_value BIGINT,
+    currency VARCHAR(3),
+    contracting_authority VARCHAR(255),
+    cpv_code VARCHAR(10),
+    publication_date TIMESTAMPTZ,
+    submission_deadline TIMESTAMPTZ,
+    procedure_type VARCHAR(100),
+    county VARCHAR(2),
+    contracting_authority_cui VARCHAR(12),
+    clarification_deadline TIMESTAMPTZ,
+    opening_date TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY DELETE FROM tenders WHERE tenders.id = in_id RETURNING *;
+END;
+$$;
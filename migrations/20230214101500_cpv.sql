@@ -0,0 +1,9 @@
+-- CPV 2008 vocabulary used to resolve codes to their labels, filled at startup from the
+-- vocabulary bundled in data/cpv_2008.csv. The table is kept across restarts, and loading the
+-- bundled vocabulary never overwrites labels imported later with the `import_cpv` binary.
+
+CREATE TABLE IF NOT EXISTS cpv_vocabulary (
+    code VARCHAR(10) PRIMARY KEY,
+    label_ro TEXT NOT NULL,
+    label_en TEXT NOT NULL
+);
@@ -0,0 +1,56 @@
+DROP TABLE IF EXISTS tender_lots CASCADE;
+
+DROP FUNCTION IF EXISTS create_tender_lot;
+
+CREATE TABLE tender_lots (
+    id SERIAL PRIMARY KEY,
+    id_tender INT NOT NULL REFERENCES tenders(id) ON DELETE CASCADE,
+    lot_number VARCHAR(50) NOT NULL,
+    title TEXT,
+    description TEXT,
+    estimated_value BIGINT,
+    cpv_code VARCHAR(10),
+    submission_deadline TIMESTAMPTZ,
+    UNIQUE (id_tender, lot_number)
+);
+
+CREATE OR REPLACE FUNCTION create_tender_lot(
+    IN in_id_tender INT,
+    IN in_lot_number VARCHAR(50),
+    IN in_title TEXT,
+    IN in_description TEXT,
+    IN in_estimated_value BIGINT,
+    IN in_cpv_code VARCHAR(10),
+    IN in_submission_deadline TIMESTAMPTZ
+) RETURNS TABLE (
+    id INT,
+    id_tender INT,
+    lot_number VARCHAR(50),
+    title TEXT,
+    description TEXT,
+    estimated_value BIGINT,
+    cpv_code VARCHAR(10),
+    submission_deadline TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY INSERT INTO tender_lots (
+        id_tender,
+        lot_number,
+        title,
+        description,
+        estimated_value,
+        cpv_code,
+        submission_deadline
+    ) VALUES (
+        in_id_tender,
+        in_lot_number,
+        in_title,
+        in_description,
+        in_estimated_value,
+        in_cpv_code,
+        in_submission_deadline
+    ) RETURNING *;
+END;
+$$;
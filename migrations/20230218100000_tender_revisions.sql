@@ -0,0 +1,46 @@
+DROP TABLE IF EXISTS tender_revisions CASCADE;
+
+DROP FUNCTION IF EXISTS create_tender_revision;
+
+CREATE TABLE tender_revisions (
+    id SERIAL PRIMARY KEY,
+    id_tender INT NOT NULL REFERENCES tenders(id) ON DELETE CASCADE,
+    revision INT NOT NULL,
+    changes JSONB NOT NULL,
+    material BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (id_tender, revision)
+);
+
+CREATE OR REPLACE FUNCTION create_tender_revision(
+    IN in_id_tender INT,
+    IN in_changes JSONB,
+    IN in_material BOOLEAN
+) RETURNS TABLE (
+    id INT,
+    id_tender INT,
+    revision INT,
+    changes JSONB,
+    material BOOLEAN,
+    created_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY INSERT INTO tender_revisions (
+        id_tender,
+        revision,
+        changes,
+        material
+    ) VALUES (
+        in_id_tender,
+        (
+            SELECT COALESCE(MAX(r.revision), 0) + 1
+            FROM tender_revisions r
+            WHERE r.id_tender = in_id_tender
+        ),
+        in_changes,
+        in_material
+    ) RETURNING *;
+END;
+$$;
@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS source_cursors;
+
+CREATE TABLE source_cursors (
+    source TEXT PRIMARY KEY,
+    high_water_mark TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,53 @@
+DROP TABLE IF EXISTS matches CASCADE;
+
+DROP FUNCTION IF EXISTS create_match;
+
+CREATE TABLE matches (
+    id SERIAL PRIMARY KEY,
+    id_subscription INT NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    id_tender INT NOT NULL REFERENCES tenders(id) ON DELETE CASCADE,
+    matched_fields VARCHAR(50)[] NOT NULL,
+    score INT,
+    explanation JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    notified_at TIMESTAMPTZ,
+    UNIQUE (id_subscription, id_tender)
+);
+
+-- returns nothing when the subscription was already matched to the tender
+CREATE OR REPLACE FUNCTION create_match(
+    IN in_id_subscription INT,
+    IN in_id_tender INT,
+    IN in_matched_fields VARCHAR(50)[],
+    IN in_score INT,
+    IN in_explanation JSONB
+) RETURNS TABLE (
+    id INT,
+    id_subscription INT,
+    id_tender INT,
+    matched_fields VARCHAR(50)[],
+    score INT,
+    explanation JSONB,
+    created_at TIMESTAMPTZ,
+    notified_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY INSERT INTO matches (
+        id_subscription,
+        id_tender,
+        matched_fields,
+        score,
+        explanation
+    ) VALUES (
+        in_id_subscription,
+        in_id_tender,
+        in_matched_fields,
+        in_score,
+        in_explanation
+    )
+    ON CONFLICT ON CONSTRAINT matches_id_subscription_id_tender_key DO NOTHING
+    RETURNING *;
+END;
+$$;
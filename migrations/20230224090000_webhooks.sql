@@ -0,0 +1,110 @@
+DROP TABLE IF EXISTS webhook_deliveries CASCADE;
+DROP TABLE IF EXISTS webhooks CASCADE;
+
+DROP FUNCTION IF EXISTS create_webhook;
+DROP FUNCTION IF EXISTS update_webhook;
+
+DROP TYPE IF EXISTS webhook_event;
+DROP TYPE IF EXISTS delivery_status;
+
+CREATE TYPE webhook_event AS ENUM (
+    'match_created',
+    'match_updated'
+);
+
+CREATE TYPE delivery_status AS ENUM (
+    'pending',
+    'delivered',
+    'failed'
+);
+
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY,
+    id_user INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    secret VARCHAR(255) NOT NULL,
+    events webhook_event[] NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    consecutive_failures INT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE webhook_deliveries (
+    id SERIAL PRIMARY KEY,
+    id_webhook INT NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event webhook_event NOT NULL,
+    payload JSONB NOT NULL,
+    status delivery_status NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_status_code INT,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX webhook_deliveries_pending_idx
+    ON webhook_deliveries (next_attempt_at)
+    WHERE status = 'pending';
+
+CREATE OR REPLACE FUNCTION create_webhook(
+    IN in_id_user INT,
+    IN in_url TEXT,
+    IN in_secret VARCHAR(255),
+    IN in_events webhook_event[]
+) RETURNS TABLE (
+    id INT,
+    id_user INT,
+    url TEXT,
+    secret VARCHAR(255),
+    events webhook_event[],
+    enabled BOOLEAN,
+    consecutive_failures INT,
+    created_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY INSERT INTO webhooks (
+        id_user,
+        url,
+        secret,
+        events
+    ) VALUES (
+        in_id_user,
+        in_url,
+        in_secret,
+        in_events
+    ) RETURNING *;
+END;
+$$;
+
+-- updating a webhook also re-enables it, and keeps its secret unless a new one is given
+CREATE OR REPLACE FUNCTION update_webhook(
+    IN in_id INT,
+    IN in_url TEXT,
+    IN in_secret VARCHAR(255),
+    IN in_events webhook_event[]
+) RETURNS TABLE (
+    id INT,
+    id_user INT,
+    url TEXT,
+    secret VARCHAR(255),
+    events webhook_event[],
+    enabled BOOLEAN,
+    consecutive_failures INT,
+    created_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY UPDATE webhooks SET
+        url = in_url,
+        secret = COALESCE(in_secret, webhooks.secret),
+        events = in_events,
+        enabled = TRUE,
+        consecutive_failures = 0
+    WHERE webhooks.id = in_id
+    RETURNING *;
+END;
+$$;
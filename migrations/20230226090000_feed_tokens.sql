@@ -0,0 +1,35 @@
+DROP TABLE IF EXISTS feed_tokens CASCADE;
+
+DROP FUNCTION IF EXISTS rotate_feed_token;
+
+CREATE TABLE feed_tokens (
+    id_user INT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    token VARCHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- a user has a single feed token, replaced by every rotation
+CREATE OR REPLACE FUNCTION rotate_feed_token(
+    IN in_id_user INT,
+    IN in_token VARCHAR(64)
+) RETURNS TABLE (
+    id_user INT,
+    token VARCHAR(64),
+    created_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY INSERT INTO feed_tokens (
+        id_user,
+        token
+    ) VALUES (
+        in_id_user,
+        in_token
+    )
+    ON CONFLICT ON CONSTRAINT feed_tokens_pkey DO UPDATE SET
+        token = EXCLUDED.token,
+        created_at = NOW()
+    RETURNING *;
+END;
+$$;
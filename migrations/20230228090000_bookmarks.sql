@@ -0,0 +1,35 @@
+DROP TABLE IF EXISTS bookmarks CASCADE;
+
+DROP FUNCTION IF EXISTS create_bookmark;
+
+CREATE TABLE bookmarks (
+    id_user INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    id_tender INT NOT NULL REFERENCES tenders(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (id_user, id_tender)
+);
+
+-- bookmarking a tender twice returns the existing bookmark
+CREATE OR REPLACE FUNCTION create_bookmark(
+    IN in_id_user INT,
+    IN in_id_tender INT
+) RETURNS TABLE (
+    id_user INT,
+    id_tender INT,
+    created_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY INSERT INTO bookmarks (
+        id_user,
+        id_tender
+    ) VALUES (
+        in_id_user,
+        in_id_tender
+    )
+    ON CONFLICT ON CONSTRAINT bookmarks_pkey DO UPDATE SET
+        created_at = bookmarks.created_at
+    RETURNING *;
+END;
+$$;
@@ -0,0 +1,47 @@
+//! Loads the full CPV 2008 vocabulary from the XML file published by the EU:
+//!
+//! ```text
+//! DATABASE_URL=postgres://.. import_cpv <cpv_2008.xml>
+//! ```
+//!
+//! Codes already in the vocabulary get their labels replaced, so the import can be rerun. With
+//! `--csv`, the vocabulary is written to stdout instead, as the CSV bundled with the service:
+//!
+//! ```text
+//! import_cpv --csv <cpv_2008.xml> > data/cpv_2008.csv
+//! ```
+
+use anyhow::{Context, Error, Result};
+use seap_subscription_api::{cpv, models::cpv as cpv_model};
+use sqlx::PgPool;
+use std::{env, fs::File, io, io::BufReader};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let csv = args.first().is_some_and(|arg| arg == "--csv");
+
+    if csv {
+        args.remove(0);
+    }
+
+    let path = args
+        .first()
+        .ok_or_else(|| Error::msg("Usage: import_cpv [--csv] <cpv_2008.xml>"))?;
+
+    let file = File::open(path).with_context(|| format!("Couldn't open {path}."))?;
+    let codes = cpv::read_vocabulary(BufReader::new(file))?;
+
+    if csv {
+        return cpv::write_csv(io::stdout().lock(), &codes);
+    }
+
+    let url = env::var("DATABASE_URL").context("DATABASE_URL isn't set.")?;
+    let pool = PgPool::connect(&url).await?;
+
+    let written = cpv_model::upsert_many(&pool, &codes).await?;
+
+    println!("Imported {written} CPV codes.");
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Backfills tenders from a CSV dump, the command line equivalent of `POST /tenders/import`:
+//!
+//! ```text
+//! DATABASE_URL=postgres://.. import_tenders <file.csv> [mapping.json]
+//! ```
+//!
+//! The optional mapping file holds the same settings as the query of the endpoint, e.g.
+//! `{"delimiter": ";", "title": "Titlu"}`.
+
+use anyhow::{Context, Error, Result};
+use seap_subscription_api::ingest::open_data::{self, Mapping};
+use sqlx::PgPool;
+use std::{env, fs::File, io::BufReader};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+
+    let path = args
+        .next()
+        .ok_or_else(|| Error::msg("Usage: import_tenders <file.csv> [mapping.json]"))?;
+
+    let mapping: Mapping = match args.next() {
+        Some(mapping) => serde_json::from_reader(File::open(&mapping)?)
+            .with_context(|| format!("Invalid mapping file: {mapping}."))?,
+        None => Mapping::default(),
+    };
+
+    let url = env::var("DATABASE_URL").context("DATABASE_URL isn't set.")?;
+    let pool = PgPool::connect(&url).await?;
+
+    let file = File::open(&path).with_context(|| format!("Couldn't open {path}."))?;
+    let report = open_data::import(&pool, BufReader::new(file), mapping).await?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
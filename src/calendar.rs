@@ -0,0 +1,423 @@
+//! iCalendar (RFC 5545) export of the dates of the tenders a user follows.
+//!
+//! Every tender the user was matched to or bookmarked contributes an event for each of its
+//! submission deadline, clarification deadline and opening date. An event's UID depends only
+//! on the tender's notice number, which outlives its id, and the kind of date, and its
+//! `SEQUENCE` counts the tender's material revisions, so calendar clients move the event when a
+//! notice is revised instead of adding a second one. Like feeds, calendars are authenticated by
+//! the user's feed token.
+
+use crate::{
+    feed,
+    models::{revision, tender, Tender},
+    notify::digest,
+};
+use anyhow::Result;
+use sqlx::PgPool;
+use time::{
+    format_description::FormatItem, macros::format_description, Duration, OffsetDateTime, UtcOffset,
+};
+
+/// Events that passed longer ago than this are left out.
+const HISTORY: Duration = Duration::days(30);
+
+const UTC: &[FormatItem<'_>] = format_description!("[year][month][day]T[hour][minute][second]Z");
+
+/// Octets in a content line before it is folded.
+const LINE_LEN: usize = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Submission,
+    Clarification,
+    Opening,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 3] = [Kind::Submission, Kind::Clarification, Kind::Opening];
+
+    fn slug(self) -> &'static str {
+        match self {
+            Kind::Submission => "submission",
+            Kind::Clarification => "clarification",
+            Kind::Opening => "opening",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Submission => "Submission deadline",
+            Kind::Clarification => "Clarification deadline",
+            Kind::Opening => "Opening of tenders",
+        }
+    }
+
+    fn date(self, tender: &Tender) -> Option<OffsetDateTime> {
+        match self {
+            Kind::Submission => tender.submission_deadline,
+            Kind::Clarification => tender.clarification_deadline,
+            Kind::Opening => tender.opening_date,
+        }
+    }
+
+    /// Deadlines are reminded of a day ahead.
+    fn is_deadline(self) -> bool {
+        matches!(self, Kind::Submission | Kind::Clarification)
+    }
+}
+
+/// A followed tender, with how many times and when it was last revised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub tender: Tender,
+    /// Material revisions of the tender.
+    pub sequence: usize,
+    /// When the tender was last revised, or published if it never was.
+    pub modified: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calendar {
+    pub entries: Vec<Entry>,
+}
+
+/// The calendar of the tenders the user follows, leaving out those whose dates all passed.
+pub async fn load(pool: &PgPool, id_user: usize) -> Result<Calendar> {
+    let since = OffsetDateTime::now_utc() - HISTORY;
+
+    let mut entries = Vec::new();
+
+    for tender in tender::get_followed_by_user(pool, id_user, since).await? {
+        let revisions = revision::get_all_of_tender(pool, tender.id.try_into()?).await?;
+
+        entries.push(Entry {
+            sequence: revisions.iter().filter(|r| r.material).count(),
+            modified: revisions
+                .iter()
+                .map(|r| r.created_at)
+                .max()
+                .unwrap_or(tender.publication_date),
+            tender,
+        });
+    }
+
+    Ok(Calendar { entries })
+}
+
+impl Calendar {
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".into(),
+            "PRODID:-//SEAP Subscription API//Deadlines//EN".into(),
+            "CALSCALE:GREGORIAN".into(),
+            "METHOD:PUBLISH".into(),
+            "X-WR-CALNAME:Tender deadlines".into(),
+        ];
+
+        for entry in &self.entries {
+            for kind in Kind::ALL {
+                if let Some(date) = kind.date(&entry.tender) {
+                    lines.extend(event(entry, kind, date));
+                }
+            }
+        }
+
+        lines.push("END:VCALENDAR".into());
+
+        lines.iter().map(|line| fold(line) + "\r\n").collect()
+    }
+}
+
+fn event(entry: &Entry, kind: Kind, date: OffsetDateTime) -> Vec<String> {
+    let tender = &entry.tender;
+    let summary = escape(&format!("{}: {}", kind.label(), tender.title));
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!(
+            "UID:tender-{}-{}@seap-subscription-api",
+            escape(&tender.notice_number),
+            kind.slug()
+        ),
+        format!("DTSTAMP:{}", utc(entry.modified)),
+        format!("LAST-MODIFIED:{}", utc(entry.modified)),
+        format!("SEQUENCE:{}", entry.sequence),
+        format!("DTSTART:{}", utc(date)),
+        format!("SUMMARY:{summary}"),
+        format!("DESCRIPTION:{}", escape(&description(tender))),
+        format!("URL:{}", feed::notice_url(tender)),
+        "STATUS:CONFIRMED".into(),
+        "TRANSP:TRANSPARENT".into(),
+    ];
+
+    if kind.is_deadline() {
+        lines.extend([
+            "BEGIN:VALARM".into(),
+            "ACTION:DISPLAY".into(),
+            "TRIGGER:-P1D".into(),
+            format!("DESCRIPTION:{summary}"),
+            "END:VALARM".into(),
+        ]);
+    }
+
+    lines.push("END:VEVENT".into());
+
+    lines
+}
+
+fn description(tender: &Tender) -> String {
+    let mut lines = vec![format!("Notice number: {}", tender.notice_number)];
+
+    if let Some(authority) = &tender.contracting_authority {
+        lines.push(format!("Contracting authority: {authority}"));
+    }
+
+    if let Some(value) = tender.estimated_value {
+        lines.push(format!(
+            "Estimated value: {} {}",
+            digest::amount(value),
+            tender.currency
+        ));
+    }
+
+    for kind in Kind::ALL {
+        if let Some(date) = kind.date(tender) {
+            lines.push(format!("{}: {}", kind.label(), digest::datetime(date)));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn utc(datetime: OffsetDateTime) -> String {
+    datetime
+        .to_offset(UtcOffset::UTC)
+        .format(UTC)
+        .unwrap_or_default()
+}
+
+/// Escapes a TEXT value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Splits a content line longer than 75 octets into a line and continuation lines starting
+/// with a space, without splitting a character.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut len = 0;
+
+    for c in line.chars() {
+        if len + c.len_utf8() > LINE_LEN {
+            folded.push_str("\r\n ");
+            // the leading space counts towards the continuation line
+            len = 1;
+        }
+
+        folded.push(c);
+        len += c.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        calendar::{escape, fold, load, Calendar, Entry},
+        models::{load_fixtures, tender, Tender},
+    };
+    use anyhow::Result;
+    use sqlx::{query, PgPool};
+    use time::macros::datetime;
+
+    fn tender() -> Tender {
+        Tender {
+            title: "Furnizare calculatoare, imprimante; scanere".into(),
+            description: None,
+            cpv_code: None,
+            submission_deadline: Some(datetime!(2023-03-01 17:00 +02:00)),
+            procedure_type: None,
+            county: None,
+            contracting_authority_cui: None,
+            clarification_deadline: Some(datetime!(2023-02-20 12:00 UTC)),
+            ..tender::sample()
+        }
+    }
+
+    /// The unfolded lines of the events with the UID.
+    fn event(ics: &str, uid: &str) -> Vec<String> {
+        let unfolded = ics.replace("\r\n ", "");
+
+        let lines: Vec<&str> = unfolded.split("\r\n").collect();
+        let start = lines
+            .iter()
+            .position(|line| *line == format!("UID:{uid}"))
+            .unwrap_or_else(|| panic!("no event {uid}"));
+        let end = start
+            + lines[start..]
+                .iter()
+                .position(|line| *line == "END:VEVENT")
+                .unwrap();
+
+        lines[start..end]
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_render() {
+        let calendar = Calendar {
+            entries: vec![Entry {
+                tender: tender(),
+                sequence: 2,
+                modified: datetime!(2023-02-15 08:30 UTC),
+            }],
+        };
+
+        let ics = calendar.render();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics
+            .split("\r\n")
+            .all(|line| line.len() <= 75 && !line.contains('\n')));
+
+        {
+            let lines = event(&ics, "tender-CN1052000-submission@seap-subscription-api");
+
+            assert!(lines.contains(&"DTSTART:20230301T150000Z".to_string()));
+            assert!(lines.contains(&"DTSTAMP:20230215T083000Z".to_string()));
+            assert!(lines.contains(&"SEQUENCE:2".to_string()));
+            assert!(lines.contains(
+                &"SUMMARY:Submission deadline: Furnizare calculatoare\\, imprimante\\; scanere"
+                    .to_string()
+            ));
+            assert!(lines.contains(&"TRIGGER:-P1D".to_string()));
+            assert!(lines
+                .iter()
+                .any(|line| line.starts_with("URL:") && line.contains("CN1052000")));
+
+            let description = lines
+                .iter()
+                .find(|line| line.starts_with("DESCRIPTION:Notice number"))
+                .unwrap();
+
+            assert!(description.contains("\\nEstimated value: 250.000 RON\\n"));
+            assert!(description.contains("Clarification deadline: 2023-02-20 12:00 UTC"));
+        }
+
+        {
+            let lines = event(&ics, "tender-CN1052000-clarification@seap-subscription-api");
+
+            assert!(lines.contains(&"DTSTART:20230220T120000Z".to_string()));
+        }
+
+        assert!(!ics.contains("tender-CN1052000-opening"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\\b;c,d\r\ne"), "a\\\\b\\;c\\,d\\ne".to_string());
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("short"), "short");
+
+        {
+            let line = "x".repeat(160);
+            let folded = fold(&line);
+            let parts: Vec<&str> = folded.split("\r\n").collect();
+
+            assert_eq!(parts.len(), 3);
+            assert_eq!(parts[0].len(), 75);
+            assert_eq!(parts[1].len(), 75);
+            assert!(parts[1].starts_with(' '));
+            assert_eq!(folded.replace("\r\n ", ""), line);
+        }
+
+        {
+            // two-octet characters are never split
+            let line = "ș".repeat(50);
+            let folded = fold(&line);
+
+            assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+            assert_eq!(folded.replace("\r\n ", ""), line);
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_load(pool: PgPool) -> Result<()> {
+        load_fixtures(&pool).await?;
+
+        // move the fixtures' dates into the future so they aren't left out as past events
+        query!(
+            r#"UPDATE tenders SET
+                submission_deadline = submission_deadline + (NOW() - '2023-01-01Z'),
+                clarification_deadline = clarification_deadline + (NOW() - '2023-01-01Z'),
+                opening_date = opening_date + (NOW() - '2023-01-01Z')"#
+        )
+        .execute(&pool)
+        .await?;
+
+        query!(
+            r#"INSERT INTO tender_revisions (id_tender, revision, changes, material, created_at)
+                VALUES (1, 1, '[]', TRUE, '2023-02-11 10:00Z'), (1, 2, '[]', FALSE, '2023-02-12 10:00Z')"#
+        )
+        .execute(&pool)
+        .await?;
+
+        {
+            let calendar = load(&pool, 1).await?;
+
+            let tenders: Vec<i32> = calendar.entries.iter().map(|e| e.tender.id).collect();
+
+            assert_eq!(tenders, vec![1, 2]);
+            assert_eq!(calendar.entries[0].sequence, 1);
+            assert_eq!(
+                calendar.entries[0].modified,
+                datetime!(2023-02-12 10:00 UTC)
+            );
+            assert_eq!(calendar.entries[1].sequence, 0);
+            assert_eq!(
+                calendar.entries[1].modified,
+                calendar.entries[1].tender.publication_date
+            );
+
+            // submission, clarification and opening of 1, submission of 2
+            assert_eq!(calendar.render().matches("BEGIN:VEVENT").count(), 4);
+        }
+
+        {
+            query!(
+                "UPDATE tenders SET submission_deadline = NOW() - INTERVAL '31 days' WHERE id = 2"
+            )
+            .execute(&pool)
+            .await?;
+
+            let calendar = load(&pool, 1).await?;
+
+            assert_eq!(calendar.entries.len(), 1);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+//! Romanian counties (județe) and the municipality of Bucharest.
+//!
+//! Counties are identified by their vehicle registration code (`CJ`, `B`, ...), which is also
+//! how SEAP abbreviates them. The SIRUTA and NUTS 3 codes are kept alongside for imports that
+//! only carry those (TED notices locate everything by NUTS).
+
+use crate::matching::normalize::normalize;
+use anyhow::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct County {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub siruta: u16,
+    pub nuts: &'static str,
+}
+
+const fn county(code: &'static str, name: &'static str, siruta: u16, nuts: &'static str) -> County {
+    County {
+        code,
+        name,
+        siruta,
+        nuts,
+    }
+}
+
+pub const COUNTIES: [County; 42] = [
+    county("AB", "Alba", 1, "RO121"),
+    county("AR", "Arad", 2, "RO421"),
+    county("AG", "Argeș", 3, "RO311"),
+    county("BC", "Bacău", 4, "RO211"),
+    county("BH", "Bihor", 5, "RO111"),
+    county("BN", "Bistrița-Năsăud", 6, "RO112"),
+    county("BT", "Botoșani", 7, "RO212"),
+    county("BV", "Brașov", 8, "RO122"),
+    county("BR", "Brăila", 9, "RO221"),
+    county("BZ", "Buzău", 10, "RO222"),
+    county("CS", "Caraș-Severin", 11, "RO422"),
+    county("CJ", "Cluj", 12, "RO113"),
+    county("CT", "Constanța", 13, "RO223"),
+    county("CV", "Covasna", 14, "RO123"),
+    county("DB", "Dâmbovița", 15, "RO313"),
+    county("DJ", "Dolj", 16, "RO411"),
+    county("GL", "Galați", 17, "RO224"),
+    county("GJ", "Gorj", 18, "RO412"),
+    county("HR", "Harghita", 19, "RO124"),
+    county("HD", "Hunedoara", 20, "RO423"),
+    county("IL", "Ialomița", 21, "RO315"),
+    county("IS", "Iași", 22, "RO213"),
+    county("IF", "Ilfov", 23, "RO322"),
+    county("MM", "Maramureș", 24, "RO114"),
+    county("MH", "Mehedinți", 25, "RO413"),
+    county("MS", "Mureș", 26, "RO125"),
+    county("NT", "Neamț", 27, "RO214"),
+    county("OT", "Olt", 28, "RO414"),
+    county("PH", "Prahova", 29, "RO316"),
+    county("SM", "Satu Mare", 30, "RO115"),
+    county("SJ", "Sălaj", 31, "RO116"),
+    county("SB", "Sibiu", 32, "RO126"),
+    county("SV", "Suceava", 33, "RO215"),
+    county("TR", "Teleorman", 34, "RO317"),
+    county("TM", "Timiș", 35, "RO424"),
+    county("TL", "Tulcea", 36, "RO225"),
+    county("VS", "Vaslui", 37, "RO216"),
+    county("VL", "Vâlcea", 38, "RO415"),
+    county("VN", "Vrancea", 39, "RO226"),
+    county("B", "București", 40, "RO321"),
+    county("CL", "Călărași", 51, "RO312"),
+    county("GR", "Giurgiu", 52, "RO314"),
+];
+
+/// Looks a county up by its code or its name, ignoring case and diacritics.
+pub fn find(county: &str) -> Option<&'static County> {
+    let county = normalize(county);
+
+    COUNTIES
+        .iter()
+        .find(|c| c.code.eq_ignore_ascii_case(&county) || normalize(c.name) == county)
+}
+
+pub fn find_by_siruta(siruta: u16) -> Option<&'static County> {
+    COUNTIES.iter().find(|c| c.siruta == siruta)
+}
+
+/// Looks a county up by its NUTS 3 code (`RO113`), also accepting codes of finer levels.
+pub fn find_by_nuts(nuts: &str) -> Option<&'static County> {
+    let nuts = nuts.trim().to_ascii_uppercase();
+
+    COUNTIES.iter().find(|c| nuts.starts_with(c.nuts))
+}
+
+pub fn validate(counties: &[String]) -> Result<()> {
+    match counties.iter().find(|county| find(county).is_none()) {
+        Some(county) => Err(Error::msg(format!("Unknown county: {county}."))),
+        None => Ok(()),
+    }
+}
+
+/// Replaces every county with its canonical code, dropping unknown ones and duplicates.
+pub fn canonicalize(counties: Option<&[String]>) -> Option<Vec<String>> {
+    let mut codes: Vec<String> = Vec::new();
+
+    for county in counties? {
+        if let Some(county) = find(county) {
+            if !codes.iter().any(|code| code == county.code) {
+                codes.push(county.code.to_string());
+            }
+        }
+    }
+
+    Some(codes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::counties::{canonicalize, find, find_by_nuts, find_by_siruta, validate, COUNTIES};
+
+    #[test]
+    fn test_codes_are_unique() {
+        for (i, county) in COUNTIES.iter().enumerate() {
+            assert!(COUNTIES[i + 1..].iter().all(|c| c.code != county.code));
+            assert!(COUNTIES[i + 1..].iter().all(|c| c.siruta != county.siruta));
+            assert!(COUNTIES[i + 1..].iter().all(|c| c.nuts != county.nuts));
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find("CJ").map(|c| c.name), Some("Cluj"));
+        assert_eq!(find("cj").map(|c| c.name), Some("Cluj"));
+        assert_eq!(find("B").map(|c| c.name), Some("București"));
+        assert_eq!(find("Bucuresti").map(|c| c.code), Some("B"));
+        assert_eq!(find("BISTRIȚA-NĂSĂUD").map(|c| c.code), Some("BN"));
+        assert_eq!(find(" Satu  Mare ").map(|c| c.code), Some("SM"));
+        assert_eq!(find("XX"), None);
+        assert_eq!(find(""), None);
+    }
+
+    #[test]
+    fn test_find_by_siruta() {
+        assert_eq!(find_by_siruta(12).map(|c| c.code), Some("CJ"));
+        assert_eq!(find_by_siruta(51).map(|c| c.code), Some("CL"));
+        assert_eq!(find_by_siruta(41), None);
+    }
+
+    #[test]
+    fn test_find_by_nuts() {
+        assert_eq!(find_by_nuts("RO113").map(|c| c.code), Some("CJ"));
+        assert_eq!(find_by_nuts("ro321").map(|c| c.code), Some("B"));
+        assert_eq!(find_by_nuts("RO1"), None);
+        assert_eq!(find_by_nuts("RO"), None);
+        assert_eq!(find_by_nuts("HU110"), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&["CJ".into(), "Iași".into()]).is_ok());
+
+        let err = validate(&["CJ".into(), "Transilvania".into()]).unwrap_err();
+
+        assert_eq!(err.to_string(), "Unknown county: Transilvania.");
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let counties = vec!["cj".into(), "Cluj".into(), "Bucuresti".into()];
+
+        assert_eq!(
+            canonicalize(Some(&counties)),
+            Some(vec!["CJ".to_string(), "B".to_string()])
+        );
+        assert_eq!(canonicalize(None), None);
+    }
+}
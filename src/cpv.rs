@@ -0,0 +1,368 @@
+//! Common Procurement Vocabulary (CPV 2008) codes.
+//!
+//! A code is written as eight digits, a dash and a check digit (`30213100-6`). The digits are
+//! hierarchical: trailing zeros widen the code to its group, class or division, so
+//! `30200000-1` covers every code starting with `302`.
+//!
+//! The vocabulary itself is published by the EU as an XML file listing every code with its label
+//! in each official language, which [`read_vocabulary`] reads. The service bundles it as a CSV
+//! of codes with their Romanian and English labels, see [`bundled`], written from that file by
+//! the `import_cpv` binary.
+
+use crate::models::CpvCode;
+use anyhow::{Context, Error, Result};
+use quick_xml::{events::Event, Reader};
+use std::io::{BufRead, Read, Write};
+
+const BUNDLED: &str = include_str!("../data/cpv_2008.csv");
+
+const CHECK_WEIGHTS: [u32; 8] = [3, 7, 1, 3, 7, 1, 3, 7];
+
+pub fn check_digit(digits: &str) -> Option<u32> {
+    if digits.len() != 8 {
+        return None;
+    }
+
+    let mut sum = 0;
+
+    for (c, weight) in digits.chars().zip(CHECK_WEIGHTS) {
+        sum += c.to_digit(10)? * weight;
+    }
+
+    Some(sum % 10)
+}
+
+pub fn is_valid(code: &str) -> bool {
+    let Some((digits, check)) = code.split_once('-') else {
+        return false;
+    };
+
+    let mut check = check.chars();
+
+    match (check.next().and_then(|c| c.to_digit(10)), check.next()) {
+        (Some(check), None) => check_digit(digits) == Some(check),
+        _ => false,
+    }
+}
+
+pub fn validate(codes: &[String]) -> Result<()> {
+    match codes.iter().find(|code| !is_valid(code)) {
+        Some(code) => Err(Error::msg(format!("Invalid CPV code: {code}."))),
+        None => Ok(()),
+    }
+}
+
+/// The significant digits of a code, i.e. its digits without the trailing zeros that only
+/// pad it to eight digits. Divisions always keep their two digits.
+pub fn prefix(code: &str) -> &str {
+    let digits = digits(code);
+    let significant = digits.trim_end_matches('0');
+
+    &digits[..significant.len().max(2).min(digits.len())]
+}
+
+/// Whether `code` is `ancestor` itself or one of its descendants.
+pub fn is_within(code: &str, ancestor: &str) -> bool {
+    digits(code).starts_with(prefix(ancestor))
+}
+
+/// The code followed by its class, group and division, all with their check digits.
+pub fn ancestors(code: &str) -> Vec<String> {
+    let prefix = prefix(code);
+    let mut ancestors = Vec::new();
+
+    for len in (2..=prefix.len()).rev() {
+        let digits = format!("{:0<8}", &prefix[..len]);
+
+        if let Some(check) = check_digit(&digits) {
+            let code = format!("{digits}-{check}");
+
+            if !ancestors.contains(&code) {
+                ancestors.push(code);
+            }
+        }
+    }
+
+    ancestors
+}
+
+fn digits(code: &str) -> &str {
+    code.split('-').next().unwrap_or_default().trim()
+}
+
+/// The vocabulary bundled with the service, loaded into the database at startup.
+pub fn bundled() -> Result<Vec<CpvCode>> {
+    read_csv(BUNDLED.as_bytes())
+}
+
+/// Reads codes from a CSV with a `code,label_ro,label_en` header.
+pub fn read_csv(reader: impl Read) -> Result<Vec<CpvCode>> {
+    let mut codes = Vec::new();
+
+    for row in csv::Reader::from_reader(reader).deserialize() {
+        let code: CpvCode = row.context("Invalid CPV vocabulary CSV.")?;
+
+        if !is_valid(&code.code) {
+            return Err(Error::msg(format!("Invalid CPV code: {}.", code.code)));
+        }
+
+        codes.push(code);
+    }
+
+    Ok(codes)
+}
+
+pub fn write_csv(writer: impl Write, codes: &[CpvCode]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+
+    for code in codes {
+        writer.serialize(code)?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads the codes and their Romanian and English labels from the official CPV 2008 XML file,
+/// made of `<CPV CODE="..">` elements each holding a `<TEXT LANG="..">` per language.
+pub fn read_vocabulary(reader: impl BufRead) -> Result<Vec<CpvCode>> {
+    let mut reader = Reader::from_reader(reader);
+    reader.trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut codes = Vec::new();
+    let mut current: Option<CpvCode> = None;
+    let mut lang: Option<String> = None;
+
+    loop {
+        match reader
+            .read_event_into(&mut buf)
+            .context("Invalid CPV vocabulary XML.")?
+        {
+            Event::Start(e) if e.local_name().as_ref() == b"CPV" => {
+                let code = attribute(&e, b"CODE")?
+                    .ok_or_else(|| Error::msg("CPV element without a CODE."))?;
+
+                if !is_valid(&code) {
+                    return Err(Error::msg(format!("Invalid CPV code: {code}.")));
+                }
+
+                current = Some(CpvCode {
+                    code,
+                    label_ro: String::new(),
+                    label_en: String::new(),
+                });
+            }
+            Event::Start(e) if e.local_name().as_ref() == b"TEXT" => {
+                lang = attribute(&e, b"LANG")?;
+            }
+            Event::Text(e) => {
+                let text = e.unescape()?;
+
+                match (current.as_mut(), lang.as_deref()) {
+                    (Some(code), Some("RO")) => code.label_ro.push_str(&text),
+                    (Some(code), Some("EN")) => code.label_en.push_str(&text),
+                    _ => {}
+                }
+            }
+            Event::End(e) if e.local_name().as_ref() == b"TEXT" => lang = None,
+            Event::End(e) if e.local_name().as_ref() == b"CPV" => {
+                if let Some(code) = current.take() {
+                    if code.label_ro.is_empty() || code.label_en.is_empty() {
+                        return Err(Error::msg(format!(
+                            "CPV code {} lacks a Romanian or English label.",
+                            code.code
+                        )));
+                    }
+
+                    codes.push(code);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    Ok(codes)
+}
+
+fn attribute(element: &quick_xml::events::BytesStart, name: &[u8]) -> Result<Option<String>> {
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+
+        if attribute.key.local_name().as_ref() == name {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        cpv::{
+            ancestors, bundled, check_digit, is_valid, is_within, prefix, read_csv,
+            read_vocabulary, validate, write_csv,
+        },
+        models::CpvCode,
+    };
+
+    #[test]
+    fn test_check_digit() {
+        assert_eq!(check_digit("30213100"), Some(6));
+        assert_eq!(check_digit("45233120"), Some(6));
+        assert_eq!(check_digit("30200000"), Some(1));
+        assert_eq!(check_digit("72000000"), Some(5));
+        assert_eq!(check_digit("39130000"), Some(2));
+        assert_eq!(check_digit("3021310"), None);
+        assert_eq!(check_digit("3021310a"), None);
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid("30213100-6"));
+        assert!(is_valid("45000000-7"));
+        assert!(is_valid("09000000-3"));
+        assert!(!is_valid("30213100-5"));
+        assert!(!is_valid("30213100"));
+        assert!(!is_valid("30213100-66"));
+        assert!(!is_valid("3021310-6"));
+        assert!(!is_valid("30213100-x"));
+        assert!(!is_valid(""));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&["30200000-1".into(), "45000000-7".into()]).is_ok());
+
+        let err = validate(&["30200000-1".into(), "45000000-1".into()]).unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid CPV code: 45000000-1.");
+    }
+
+    #[test]
+    fn test_prefix() {
+        assert_eq!(prefix("30200000-1"), "302");
+        assert_eq!(prefix("30000000-9"), "30");
+        assert_eq!(prefix("03000000-1"), "03");
+        assert_eq!(prefix("45233120-6"), "4523312");
+        assert_eq!(prefix("30213100"), "302131");
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(is_within("30213100-6", "30200000-1"));
+        assert!(is_within("30213100-6", "30000000-9"));
+        assert!(is_within("30213100-6", "30213100-6"));
+        assert!(is_within("30213100", "30200000-1"));
+        assert!(!is_within("30200000-1", "30213100-6"));
+        assert!(!is_within("31000000-6", "30000000-9"));
+        assert!(!is_within("03100000-2", "30000000-9"));
+    }
+
+    #[test]
+    fn test_ancestors() {
+        assert_eq!(
+            ancestors("30213100-6"),
+            vec![
+                "30213100-6",
+                "30213000-5",
+                "30210000-4",
+                "30200000-1",
+                "30000000-9"
+            ]
+        );
+        assert_eq!(ancestors("45000000-7"), vec!["45000000-7"]);
+    }
+
+    #[test]
+    fn test_read_vocabulary() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<CPV_CODE>
+<CPV CODE="30213100-6">
+<TEXT LANG="BG">Преносими компютри</TEXT>
+<TEXT LANG="EN">Portable computers</TEXT>
+<TEXT LANG="RO">Computere portabile</TEXT>
+</CPV>
+<CPV CODE="45000000-7">
+<TEXT LANG="EN">Construction work</TEXT>
+<TEXT LANG="RO">Lucr&#259;ri de construc&#539;ii</TEXT>
+</CPV>
+</CPV_CODE>"#;
+
+        assert_eq!(
+            read_vocabulary(xml.as_bytes()).unwrap(),
+            vec![
+                CpvCode {
+                    code: "30213100-6".into(),
+                    label_ro: "Computere portabile".into(),
+                    label_en: "Portable computers".into(),
+                },
+                CpvCode {
+                    code: "45000000-7".into(),
+                    label_ro: "Lucrări de construcții".into(),
+                    label_en: "Construction work".into(),
+                },
+            ]
+        );
+
+        let invalid =
+            r#"<CPV_CODE><CPV CODE="30213100-1"><TEXT LANG="EN">x</TEXT></CPV></CPV_CODE>"#;
+
+        assert_eq!(
+            read_vocabulary(invalid.as_bytes()).unwrap_err().to_string(),
+            "Invalid CPV code: 30213100-1."
+        );
+
+        let unlabelled =
+            r#"<CPV_CODE><CPV CODE="30213100-6"><TEXT LANG="EN">x</TEXT></CPV></CPV_CODE>"#;
+
+        assert_eq!(
+            read_vocabulary(unlabelled.as_bytes())
+                .unwrap_err()
+                .to_string(),
+            "CPV code 30213100-6 lacks a Romanian or English label."
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let codes = vec![CpvCode {
+            code: "30213100-6".into(),
+            label_ro: "Computere portabile".into(),
+            label_en: "Portable computers".into(),
+        }];
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &codes).unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv.clone()).unwrap(),
+            "code,label_ro,label_en\n30213100-6,Computere portabile,Portable computers\n"
+        );
+        assert_eq!(read_csv(csv.as_slice()).unwrap(), codes);
+
+        let invalid = "code,label_ro,label_en\n30213100-1,x,x\n";
+
+        assert_eq!(
+            read_csv(invalid.as_bytes()).unwrap_err().to_string(),
+            "Invalid CPV code: 30213100-1."
+        );
+    }
+
+    #[test]
+    fn test_bundled() {
+        let codes = bundled().unwrap();
+
+        // every division
+        assert_eq!(
+            codes.iter().filter(|c| prefix(&c.code).len() == 2).count(),
+            45
+        );
+        assert!(codes.iter().any(|c| c.code == "45000000-7"));
+    }
+}
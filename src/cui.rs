@@ -0,0 +1,81 @@
+//! Romanian fiscal codes (CUI / CIF) identifying contracting authorities.
+//!
+//! A CUI has between 2 and 10 digits, the last of which is a check digit, and is often
+//! written with an `RO` prefix for VAT payers (`RO 16054368`).
+
+use anyhow::{Error, Result};
+
+const CHECK_KEY: [u32; 9] = [7, 5, 3, 2, 1, 7, 5, 3, 2];
+
+/// Strips the `RO` prefix and any whitespace.
+pub fn normalize(cui: &str) -> String {
+    let cui: String = cui.chars().filter(|c| !c.is_whitespace()).collect();
+
+    match cui.get(..2) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("RO") => cui[2..].to_string(),
+        _ => cui,
+    }
+}
+
+pub fn is_valid(cui: &str) -> bool {
+    let cui = normalize(cui);
+
+    if !(2..=10).contains(&cui.len()) || !cui.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    let digits: Vec<u32> = cui.chars().filter_map(|c| c.to_digit(10)).collect();
+    let (check, body) = digits.split_last().unwrap();
+
+    let sum: u32 = body
+        .iter()
+        .rev()
+        .zip(CHECK_KEY.iter().rev())
+        .map(|(digit, key)| digit * key)
+        .sum();
+
+    sum * 10 % 11 % 10 == *check
+}
+
+pub fn validate(cuis: &[String]) -> Result<()> {
+    match cuis.iter().find(|cui| !is_valid(cui)) {
+        Some(cui) => Err(Error::msg(format!("Invalid CUI: {cui}."))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cui::{is_valid, normalize, validate};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("RO16054368"), "16054368");
+        assert_eq!(normalize("ro 16054368"), "16054368");
+        assert_eq!(normalize(" 4305857 "), "4305857");
+        assert_eq!(normalize("R"), "R");
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid("16054368"));
+        assert!(is_valid("RO16054368"));
+        assert!(is_valid("4305857"));
+        assert!(is_valid("4540712"));
+        assert!(!is_valid("16054367"));
+        assert!(!is_valid("4305858"));
+        assert!(!is_valid("1"));
+        assert!(!is_valid("12345678901"));
+        assert!(!is_valid("43O5857"));
+        assert!(!is_valid(""));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&["16054368".into(), "RO 4305857".into()]).is_ok());
+
+        let err = validate(&["16054368".into(), "4305858".into()]).unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid CUI: 4305858.");
+    }
+}
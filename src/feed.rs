@@ -0,0 +1,539 @@
+//! Atom and RSS feeds of a user's latest matches, for users who'd rather poll than get email.
+//!
+//! Feed readers can't log in, so a feed is authenticated by the user's [`FeedToken`] passed in
+//! the query string. An entry is updated when its tender is materially revised, and the feed
+//! when any of its entries is. Feeds are served with an `ETag` and a `Last-Modified` header so
+//! readers polling an unchanged feed get a `304 Not Modified`.
+//!
+//! [`FeedToken`]: crate::models::FeedToken
+
+use crate::{
+    models::{matches, tender, user, Match, Tender, User},
+    notify::digest,
+};
+use anyhow::Result;
+use axum::http::{
+    header::{IF_MODIFIED_SINCE, IF_NONE_MATCH},
+    HeaderMap,
+};
+use quick_xml::{
+    events::{BytesDecl, BytesText, Event},
+    Writer,
+};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::env;
+use time::{
+    format_description::{well_known::Rfc2822, well_known::Rfc3339, FormatItem},
+    macros::format_description,
+    OffsetDateTime, PrimitiveDateTime, UtcOffset,
+};
+
+/// Entries in a feed.
+const FEED_SIZE: i64 = 50;
+
+/// Where entries link to, `{notice_number}` being replaced by the tender's notice number.
+/// Overridden by the `NOTICE_URL` environment variable.
+const NOTICE_URL: &str =
+    "https://www.e-licitatie.ro/pub/notices/contract-notices/list/0/0?noticeNo={notice_number}";
+
+const SITE_URL: &str = "https://www.e-licitatie.ro";
+
+const TITLE: &str = "Tenders matching your subscriptions";
+
+const HTTP_DATE: &[FormatItem<'_>] = format_description!(
+    "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Atom,
+    Rss,
+}
+
+impl Format {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Atom => "application/atom+xml; charset=utf-8",
+            Format::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+}
+
+/// A match together with its tender and when it last changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub m: Match,
+    pub tender: Tender,
+    pub updated: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feed {
+    pub user: User,
+    /// Most recently updated first.
+    pub entries: Vec<Entry>,
+}
+
+/// The user's feed of their latest matches.
+pub async fn load(pool: &PgPool, id_user: usize) -> Result<Feed> {
+    let user = user::get_one(pool, id_user).await?;
+
+    let mut entries = Vec::new();
+
+    for (m, updated) in matches::get_recent_of_user(pool, id_user, FEED_SIZE).await? {
+        let tender = tender::get_one(pool, m.id_tender.try_into()?).await?;
+
+        entries.push(Entry { m, tender, updated });
+    }
+
+    Ok(Feed { user, entries })
+}
+
+impl Feed {
+    /// When the latest entry was updated, or when the user signed up for an empty feed. Whole
+    /// seconds, the resolution of `Last-Modified`.
+    pub fn updated(&self) -> OffsetDateTime {
+        let updated = self
+            .entries
+            .iter()
+            .map(|entry| entry.updated)
+            .max()
+            .unwrap_or(self.user.created_at);
+
+        updated.replace_nanosecond(0).unwrap_or(updated)
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        let res = match format {
+            Format::Atom => self.atom(),
+            Format::Rss => self.rss(),
+        };
+
+        // writing to a Vec can't fail
+        String::from_utf8(res.unwrap_or_default()).unwrap_or_default()
+    }
+
+    fn atom(&self) -> quick_xml::Result<Vec<u8>> {
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+
+        writer
+            .create_element("feed")
+            .with_attribute(("xmlns", "http://www.w3.org/2005/Atom"))
+            .write_inner_content(|w| {
+                text(
+                    w,
+                    "id",
+                    &format!("urn:seap-subscription-api:feed:{}", self.user.id),
+                )?;
+                text(w, "title", TITLE)?;
+                text(w, "updated", &rfc3339(self.updated()))?;
+                w.create_element("author")
+                    .write_inner_content(|w| text(w, "name", "SEAP Subscription API"))?;
+
+                for entry in &self.entries {
+                    w.create_element("entry").write_inner_content(|w| {
+                        text(w, "id", &entry_id(&entry.m))?;
+                        text(w, "title", &entry.tender.title)?;
+                        text(w, "published", &rfc3339(entry.m.created_at))?;
+                        text(w, "updated", &rfc3339(entry.updated))?;
+                        w.create_element("link")
+                            .with_attribute(("rel", "alternate"))
+                            .with_attribute(("href", notice_url(&entry.tender).as_str()))
+                            .write_empty()?;
+
+                        if let Some(authority) = &entry.tender.contracting_authority {
+                            w.create_element("author")
+                                .write_inner_content(|w| text(w, "name", authority))?;
+                        }
+
+                        w.create_element("summary")
+                            .with_attribute(("type", "text"))
+                            .write_text_content(BytesText::new(&summary(entry)))?;
+
+                        Ok::<_, quick_xml::Error>(())
+                    })?;
+                }
+
+                Ok::<_, quick_xml::Error>(())
+            })?;
+
+        Ok(writer.into_inner())
+    }
+
+    fn rss(&self) -> quick_xml::Result<Vec<u8>> {
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+
+        writer
+            .create_element("rss")
+            .with_attribute(("version", "2.0"))
+            .write_inner_content(|w| {
+                w.create_element("channel").write_inner_content(|w| {
+                    text(w, "title", TITLE)?;
+                    text(w, "link", SITE_URL)?;
+                    text(w, "description", TITLE)?;
+                    text(w, "lastBuildDate", &rfc2822(self.updated()))?;
+
+                    for entry in &self.entries {
+                        w.create_element("item").write_inner_content(|w| {
+                            text(w, "title", &entry.tender.title)?;
+                            text(w, "link", &notice_url(&entry.tender))?;
+                            text(w, "description", &summary(entry))?;
+                            w.create_element("guid")
+                                .with_attribute(("isPermaLink", "false"))
+                                .write_text_content(BytesText::new(&entry_id(&entry.m)))?;
+                            text(w, "pubDate", &rfc2822(entry.updated))?;
+
+                            Ok::<_, quick_xml::Error>(())
+                        })?;
+                    }
+
+                    Ok::<_, quick_xml::Error>(())
+                })?;
+
+                Ok::<_, quick_xml::Error>(())
+            })?;
+
+        Ok(writer.into_inner())
+    }
+}
+
+fn text(w: &mut Writer<Vec<u8>>, name: &str, value: &str) -> quick_xml::Result<()> {
+    w.create_element(name)
+        .write_text_content(BytesText::new(value))?;
+
+    Ok(())
+}
+
+fn entry_id(m: &Match) -> String {
+    format!("urn:seap-subscription-api:match:{}", m.id)
+}
+
+fn summary(entry: &Entry) -> String {
+    let mut lines = vec![format!("Notice number: {}", entry.tender.notice_number)];
+
+    for (label, value) in digest::details(&entry.m, &entry.tender) {
+        lines.push(format!("{label}: {value}"));
+    }
+
+    lines.join("\n")
+}
+
+pub fn notice_url(tender: &Tender) -> String {
+    env::var("NOTICE_URL")
+        .unwrap_or_else(|_| NOTICE_URL.into())
+        .replace("{notice_number}", &tender.notice_number)
+}
+
+fn rfc3339(datetime: OffsetDateTime) -> String {
+    datetime
+        .to_offset(UtcOffset::UTC)
+        .format(&Rfc3339)
+        .unwrap_or_default()
+}
+
+fn rfc2822(datetime: OffsetDateTime) -> String {
+    datetime
+        .to_offset(UtcOffset::UTC)
+        .format(&Rfc2822)
+        .unwrap_or_default()
+}
+
+/// The `ETag` of a rendered feed.
+pub fn etag(body: &str) -> String {
+    format!(
+        "\"{}\"",
+        hex::encode(&Sha256::digest(body.as_bytes())[..16])
+    )
+}
+
+/// Formats a `Last-Modified` value.
+pub fn http_date(datetime: OffsetDateTime) -> String {
+    datetime
+        .to_offset(UtcOffset::UTC)
+        .format(HTTP_DATE)
+        .unwrap_or_default()
+}
+
+pub fn parse_http_date(value: &str) -> Option<OffsetDateTime> {
+    PrimitiveDateTime::parse(value.trim(), HTTP_DATE)
+        .ok()
+        .map(PrimitiveDateTime::assume_utc)
+}
+
+/// Whether the client's copy of the feed is still current, following RFC 9110: `If-None-Match`
+/// takes precedence over `If-Modified-Since`.
+pub fn is_fresh(headers: &HeaderMap, etag: &str, last_modified: OffsetDateTime) -> bool {
+    if let Some(value) = headers.get(IF_NONE_MATCH) {
+        let Ok(value) = value.to_str() else {
+            return false;
+        };
+
+        return value
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag);
+    }
+
+    headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date)
+        .is_some_and(|since| last_modified <= since)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        feed::{etag, http_date, is_fresh, load, parse_http_date, Entry, Feed, Format},
+        matching::{MatchResult, Outcome},
+        models::{load_fixtures, tender, Match, Tender, User},
+    };
+    use anyhow::Result;
+    use axum::http::{
+        header::{IF_MODIFIED_SINCE, IF_NONE_MATCH},
+        HeaderMap, HeaderName, HeaderValue,
+    };
+    use quick_xml::{events::Event, Reader};
+    use sqlx::{query, types::Json, PgPool};
+    use time::macros::datetime;
+
+    fn feed() -> Feed {
+        let outcome = Outcome::Unconstrained;
+
+        let entry = Entry {
+            m: Match {
+                id: 7,
+                id_subscription: 1,
+                id_tender: 1,
+                matched_fields: vec![],
+                score: Some(80),
+                explanation: Json(MatchResult {
+                    price: outcome,
+                    title: outcome,
+                    description: outcome,
+                    additional_info: outcome,
+                    query: outcome,
+                    cpv: outcome,
+                    county: outcome,
+                    authority: outcome,
+                    deadline: outcome,
+                    procedure_type: outcome,
+                    hits: vec![],
+                }),
+                created_at: datetime!(2023-02-10 11:00 UTC),
+                notified_at: None,
+            },
+            tender: Tender {
+                id: 1,
+                title: "Furnizare calculatoare <portabile> & imprimante".into(),
+                description: None,
+                estimated_value: Some(1_250_000),
+                contracting_authority: Some("Consiliul Judetean Cluj".into()),
+                cpv_code: None,
+                submission_deadline: Some(datetime!(2023-03-01 17:00 +02:00)),
+                procedure_type: None,
+                county: None,
+                contracting_authority_cui: None,
+                ..tender::sample()
+            },
+            updated: datetime!(2023-02-12 09:30:15.5 +02:00),
+        };
+
+        Feed {
+            user: User {
+                id: 1,
+                email: "test@test.test".into(),
+                created_at: datetime!(2023-01-01 00:00 UTC),
+            },
+            entries: vec![entry],
+        }
+    }
+
+    /// The text of every element named `name`, in order.
+    fn texts(xml: &str, name: &str) -> Vec<String> {
+        let mut reader = Reader::from_str(xml);
+        let mut texts = Vec::new();
+        let mut inside = false;
+
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(e) => inside = e.name().as_ref() == name.as_bytes(),
+                Event::Text(e) if inside => texts.push(e.unescape().unwrap().into_owned()),
+                Event::End(_) => inside = false,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        texts
+    }
+
+    #[test]
+    fn test_updated() {
+        let mut feed = feed();
+
+        assert_eq!(feed.updated(), datetime!(2023-02-12 07:30:15 UTC));
+
+        feed.entries.clear();
+
+        assert_eq!(feed.updated(), datetime!(2023-01-01 00:00 UTC));
+    }
+
+    #[test]
+    fn test_atom() {
+        let atom = feed().render(Format::Atom);
+
+        assert_eq!(
+            texts(&atom, "title"),
+            vec![
+                "Tenders matching your subscriptions",
+                "Furnizare calculatoare <portabile> & imprimante"
+            ]
+        );
+        assert_eq!(
+            texts(&atom, "updated"),
+            vec!["2023-02-12T07:30:15Z", "2023-02-12T07:30:15.5Z"]
+        );
+        assert_eq!(texts(&atom, "published"), vec!["2023-02-10T11:00:00Z"]);
+        assert_eq!(
+            texts(&atom, "id"),
+            vec![
+                "urn:seap-subscription-api:feed:1",
+                "urn:seap-subscription-api:match:7"
+            ]
+        );
+        assert_eq!(
+            texts(&atom, "name"),
+            vec!["SEAP Subscription API", "Consiliul Judetean Cluj"]
+        );
+        assert!(atom.contains("&lt;portabile&gt; &amp; imprimante"));
+        assert!(atom.contains("noticeNo=CN1052000"));
+
+        let summary = &texts(&atom, "summary")[0];
+
+        assert!(summary.contains("Estimated value: 1.250.000 RON"));
+        assert!(summary.contains("Submission deadline: 2023-03-01 15:00 UTC"));
+    }
+
+    #[test]
+    fn test_rss() {
+        let rss = feed().render(Format::Rss);
+
+        assert!(rss.contains("<rss version=\"2.0\">"));
+        assert_eq!(
+            texts(&rss, "lastBuildDate"),
+            vec!["Sun, 12 Feb 2023 07:30:15 +0000"]
+        );
+        assert_eq!(
+            texts(&rss, "pubDate"),
+            vec!["Sun, 12 Feb 2023 07:30:15 +0000"]
+        );
+        assert_eq!(
+            texts(&rss, "guid"),
+            vec!["urn:seap-subscription-api:match:7"]
+        );
+        assert!(texts(&rss, "link")[1].contains("noticeNo=CN1052000"));
+        assert!(texts(&rss, "description")[1].contains("Contracting authority: Consiliul"));
+    }
+
+    #[test]
+    fn test_http_date() {
+        let date = datetime!(1994-11-06 08:49:37 UTC);
+
+        assert_eq!(http_date(date), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(date));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let tag = etag("<feed/>");
+        let modified = datetime!(2023-02-12 07:30:15 UTC);
+
+        let headers = |pairs: &[(HeaderName, &str)]| {
+            let mut headers = HeaderMap::new();
+
+            for (name, value) in pairs {
+                headers.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+            }
+
+            headers
+        };
+
+        assert_eq!(tag, etag("<feed/>"));
+        assert_ne!(tag, etag("<feed></feed>"));
+
+        assert!(!is_fresh(&headers(&[]), &tag, modified));
+        assert!(is_fresh(&headers(&[(IF_NONE_MATCH, &tag)]), &tag, modified));
+        assert!(is_fresh(
+            &headers(&[(IF_NONE_MATCH, &format!("\"other\", W/{tag}"))]),
+            &tag,
+            modified
+        ));
+        assert!(is_fresh(&headers(&[(IF_NONE_MATCH, "*")]), &tag, modified));
+        assert!(!is_fresh(
+            &headers(&[(IF_NONE_MATCH, "\"other\"")]),
+            &tag,
+            modified
+        ));
+
+        assert!(is_fresh(
+            &headers(&[(IF_MODIFIED_SINCE, "Sun, 12 Feb 2023 07:30:15 GMT")]),
+            &tag,
+            modified
+        ));
+        assert!(!is_fresh(
+            &headers(&[(IF_MODIFIED_SINCE, "Sun, 12 Feb 2023 07:30:14 GMT")]),
+            &tag,
+            modified
+        ));
+        assert!(!is_fresh(
+            &headers(&[(IF_MODIFIED_SINCE, "garbage")]),
+            &tag,
+            modified
+        ));
+
+        // a changed etag wins over an unchanged date
+        assert!(!is_fresh(
+            &headers(&[
+                (IF_NONE_MATCH, "\"other\""),
+                (IF_MODIFIED_SINCE, "Sun, 12 Feb 2023 07:30:15 GMT")
+            ]),
+            &tag,
+            modified
+        ));
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_load(pool: PgPool) -> Result<()> {
+        load_fixtures(&pool).await?;
+
+        query!("UPDATE matches SET created_at = '2023-02-10 10:00Z'::TIMESTAMPTZ + id * INTERVAL '1 hour'")
+            .execute(&pool)
+            .await?;
+
+        {
+            let feed = load(&pool, 1).await?;
+
+            let tenders: Vec<i32> = feed.entries.iter().map(|e| e.tender.id).collect();
+
+            assert_eq!(tenders, vec![3, 2, 1]);
+            assert_eq!(feed.updated(), datetime!(2023-02-10 13:00 UTC));
+        }
+
+        {
+            let feed = load(&pool, 100).await;
+
+            assert!(feed.is_err());
+        }
+
+        Ok(())
+    }
+}
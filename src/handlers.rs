@@ -1,20 +1,131 @@
+mod create_bookmark;
+mod create_feed_token;
 mod create_subscription;
+mod create_tender;
 mod create_user;
+mod create_webhook;
+mod delete_bookmark;
 mod delete_subscription;
+mod delete_tender;
 mod delete_user;
+mod delete_webhook;
+mod explain_match;
+mod get_bookmarks;
+mod get_calendar;
+mod get_cpv_codes;
+mod get_feed;
+mod get_matches;
+mod get_preferences;
 mod get_subscriptions;
+mod get_tender_lots;
+mod get_tender_revisions;
+mod get_tenders;
 mod get_users;
+mod get_webhooks;
+mod import_tenders;
+mod pause_subscription;
+mod preview_subscription;
+mod update_preferences;
 mod update_subscription;
+mod update_tender;
 mod update_user;
+mod update_webhook;
 
 // reexports
+pub use create_bookmark::create_bookmark;
+pub use create_feed_token::create_feed_token;
 pub use create_subscription::create_subscription;
+pub use create_tender::create_tender;
 pub use create_user::create_user;
+pub use create_webhook::create_webhook;
+pub use delete_bookmark::delete_bookmark;
 pub use delete_subscription::delete_subscription;
+pub use delete_tender::delete_tender;
 pub use delete_user::delete_user;
+pub use delete_webhook::delete_webhook;
+pub use explain_match::explain_match;
+pub use get_bookmarks::get_user_bookmarks;
+pub use get_calendar::get_calendar;
+pub use get_cpv_codes::get_cpv_code;
+pub use get_feed::get_atom_feed;
+pub use get_feed::get_rss_feed;
+pub use get_matches::get_subscription_matches;
+pub use get_matches::get_user_matches;
+pub use get_preferences::get_user_preferences;
 pub use get_subscriptions::get_subscription_by_id;
 pub use get_subscriptions::get_subscriptions;
+pub use get_tender_lots::get_tender_lots;
+pub use get_tender_revisions::get_tender_revisions;
+pub use get_tenders::get_tender_by_id;
+pub use get_tenders::get_tenders;
 pub use get_users::get_user_by_id;
 pub use get_users::get_users;
+pub use get_webhooks::get_user_webhooks;
+pub use get_webhooks::get_webhook_deliveries;
+pub use import_tenders::import_tenders;
+pub use pause_subscription::pause_subscription;
+pub use pause_subscription::resume_subscription;
+pub use preview_subscription::preview_subscription;
+pub use update_preferences::update_user_preferences;
 pub use update_subscription::update_subscription;
+pub use update_tender::update_tender;
 pub use update_user::update_user;
+pub use update_webhook::update_webhook;
+
+use crate::{matching::query::ParseError, models::feed_token};
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+/// The query of the endpoints feed readers and calendar clients poll, which can't log in.
+#[derive(Deserialize)]
+pub struct TokenQuery {
+    pub token: String,
+}
+
+/// The response to a subscription that can't be evaluated, with the position of the error in
+/// its query when that's what failed to parse.
+fn invalid_subscription(err: anyhow::Error) -> (StatusCode, Json<Value>) {
+    let mut body = json!({"Error": err.to_string()});
+
+    if let Some(err) = err.downcast_ref::<ParseError>() {
+        body["Position"] = json!(err.position);
+    }
+
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(body))
+}
+
+/// Checks the query carries the user's feed token, returning the response to send otherwise.
+async fn verify_feed_token(
+    pool: &PgPool,
+    id: usize,
+    query: Option<Query<TokenQuery>>,
+) -> Result<(), Response> {
+    let Some(Query(query)) = query else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"Error": "Expected a feed token."})),
+        )
+            .into_response());
+    };
+
+    match feed_token::verify(pool, id, &query.token).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"Error": "Invalid feed token."})),
+        )
+            .into_response()),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        )
+            .into_response()),
+    }
+}
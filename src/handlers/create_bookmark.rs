@@ -0,0 +1,41 @@
+use crate::models::{bookmark, tender, user};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn create_bookmark(
+    State(pool): State<PgPool>,
+    Path((id, id_tender)): Path<(usize, usize)>,
+) -> (StatusCode, Json<Value>) {
+    if let Err(err) = user::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    if let Err(err) = tender::get_one(&pool, id_tender).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match bookmark::create(&pool, id, id_tender).await {
+        Ok(bookmark) => match serde_json::to_value(bookmark) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
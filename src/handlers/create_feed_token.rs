@@ -0,0 +1,35 @@
+use crate::models::{feed_token, user};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+/// Issues the user a new feed token, so the previous one stops working.
+pub async fn create_feed_token(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+) -> (StatusCode, Json<Value>) {
+    if let Err(err) = user::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match feed_token::rotate(&pool, id).await {
+        Ok(token) => match serde_json::to_value(token) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -1,3 +1,4 @@
+use super::invalid_subscription;
 use crate::models::subscription;
 use crate::models::Subscription;
 use axum::{extract::State, http::StatusCode, Json};
@@ -10,6 +11,10 @@ pub async fn create_subscription(
     State(pool): State<PgPool>,
     Json(payload): Json<Subscription>,
 ) -> (StatusCode, Json<Value>) {
+    if let Err(err) = subscription::validate(&payload) {
+        return invalid_subscription(err);
+    }
+
     match subscription::create(&pool, &payload).await {
         Ok(sub) => match serde_json::to_value(sub) {
             Ok(val) => (StatusCode::ACCEPTED, Json(val)),
@@ -0,0 +1,24 @@
+use crate::models::tender;
+use crate::models::Tender;
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn create_tender(
+    State(pool): State<PgPool>,
+    Json(payload): Json<Tender>,
+) -> (StatusCode, Json<Value>) {
+    match tender::create(&pool, &payload).await {
+        Ok(tender) => match serde_json::to_value(tender) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
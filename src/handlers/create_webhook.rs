@@ -0,0 +1,57 @@
+use crate::models::{
+    user,
+    webhook::{self, WebhookParams},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn create_webhook(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    Json(params): Json<WebhookParams>,
+) -> (StatusCode, Json<Value>) {
+    if let Err(err) = webhook::validate(&params) {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    if let Err(err) = webhook::validate_host(&params).await {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    if let Err(err) = user::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match webhook::create(&pool, id, &params).await {
+        Ok(hook) => match serde_json::to_value(&hook) {
+            // the secret is only ever shown here
+            Ok(mut val) => {
+                val["secret"] = json!(hook.secret);
+
+                (StatusCode::ACCEPTED, Json(val))
+            }
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
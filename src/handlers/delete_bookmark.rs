@@ -0,0 +1,27 @@
+use crate::models::bookmark;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn delete_bookmark(
+    State(pool): State<PgPool>,
+    Path((id, id_tender)): Path<(usize, usize)>,
+) -> (StatusCode, Json<Value>) {
+    match bookmark::delete(&pool, id, id_tender).await {
+        Ok(bookmark) => match serde_json::to_value(bookmark) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
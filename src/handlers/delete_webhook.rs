@@ -0,0 +1,27 @@
+use crate::models::webhook;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn delete_webhook(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+) -> (StatusCode, Json<Value>) {
+    match webhook::delete(&pool, id).await {
+        Ok(hook) => match serde_json::to_value(hook) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -0,0 +1,62 @@
+use super::invalid_subscription;
+use crate::{
+    matching::{
+        self,
+        score::{score, Weights},
+    },
+    models::{subscription, tender},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+/// Evaluates the subscription against the tender as it is now, whether they were matched or
+/// not, so support can tell why a notice was or wasn't sent.
+pub async fn explain_match(
+    State(pool): State<PgPool>,
+    Path((id, id_tender)): Path<(usize, usize)>,
+) -> (StatusCode, Json<Value>) {
+    let sub = match subscription::get_one(&pool, id).await {
+        Ok(sub) => sub,
+        Err(err) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"Error": err.to_string()})),
+            )
+        }
+    };
+
+    let tender = match tender::get_one(&pool, id_tender).await {
+        Ok(tender) => tender,
+        Err(err) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"Error": err.to_string()})),
+            )
+        }
+    };
+
+    let res = match matching::matches(&sub, &tender) {
+        Ok(res) => res,
+        Err(err) => return invalid_subscription(err.into()),
+    };
+    let is_match = res.is_match();
+    let score = is_match.then(|| score(&sub, &tender, &res, &Weights::from_env()));
+
+    match serde_json::to_value(res) {
+        Ok(mut val) => {
+            val["is_match"] = json!(is_match);
+            val["score"] = json!(score);
+
+            (StatusCode::ACCEPTED, Json(val))
+        }
+        Err(err) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    models::{bookmark, user},
+    utils::Pagination,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn get_user_bookmarks(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    pagination: Option<Query<Pagination>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(Query(pagination)) = pagination else {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": "Expected pagination query params."})),
+        );
+    };
+
+    if let Err(err) = user::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match bookmark::get_all_of_user(&pool, id, &pagination).await {
+        Ok(bookmarks) => match serde_json::to_value(bookmarks) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -0,0 +1,42 @@
+use super::{verify_feed_token, TokenQuery};
+use crate::calendar;
+use axum::{
+    extract::{Path, Query, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+
+/// The deadlines of the user's matched and bookmarked tenders, authenticated by the feed
+/// token since calendar clients can't log in either.
+pub async fn get_calendar(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    query: Option<Query<TokenQuery>>,
+) -> Response {
+    if let Err(res) = verify_feed_token(&pool, id, query).await {
+        return res;
+    }
+
+    match calendar::load(&pool, id).await {
+        Ok(calendar) => (
+            StatusCode::OK,
+            [
+                (CONTENT_TYPE, "text/calendar; charset=utf-8"),
+                (CACHE_CONTROL, "private, no-cache"),
+            ],
+            calendar.render(),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{cpv, models::cpv as cpv_model};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn get_cpv_code(
+    State(pool): State<PgPool>,
+    Path(code): Path<String>,
+) -> (StatusCode, Json<Value>) {
+    if !cpv::is_valid(&code) {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": format!("Invalid CPV code: {code}.")})),
+        );
+    }
+
+    match cpv_model::resolve(&pool, &code).await {
+        Ok(codes) if codes.is_empty() => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": format!("Unknown CPV code: {code}.")})),
+        ),
+        Ok(codes) => match serde_json::to_value(codes) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
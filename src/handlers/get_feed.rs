@@ -0,0 +1,76 @@
+use super::{verify_feed_token, TokenQuery};
+use crate::feed::{self, Format};
+use axum::{
+    extract::{Path, Query, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, LAST_MODIFIED},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+
+pub async fn get_atom_feed(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    query: Option<Query<TokenQuery>>,
+    headers: HeaderMap,
+) -> Response {
+    get_feed(&pool, id, query, &headers, Format::Atom).await
+}
+
+pub async fn get_rss_feed(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    query: Option<Query<TokenQuery>>,
+    headers: HeaderMap,
+) -> Response {
+    get_feed(&pool, id, query, &headers, Format::Rss).await
+}
+
+async fn get_feed(
+    pool: &PgPool,
+    id: usize,
+    query: Option<Query<TokenQuery>>,
+    headers: &HeaderMap,
+    format: Format,
+) -> Response {
+    if let Err(res) = verify_feed_token(pool, id, query).await {
+        return res;
+    }
+
+    let feed = match feed::load(pool, id).await {
+        Ok(feed) => feed,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"Error": err.to_string()})),
+            )
+                .into_response()
+        }
+    };
+
+    let body = feed.render(format);
+    let etag = feed::etag(&body);
+    let last_modified = feed.updated();
+
+    let validators = [
+        (ETAG, etag.clone()),
+        (LAST_MODIFIED, feed::http_date(last_modified)),
+        (CACHE_CONTROL, "private, no-cache".to_string()),
+    ];
+
+    if feed::is_fresh(headers, &etag, last_modified) {
+        return (StatusCode::NOT_MODIFIED, validators).into_response();
+    }
+
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, format.content_type())],
+        validators,
+        body,
+    )
+        .into_response()
+}
@@ -0,0 +1,34 @@
+use crate::models::{lot, tender};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn get_tender_lots(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+) -> (StatusCode, Json<Value>) {
+    if let Err(err) = tender::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match lot::get_all_of_tender(&pool, id).await {
+        Ok(lots) => match serde_json::to_value(lots) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{models::tender, utils::Pagination};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn get_tender_by_id(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+) -> (StatusCode, Json<Value>) {
+    match tender::get_one(&pool, id).await {
+        Ok(tender) => match serde_json::to_value(tender) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
+
+pub async fn get_tenders(
+    State(pool): State<PgPool>,
+    pagination: Option<Query<Pagination>>,
+) -> (StatusCode, Json<Value>) {
+    match pagination {
+        Some(Query(pagination)) => match tender::get_paginated(&pool, &pagination).await {
+            Ok(tenders) => match serde_json::to_value(tenders) {
+                Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+                Err(err) => (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(json!({"Error": err.to_string()})),
+                ),
+            },
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        None => {
+            let msg = "Expected pagination query params.";
+
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "Error": msg })),
+            )
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{
+    models::{delivery, user, webhook},
+    utils::Pagination,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn get_user_webhooks(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+) -> (StatusCode, Json<Value>) {
+    if let Err(err) = user::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match webhook::get_all_of_user(&pool, id).await {
+        Ok(hooks) => match serde_json::to_value(hooks) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
+
+pub async fn get_webhook_deliveries(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    pagination: Option<Query<Pagination>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(Query(pagination)) = pagination else {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": "Expected pagination query params."})),
+        );
+    };
+
+    if let Err(err) = webhook::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match delivery::get_all_of_webhook(&pool, id, &pagination).await {
+        Ok(deliveries) => match serde_json::to_value(deliveries) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -0,0 +1,41 @@
+use crate::ingest::open_data::{self, Mapping};
+use axum::{
+    extract::{BodyStream, Query, State},
+    http::StatusCode,
+    Json,
+};
+use futures_util::TryStreamExt;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::io;
+use tokio_util::io::{StreamReader, SyncIoBridge};
+
+/// Imports the CSV file sent as the request body, streaming it as it arrives. The query maps
+/// columns onto tender fields, e.g. `?delimiter=;&title=Titlu`.
+pub async fn import_tenders(
+    State(pool): State<PgPool>,
+    Query(mapping): Query<Mapping>,
+    body: BodyStream,
+) -> (StatusCode, Json<Value>) {
+    let body = body.map_err(io::Error::other);
+    let reader = SyncIoBridge::new(StreamReader::new(body));
+
+    match open_data::import(&pool, reader, mapping).await {
+        Ok(report) => match serde_json::to_value(report) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        // storing the rows failed and the whole import was rolled back
+        Err(err) if err.is::<sqlx::Error>() => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+        Err(err) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -0,0 +1,65 @@
+use crate::models::subscription;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+#[derive(Debug, Deserialize)]
+pub struct PauseParams {
+    pub until: OffsetDateTime,
+}
+
+/// Silences the subscription until the given time, keeping its configuration.
+pub async fn pause_subscription(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    Json(params): Json<PauseParams>,
+) -> (StatusCode, Json<Value>) {
+    if params.until <= OffsetDateTime::now_utc() {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": "until must be in the future."})),
+        );
+    }
+
+    set_paused_until(&pool, id, Some(params.until)).await
+}
+
+pub async fn resume_subscription(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+) -> (StatusCode, Json<Value>) {
+    set_paused_until(&pool, id, None).await
+}
+
+async fn set_paused_until(
+    pool: &PgPool,
+    id: usize,
+    until: Option<OffsetDateTime>,
+) -> (StatusCode, Json<Value>) {
+    if let Err(err) = subscription::get_one(pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match subscription::set_paused_until(pool, id, until).await {
+        Ok(sub) => match serde_json::to_value(sub) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
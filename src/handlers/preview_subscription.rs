@@ -0,0 +1,46 @@
+use super::invalid_subscription;
+use crate::{
+    matcher::{self, PreviewOptions},
+    matching::score::Weights,
+    models::{subscription, Subscription},
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn preview_subscription(
+    State(pool): State<PgPool>,
+    options: Option<Query<PreviewOptions>>,
+    Json(payload): Json<Subscription>,
+) -> (StatusCode, Json<Value>) {
+    let Query(options) = options.unwrap_or_default();
+
+    if let Err(err) = options.validate() {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    if let Err(err) = subscription::validate(&payload) {
+        return invalid_subscription(err);
+    }
+
+    match matcher::preview(&pool, &payload, &options, &Weights::from_env()).await {
+        Ok(preview) => match serde_json::to_value(preview) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
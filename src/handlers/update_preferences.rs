@@ -0,0 +1,45 @@
+use crate::models::{
+    preferences::{self, Preferences},
+    user,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn update_user_preferences(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    Json(prefs): Json<Preferences>,
+) -> (StatusCode, Json<Value>) {
+    if let Err(err) = preferences::validate(&prefs) {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    if let Err(err) = user::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match preferences::set(&pool, id, &prefs).await {
+        Ok(prefs) => match serde_json::to_value(prefs) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -1,3 +1,4 @@
+use super::invalid_subscription;
 use crate::models::subscription;
 use crate::models::Subscription;
 use axum::extract::Path;
@@ -7,9 +8,13 @@ use sqlx::PgPool;
 
 pub async fn update_subscription(
     State(pool): State<PgPool>,
-    Path(id): Path<usize>,
+    Path(_id): Path<usize>,
     Json(sub): Json<Subscription>,
 ) -> (StatusCode, Json<Value>) {
+    if let Err(err) = subscription::validate(&sub) {
+        return invalid_subscription(err);
+    }
+
     match subscription::update(&pool, sub).await {
         Ok(sub) => match serde_json::to_value(sub) {
             Ok(val) => (StatusCode::ACCEPTED, Json(val)),
@@ -0,0 +1,36 @@
+use crate::models::tender;
+use crate::models::Tender;
+use axum::extract::Path;
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn update_tender(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    Json(tender): Json<Tender>,
+) -> (StatusCode, Json<Value>) {
+    let id = match id.try_into() {
+        Ok(id) => id,
+        Err(_) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": "The provided id is too large."})),
+            )
+        }
+    };
+
+    match tender::update(&pool, Tender { id, ..tender }).await {
+        Ok(tender) => match serde_json::to_value(tender) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
@@ -0,0 +1,49 @@
+use crate::models::webhook::{self, WebhookParams};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub async fn update_webhook(
+    State(pool): State<PgPool>,
+    Path(id): Path<usize>,
+    Json(params): Json<WebhookParams>,
+) -> (StatusCode, Json<Value>) {
+    if let Err(err) = webhook::validate(&params) {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    if let Err(err) = webhook::validate_host(&params).await {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    if let Err(err) = webhook::get_one(&pool, id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"Error": err.to_string()})),
+        );
+    }
+
+    match webhook::update(&pool, id, &params).await {
+        Ok(hook) => match serde_json::to_value(hook) {
+            Ok(val) => (StatusCode::ACCEPTED, Json(val)),
+            Err(err) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"Error": err.to_string()})),
+            ),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"Error": err.to_string()})),
+        ),
+    }
+}
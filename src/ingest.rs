@@ -0,0 +1,403 @@
+//! Ingestion of tenders published by external notice sources.
+//!
+//! Every source parses its own format into [`Tender`]s, or into [`Notice`]s when the format
+//! also describes lots; this module holds the parsing helpers they share and [`store`] and
+//! [`store_notices`], which save the parsed tenders. The CSV backfill of [`open_data`] stores its
+//! rows itself, in batches, while the [`poller`] keeps fetching new notices from a
+//! [`TenderSource`](source::TenderSource) in the background.
+
+pub mod open_data;
+pub mod poller;
+pub mod seap;
+pub mod source;
+pub mod ted;
+
+use crate::{
+    models::{self, lot, tender, Lot, Revision, Tender},
+    revision,
+};
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime,
+    PrimitiveDateTime, Time,
+};
+use time_tz::{timezones, Offset, PrimitiveDateTimeExt, TimeZone};
+
+/// A tender together with the lots it is divided into, if any.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Notice {
+    pub tender: Tender,
+    pub lots: Vec<Lot>,
+}
+
+impl From<Tender> for Notice {
+    fn from(tender: Tender) -> Self {
+        Notice {
+            tender,
+            lots: Vec::new(),
+        }
+    }
+}
+
+/// A tender that was either stored for the first time or revised by a republication of its
+/// notice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ingested {
+    pub tender: Tender,
+    /// `None` for a tender that wasn't stored before.
+    pub revision: Option<Revision>,
+}
+
+impl Ingested {
+    /// New tenders and material revisions are worth notifying subscribers about.
+    pub fn is_notifiable(&self) -> bool {
+        self.revision.as_ref().is_none_or(|r| r.material)
+    }
+}
+
+/// Saves the tenders, identified by their notice number. A tender that is already stored is
+/// updated and the fields its republication changed are recorded as a revision, while the
+/// fields the republication lacks keep their stored value; one that didn't change is left
+/// alone and isn't returned.
+pub async fn store(pool: &PgPool, tenders: &[Tender]) -> Result<Vec<Ingested>> {
+    let mut ingested = Vec::new();
+
+    for t in tenders {
+        let mut tx = pool.begin().await?;
+
+        ingested.extend(store_tender(&mut tx, t).await?);
+
+        tx.commit().await?;
+    }
+
+    Ok(ingested)
+}
+
+/// Same as [`store`], saving the lots of every created or revised tender along with it, in the
+/// same transaction.
+pub async fn store_notices(pool: &PgPool, notices: &[Notice]) -> Result<Vec<Ingested>> {
+    let mut ingested = Vec::new();
+
+    for notice in notices {
+        let mut tx = pool.begin().await?;
+
+        if let Some(i) = store_tender(&mut tx, &notice.tender).await? {
+            lot::delete_all_of_tender(&mut tx, i.tender.id).await?;
+
+            for l in &notice.lots {
+                let l = Lot {
+                    id_tender: i.tender.id,
+                    ..l.clone()
+                };
+
+                lot::create(&mut tx, &l).await?;
+            }
+
+            ingested.push(i);
+        }
+
+        tx.commit().await?;
+    }
+
+    Ok(ingested)
+}
+
+async fn store_tender(tx: &mut Transaction<'_, Postgres>, t: &Tender) -> Result<Option<Ingested>> {
+    let stored = match tender::get_by_notice_number(&mut *tx, &t.notice_number).await {
+        Ok(stored) => stored,
+        Err(err) if matches!(err.downcast_ref(), Some(sqlx::Error::RowNotFound)) => {
+            return Ok(Some(Ingested {
+                tender: tender::create(&mut *tx, t).await?,
+                revision: None,
+            }));
+        }
+        Err(err) => return Err(err),
+    };
+
+    let t = revision::merge(&stored, t);
+    let changes = revision::diff(&stored, &t);
+
+    if changes.is_empty() {
+        return Ok(None);
+    }
+
+    let t = Tender { id: stored.id, ..t };
+
+    Ok(Some(Ingested {
+        tender: tender::update(&mut *tx, t).await?,
+        revision: Some(models::revision::create(&mut *tx, stored.id, &changes).await?),
+    }))
+}
+
+/// Parses the timestamps found in notices: RFC 3339, or a local Romanian date and time as
+/// `2023-02-10T10:00:00`, `2023-02-10 10:00`, `10.02.2023 10:00` or a bare date.
+pub(crate) fn parse_datetime(text: &str) -> Option<OffsetDateTime> {
+    let text = text.trim();
+
+    if let Ok(datetime) = OffsetDateTime::parse(text, &Rfc3339) {
+        return Some(datetime);
+    }
+
+    let datetime_formats = [
+        format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]"),
+        format_description!("[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond]"),
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+        format_description!("[year]-[month]-[day] [hour]:[minute]"),
+        format_description!("[day].[month].[year] [hour]:[minute]"),
+    ];
+
+    for format in datetime_formats {
+        if let Ok(datetime) = PrimitiveDateTime::parse(text, format) {
+            return Some(in_bucharest(datetime));
+        }
+    }
+
+    let date_formats = [
+        format_description!("[year]-[month]-[day]"),
+        format_description!("[day].[month].[year]"),
+    ];
+
+    for format in date_formats {
+        if let Ok(date) = Date::parse(text, format) {
+            return Some(in_bucharest(date.with_time(Time::MIDNIGHT)));
+        }
+    }
+
+    None
+}
+
+/// Parses amounts written either as numbers or with thousands separators in the English
+/// (`1,500,000.50`) or Romanian (`1.500.000,50`) style, rounding to whole units.
+pub(crate) fn parse_amount(text: &str) -> Option<i64> {
+    let text: String = text
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-'))
+        .collect();
+
+    let decimal_separator = match (text.rfind('.'), text.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => Some(','),
+        (Some(_), Some(_)) => Some('.'),
+        // a lone separator followed by exactly three digits groups thousands
+        (Some(i), None) | (None, Some(i)) if text.len() - i - 1 == 3 => None,
+        (Some(_), None) if text.matches('.').count() == 1 => Some('.'),
+        (None, Some(_)) if text.matches(',').count() == 1 => Some(','),
+        _ => None,
+    };
+
+    let normalized: String = text
+        .chars()
+        .filter_map(|c| match c {
+            '.' | ',' if Some(c) == decimal_separator => Some('.'),
+            '.' | ',' => None,
+            c => Some(c),
+        })
+        .collect();
+
+    normalized
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(|value| value.round() as i64)
+}
+
+/// Reads a SEAP time, which is local to Romania. A time skipped by the change to summer time
+/// is read in summer time, and one repeated by the change back in the first, summer, offset.
+fn in_bucharest(local: PrimitiveDateTime) -> OffsetDateTime {
+    let tz = timezones::db::europe::BUCHAREST;
+
+    local.assume_timezone(tz).take_first().unwrap_or_else(|| {
+        // past the transition, so in its new offset
+        local.assume_offset(tz.get_offset_utc(&local.assume_utc()).to_utc())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ingest::{parse_amount, parse_datetime, seap, store, store_notices, ted, Ingested},
+        models::{lot, tender, Tender},
+    };
+    use anyhow::Result;
+    use sqlx::PgPool;
+    use time::macros::datetime;
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_store(pool: PgPool) -> Result<()> {
+        let tenders = seap::parse(include_str!("ingest/fixtures/seap/notice_list.json"))?.tenders;
+
+        {
+            let ingested = store(&pool, &tenders).await?;
+
+            assert_eq!(ingested.len(), 3);
+            assert!(ingested.iter().all(|i| i.revision.is_none()));
+            assert!(ingested.iter().all(Ingested::is_notifiable));
+        }
+
+        {
+            let ingested = store(&pool, &tenders).await?;
+
+            assert!(ingested.is_empty());
+        }
+
+        {
+            let t = tender::get_by_notice_number(&pool, "SCN1090001").await?;
+
+            assert_eq!(t.title, "Achizitie mobilier de birou");
+            assert_eq!(t.county.as_deref(), Some("IS"));
+        }
+
+        {
+            let extended = Tender {
+                submission_deadline: Some(datetime!(2023-03-01 12:00 UTC)),
+                ..tenders[1].clone()
+            };
+
+            let ingested = store(&pool, &[extended]).await?;
+
+            assert_eq!(ingested.len(), 1);
+            assert_eq!(ingested[0].tender.notice_number, "SCN1090001");
+            assert!(ingested[0].is_notifiable());
+
+            let revision = ingested[0].revision.as_ref().unwrap();
+
+            assert_eq!(revision.revision, 1);
+            assert!(revision.material);
+            assert_eq!(revision.changes.len(), 1);
+            assert_eq!(revision.changes[0].field, "submission_deadline");
+        }
+
+        {
+            let corrected = Tender {
+                title: "Achizitie mobilier de birou (erata)".into(),
+                submission_deadline: Some(datetime!(2023-03-01 12:00 UTC)),
+                ..tenders[1].clone()
+            };
+
+            let ingested = store(&pool, &[corrected]).await?;
+
+            assert!(!ingested[0].is_notifiable());
+            assert_eq!(ingested[0].revision.as_ref().unwrap().revision, 2);
+
+            let t = tender::get_by_notice_number(&pool, "SCN1090001").await?;
+
+            assert_eq!(t.title, "Achizitie mobilier de birou (erata)");
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_store_sparse_republication(pool: PgPool) -> Result<()> {
+        let detail = seap::parse(include_str!("ingest/fixtures/seap/notice_detail.json"))?.tenders;
+
+        store(&pool, &detail).await?;
+
+        let listed = Tender {
+            description: None,
+            additional_info: None,
+            ..detail[0].clone()
+        };
+
+        assert!(store(&pool, &[listed]).await?.is_empty());
+
+        let t = tender::get_by_notice_number(&pool, "CN1052000").await?;
+
+        assert_eq!(t.description, detail[0].description);
+        assert_eq!(t.additional_info, detail[0].additional_info);
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_store_notices(pool: PgPool) -> Result<()> {
+        let notices = vec![ted::parse(include_str!(
+            "../tests/fixtures/ted/cn_multiple_lots.xml"
+        ))?];
+
+        {
+            let ingested = store_notices(&pool, &notices).await?;
+
+            assert_eq!(ingested.len(), 1);
+        }
+
+        {
+            let ingested = store_notices(&pool, &notices).await?;
+
+            assert!(ingested.is_empty());
+        }
+
+        {
+            let t = tender::get_by_notice_number(&pool, &notices[0].tender.notice_number).await?;
+            let lots = lot::get_all_of_tender(&pool, t.id as usize).await?;
+
+            assert_eq!(lots.len(), 3);
+            assert!(lots.iter().all(|l| l.id_tender == t.id));
+        }
+
+        {
+            let mut corrected = notices[0].clone();
+            corrected.tender.title = "Intretinerea drumurilor nationale (erata)".into();
+            corrected.lots.pop();
+
+            let ingested = store_notices(&pool, &[corrected]).await?;
+            let lots = lot::get_all_of_tender(&pool, ingested[0].tender.id as usize).await?;
+
+            assert_eq!(lots.len(), 2);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        let cases = [
+            ("2023-02-10T10:00:00+02:00", datetime!(2023-02-10 08:00 UTC)),
+            ("2023-02-10T10:00:00Z", datetime!(2023-02-10 10:00 UTC)),
+            ("2023-02-10T10:00:00", datetime!(2023-02-10 08:00 UTC)),
+            ("2023-07-10T10:00:00", datetime!(2023-07-10 07:00 UTC)),
+            (
+                "2023-07-10T10:00:00.123",
+                datetime!(2023-07-10 07:00:00.123 UTC),
+            ),
+            ("2023-03-26 02:30", datetime!(2023-03-26 00:30 UTC)),
+            ("2023-03-26 03:30", datetime!(2023-03-26 00:30 UTC)),
+            ("2023-10-29 03:30", datetime!(2023-10-29 00:30 UTC)),
+            ("2023-10-29 04:30", datetime!(2023-10-29 02:30 UTC)),
+            ("10.02.2023 15:30", datetime!(2023-02-10 13:30 UTC)),
+            (" 2023-02-10 ", datetime!(2023-02-09 22:00 UTC)),
+            ("10.02.2023", datetime!(2023-02-09 22:00 UTC)),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(parse_datetime(text), Some(expected), "{text}");
+        }
+
+        assert_eq!(parse_datetime(""), None);
+        assert_eq!(parse_datetime("mâine"), None);
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        let cases = [
+            ("1500000", Some(1_500_000)),
+            ("1500000.49", Some(1_500_000)),
+            ("1,500,000.50", Some(1_500_001)),
+            ("1.500.000,50", Some(1_500_001)),
+            ("1.500.000", Some(1_500_000)),
+            ("1,500", Some(1_500)),
+            ("80000,5", Some(80_001)),
+            ("250.000,00 RON", Some(250_000)),
+            ("", None),
+            ("n/a", None),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(parse_amount(text), expected, "{text}");
+        }
+    }
+}
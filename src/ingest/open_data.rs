@@ -0,0 +1,470 @@
+//! Backfill from the open-data CSV dumps published on data.gov.ro.
+//!
+//! The dumps name their columns differently from one year to the next, so the column holding
+//! each tender field is configured by a [`Mapping`]. Rows are read one at a time and stored in
+//! batches within a single transaction: a row that can't be mapped is reported and skipped,
+//! while a database error rolls the whole import back.
+
+use crate::{
+    counties, cpv,
+    ingest::{parse_amount, parse_datetime},
+    models::{tender, Tender},
+};
+use anyhow::{Error, Result};
+use csv::{ReaderBuilder, StringRecord};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::io::Read;
+use tokio::{sync::mpsc, task};
+
+pub const BATCH_SIZE: usize = 500;
+
+/// The header of the column holding each tender field. Only the notice number, the title and
+/// the publication date are required; the other columns are optional.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mapping {
+    pub delimiter: char,
+    pub notice_number: String,
+    pub title: String,
+    pub description: String,
+    pub additional_info: String,
+    pub estimated_value: String,
+    pub currency: String,
+    pub contracting_authority: String,
+    pub contracting_authority_cui: String,
+    pub cpv_code: String,
+    pub publication_date: String,
+    pub submission_deadline: String,
+    pub procedure_type: String,
+    pub county: String,
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Mapping {
+            delimiter: ',',
+            notice_number: "NumarAnunt".into(),
+            title: "DenumireContract".into(),
+            description: "DescriereContract".into(),
+            additional_info: "InformatiiSuplimentare".into(),
+            estimated_value: "ValoareEstimata".into(),
+            currency: "Moneda".into(),
+            contracting_authority: "AutoritateContractanta".into(),
+            contracting_authority_cui: "AutoritateContractantaCUI".into(),
+            cpv_code: "CodCPV".into(),
+            publication_date: "DataAnunt".into(),
+            submission_deadline: "DataLimitaDepunere".into(),
+            procedure_type: "TipProcedura".into(),
+            county: "Judet".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RowError {
+    pub line: u64,
+    pub error: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub imported: u64,
+    /// Rows whose notice number was already stored.
+    pub skipped: u64,
+    pub errors: Vec<RowError>,
+}
+
+/// Positions of the mapped columns in the header.
+struct Columns {
+    notice_number: usize,
+    title: usize,
+    publication_date: usize,
+    description: Option<usize>,
+    additional_info: Option<usize>,
+    estimated_value: Option<usize>,
+    currency: Option<usize>,
+    contracting_authority: Option<usize>,
+    contracting_authority_cui: Option<usize>,
+    cpv_code: Option<usize>,
+    submission_deadline: Option<usize>,
+    procedure_type: Option<usize>,
+    county: Option<usize>,
+}
+
+/// Imports the tenders of a CSV file, reading it on a blocking thread while the rows read so
+/// far are stored.
+pub async fn import(
+    pool: &PgPool,
+    reader: impl Read + Send + 'static,
+    mapping: Mapping,
+) -> Result<Report> {
+    let (rows, mut received) = mpsc::channel(BATCH_SIZE);
+    let parser = task::spawn_blocking(move || parse(reader, &mapping, rows));
+
+    let mut tx = pool.begin().await?;
+    let mut report = Report::default();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+    while let Some(row) = received.recv().await {
+        match row {
+            Ok(tender) => batch.push(tender),
+            Err(error) => report.errors.push(error),
+        }
+
+        if batch.len() == BATCH_SIZE {
+            report.store(&mut tx, &batch).await?;
+            batch.clear();
+        }
+    }
+
+    parser.await??;
+
+    report.store(&mut tx, &batch).await?;
+    tx.commit().await?;
+
+    Ok(report)
+}
+
+impl Report {
+    async fn store(&mut self, tx: &mut Transaction<'_, Postgres>, batch: &[Tender]) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let created = tender::create_many(tx, batch).await?;
+
+        self.imported += created;
+        self.skipped += batch.len() as u64 - created;
+
+        Ok(())
+    }
+}
+
+fn parse(
+    reader: impl Read,
+    mapping: &Mapping,
+    rows: mpsc::Sender<Result<Tender, RowError>>,
+) -> Result<()> {
+    let delimiter = u8::try_from(mapping.delimiter)
+        .ok()
+        .filter(u8::is_ascii)
+        .ok_or_else(|| Error::msg("The delimiter must be an ASCII character."))?;
+
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(reader);
+
+    let columns = Columns::new(reader.headers()?, mapping)?;
+    let mut record = StringRecord::new();
+
+    loop {
+        let line = reader.position().line();
+
+        let row = match reader.read_record(&mut record) {
+            Ok(false) => return Ok(()),
+            Ok(true) => columns.tender(&record).map_err(|error| RowError {
+                line: record.position().map_or(line, |position| position.line()),
+                error,
+            }),
+            Err(err) if err.is_io_error() => return Err(err.into()),
+            Err(err) => Err(RowError {
+                line,
+                error: err.to_string(),
+            }),
+        };
+
+        // the import stopped early, there's no one left to store the rows
+        if rows.blocking_send(row).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+impl Columns {
+    fn new(headers: &StringRecord, mapping: &Mapping) -> Result<Columns> {
+        let find = |name: &str| {
+            headers.iter().position(|header| {
+                header
+                    .trim_start_matches('\u{feff}')
+                    .trim()
+                    .eq_ignore_ascii_case(name.trim())
+            })
+        };
+
+        let require =
+            |name: &str| find(name).ok_or_else(|| Error::msg(format!("Missing column: {name}.")));
+
+        Ok(Columns {
+            notice_number: require(&mapping.notice_number)?,
+            title: require(&mapping.title)?,
+            publication_date: require(&mapping.publication_date)?,
+            description: find(&mapping.description),
+            additional_info: find(&mapping.additional_info),
+            estimated_value: find(&mapping.estimated_value),
+            currency: find(&mapping.currency),
+            contracting_authority: find(&mapping.contracting_authority),
+            contracting_authority_cui: find(&mapping.contracting_authority_cui),
+            cpv_code: find(&mapping.cpv_code),
+            submission_deadline: find(&mapping.submission_deadline),
+            procedure_type: find(&mapping.procedure_type),
+            county: find(&mapping.county),
+        })
+    }
+
+    fn tender(&self, record: &StringRecord) -> Result<Tender, String> {
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+        let text = |column: Option<usize>| field(column).map(str::to_string);
+
+        let notice_number = text(Some(self.notice_number)).ok_or("Missing notice number.")?;
+        let title = text(Some(self.title)).ok_or("Missing title.")?;
+
+        let publication_date =
+            field(Some(self.publication_date)).ok_or("Missing publication date.")?;
+        let publication_date = parse_datetime(publication_date)
+            .ok_or_else(|| format!("Invalid publication date: {publication_date}."))?;
+
+        let submission_deadline = match field(self.submission_deadline) {
+            Some(deadline) => Some(
+                parse_datetime(deadline)
+                    .ok_or_else(|| format!("Invalid submission deadline: {deadline}."))?,
+            ),
+            None => None,
+        };
+
+        let estimated_value = match field(self.estimated_value) {
+            Some(value) => Some(
+                parse_amount(value).ok_or_else(|| format!("Invalid estimated value: {value}."))?,
+            ),
+            None => None,
+        };
+
+        let currency = match field(self.currency) {
+            Some(currency) if currency.len() == 3 && currency.chars().all(char::is_alphabetic) => {
+                currency.to_uppercase()
+            }
+            Some(currency) => return Err(format!("Invalid currency: {currency}.")),
+            None => "RON".into(),
+        };
+
+        // "45233120-6 - Lucrari de constructii de drumuri (Rev.2)"
+        let cpv_code = match field(self.cpv_code) {
+            Some(code) => match code.split_whitespace().next() {
+                Some(code) if cpv::is_valid(code) => Some(code.to_string()),
+                _ => return Err(format!("Invalid CPV code: {code}.")),
+            },
+            None => None,
+        };
+
+        Ok(Tender {
+            id: 0,
+            notice_number,
+            title,
+            description: text(self.description),
+            additional_info: text(self.additional_info),
+            estimated_value,
+            currency,
+            contracting_authority: text(self.contracting_authority),
+            cpv_code,
+            publication_date,
+            submission_deadline,
+            procedure_type: text(self.procedure_type),
+            county: field(self.county)
+                .and_then(counties::find)
+                .map(|county| county.code.to_string()),
+            contracting_authority_cui: text(self.contracting_authority_cui),
+            clarification_deadline: None,
+            opening_date: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ingest::open_data::{import, Columns, Mapping, RowError},
+        models::tender,
+    };
+    use anyhow::Result;
+    use csv::StringRecord;
+    use sqlx::PgPool;
+    use time::macros::datetime;
+
+    const TENDERS: &str = include_str!("fixtures/open_data/tenders.csv");
+
+    fn columns(headers: &[&str], mapping: &Mapping) -> Result<Columns> {
+        Columns::new(&StringRecord::from(headers.to_vec()), mapping)
+    }
+
+    #[test]
+    fn test_columns() {
+        let mapping = Mapping {
+            title: "Titlu".into(),
+            ..Mapping::default()
+        };
+
+        {
+            let columns =
+                columns(&["\u{feff}NumarAnunt", " titlu ", "DataAnunt"], &mapping).unwrap();
+
+            assert_eq!(
+                (
+                    columns.notice_number,
+                    columns.title,
+                    columns.publication_date
+                ),
+                (0, 1, 2)
+            );
+            assert_eq!(columns.county, None);
+        }
+
+        {
+            let err = columns(&["NumarAnunt", "DenumireContract", "DataAnunt"], &mapping)
+                .err()
+                .unwrap();
+
+            assert_eq!(err.to_string(), "Missing column: Titlu.");
+        }
+    }
+
+    #[test]
+    fn test_tender() {
+        let columns = columns(
+            &[
+                "NumarAnunt",
+                "DenumireContract",
+                "DataAnunt",
+                "ValoareEstimata",
+                "CodCPV",
+                "Judet",
+                "Moneda",
+            ],
+            &Mapping::default(),
+        )
+        .unwrap();
+
+        let row = |values: &[&str]| columns.tender(&StringRecord::from(values.to_vec()));
+
+        {
+            let t = row(&[
+                "CN1052000",
+                " Furnizare laptopuri ",
+                "10.02.2023 10:00",
+                "250.000,00",
+                "30213100-6 - Computere portabile",
+                "Cluj",
+                "",
+            ])
+            .unwrap();
+
+            assert_eq!(t.title, "Furnizare laptopuri");
+            assert_eq!(t.publication_date, datetime!(2023-02-10 08:00 UTC));
+            assert_eq!(t.estimated_value, Some(250_000));
+            assert_eq!(t.cpv_code.as_deref(), Some("30213100-6"));
+            assert_eq!(t.county.as_deref(), Some("CJ"));
+            assert_eq!(t.currency, "RON");
+        }
+
+        {
+            let t = row(&["CN1052000", "Furnizare laptopuri", "2023-02-10"]).unwrap();
+
+            assert_eq!(t.estimated_value, None);
+            assert_eq!(t.cpv_code, None);
+        }
+
+        let cases = [
+            (
+                ["", "Titlu", "2023-02-10", "", "", "", ""],
+                "Missing notice number.",
+            ),
+            (["CN1", " ", "2023-02-10", "", "", "", ""], "Missing title."),
+            (
+                ["CN1", "Titlu", "", "", "", "", ""],
+                "Missing publication date.",
+            ),
+            (
+                ["CN1", "Titlu", "ieri", "", "", "", ""],
+                "Invalid publication date: ieri.",
+            ),
+            (
+                ["CN1", "Titlu", "2023-02-10", "mult", "", "", ""],
+                "Invalid estimated value: mult.",
+            ),
+            (
+                ["CN1", "Titlu", "2023-02-10", "", "30213100-1", "", ""],
+                "Invalid CPV code: 30213100-1.",
+            ),
+            (
+                ["CN1", "Titlu", "2023-02-10", "", "", "", "lei noi"],
+                "Invalid currency: lei noi.",
+            ),
+        ];
+
+        for (values, expected) in cases {
+            assert_eq!(row(&values).unwrap_err(), expected);
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_import(pool: PgPool) -> Result<()> {
+        let mapping = Mapping {
+            delimiter: ';',
+            ..Mapping::default()
+        };
+
+        {
+            let report = import(&pool, TENDERS.as_bytes(), mapping.clone()).await?;
+
+            assert_eq!(report.imported, 2);
+            assert_eq!(report.skipped, 1);
+            assert_eq!(
+                report.errors,
+                vec![
+                    RowError {
+                        line: 4,
+                        error: "Invalid publication date: 31.02.2023.".into()
+                    },
+                    RowError {
+                        line: 6,
+                        error: "Missing title.".into()
+                    },
+                ]
+            );
+        }
+
+        {
+            let t = tender::get_by_notice_number(&pool, "CN1053001").await?;
+
+            assert_eq!(t.title, "Reabilitare scoala gimnaziala; corp B");
+            assert_eq!(t.county.as_deref(), Some("SV"));
+            assert_eq!(t.contracting_authority_cui.as_deref(), Some("4305857"));
+        }
+
+        {
+            let report = import(&pool, TENDERS.as_bytes(), mapping).await?;
+
+            assert_eq!(report.imported, 0);
+            assert_eq!(report.skipped, 3);
+        }
+
+        {
+            let mapping = Mapping {
+                title: "Titlu".into(),
+                ..Mapping::default()
+            };
+
+            let res = import(&pool, TENDERS.as_bytes(), mapping).await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+}
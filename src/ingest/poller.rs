@@ -0,0 +1,308 @@
+//! Background polling of a [`TenderSource`].
+//!
+//! The poller fetches the notices published since the source's persisted high-water mark,
+//! stores them, runs the [`matcher`] over the stored tenders and only then persists the new
+//! mark. When matching fails the mark is kept and the tenders are matched again at the next
+//! poll, since fetching the same notices again doesn't store them a second time.
+//! Polls are spaced by the [`Schedule`]'s interval plus a random jitter, so several instances
+//! don't hit a source in lockstep, and a failing source is retried with an exponential backoff
+//! instead.
+
+use crate::{
+    ingest::{source::TenderSource, store_notices, Ingested},
+    matcher,
+    matching::score::Weights,
+    models::source_cursor,
+};
+use anyhow::{Context, Result};
+use rand::Rng;
+use shuttle_service::tracing::{info, warn};
+use sqlx::PgPool;
+use std::{env, time::Duration};
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    pub interval: Duration,
+    /// Upper bound of the random delay added to every wait.
+    pub jitter: Duration,
+    /// Wait after the first failure, doubled after every further one.
+    pub retry: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            interval: Duration::from_secs(15 * 60),
+            jitter: Duration::from_secs(60),
+            retry: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl Schedule {
+    /// The default schedule, overridden by the `POLL_INTERVAL_SECS`, `POLL_JITTER_SECS`,
+    /// `POLL_RETRY_SECS` and `POLL_MAX_BACKOFF_SECS` environment variables.
+    pub fn from_env() -> Self {
+        let secs = |name: &str, default: Duration| {
+            env::var(name)
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .map_or(default, Duration::from_secs)
+        };
+
+        let default = Schedule::default();
+
+        Schedule {
+            interval: secs("POLL_INTERVAL_SECS", default.interval),
+            jitter: secs("POLL_JITTER_SECS", default.jitter),
+            retry: secs("POLL_RETRY_SECS", default.retry),
+            max_backoff: secs("POLL_MAX_BACKOFF_SECS", default.max_backoff),
+        }
+    }
+
+    /// How long to wait before the next poll, after `failures` consecutive failed polls.
+    pub fn delay(&self, failures: u32, rng: &mut impl Rng) -> Duration {
+        let base = match failures {
+            0 => self.interval,
+            failures => self
+                .retry
+                .saturating_mul(2u32.saturating_pow(failures - 1))
+                .min(self.max_backoff),
+        };
+
+        base + self.jitter.mul_f64(rng.gen())
+    }
+}
+
+/// Polls the source forever, starting right away.
+pub fn spawn(
+    pool: PgPool,
+    source: impl TenderSource + 'static,
+    schedule: Schedule,
+    weights: Weights,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut failures = 0;
+        let mut unmatched = Vec::new();
+
+        loop {
+            match poll(&pool, &source, &weights, &mut unmatched).await {
+                Ok(Polled { ingested, dropped }) => {
+                    failures = 0;
+
+                    if !ingested.is_empty() || dropped > 0 {
+                        info!(
+                            "Ingested {} tenders from {}, dropped {dropped} incomplete notices.",
+                            ingested.len(),
+                            source.name()
+                        );
+                    }
+                }
+                Err(err) => {
+                    failures += 1;
+
+                    warn!(
+                        "Polling {} failed ({failures} in a row): {err:#}",
+                        source.name()
+                    );
+                }
+            }
+
+            let delay = schedule.delay(failures, &mut rand::thread_rng());
+
+            tokio::time::sleep(delay).await;
+        }
+    })
+}
+
+/// The outcome of a poll.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polled {
+    /// The tenders that were created or revised.
+    pub ingested: Vec<Ingested>,
+    /// The notices the source dropped as incomplete.
+    pub dropped: usize,
+}
+
+/// Fetches and stores the notices published since the last poll and matches the stored
+/// tenders, along with the `unmatched` ones of earlier polls whose matching failed. Tenders
+/// are left in `unmatched` until they are matched.
+pub async fn poll(
+    pool: &PgPool,
+    source: &dyn TenderSource,
+    weights: &Weights,
+    unmatched: &mut Vec<Ingested>,
+) -> Result<Polled> {
+    let name = source.name();
+    let high_water_mark = source_cursor::get(pool, &name).await?;
+
+    let fetched = source.fetch(high_water_mark.as_deref()).await?;
+    let ingested = store_notices(pool, &fetched.notices).await?;
+
+    unmatched.extend(ingested.iter().cloned());
+
+    matcher::record_ingested(pool, unmatched, weights)
+        .await
+        .with_context(|| format!("Matching tenders from {name} failed"))?;
+
+    unmatched.clear();
+
+    if let Some(mark) = fetched.high_water_mark {
+        if Some(&mark) != high_water_mark.as_ref() {
+            source_cursor::set(pool, &name, &mark).await?;
+        }
+    }
+
+    Ok(Polled {
+        ingested,
+        dropped: fetched.dropped,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ingest::{
+            poller::{poll, Schedule},
+            source::DirectorySource,
+        },
+        matching::score::Weights,
+        models::{source_cursor, tender},
+    };
+    use anyhow::Result;
+    use rand::{rngs::StdRng, SeedableRng};
+    use sqlx::{Executor, PgPool};
+    use std::{fs, time::Duration};
+
+    #[test]
+    fn test_delay() {
+        let schedule = Schedule {
+            interval: Duration::from_secs(600),
+            jitter: Duration::from_secs(60),
+            retry: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(300),
+        };
+
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let cases = [
+            (0, 600),
+            (1, 30),
+            (2, 60),
+            (3, 120),
+            (4, 240),
+            (5, 300),
+            (40, 300),
+        ];
+
+        for (failures, base) in cases {
+            let delay = schedule.delay(failures, &mut rng);
+
+            assert!(delay >= Duration::from_secs(base), "{failures}: {delay:?}");
+            assert!(
+                delay <= Duration::from_secs(base + 60),
+                "{failures}: {delay:?}"
+            );
+        }
+
+        let schedule = Schedule {
+            jitter: Duration::ZERO,
+            ..schedule
+        };
+
+        assert_eq!(schedule.delay(0, &mut rng), Duration::from_secs(600));
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_poll(pool: PgPool) -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = DirectorySource::new(dir.path());
+        let mut unmatched = Vec::new();
+
+        fs::write(
+            dir.path().join("2023-02-10-list.json"),
+            include_str!("fixtures/seap/notice_list.json"),
+        )?;
+
+        {
+            let polled = poll(&pool, &source, &Weights::default(), &mut unmatched).await?;
+
+            assert_eq!(polled.ingested.len(), 3);
+            assert_eq!(polled.dropped, 0);
+        }
+
+        {
+            let polled = poll(&pool, &source, &Weights::default(), &mut unmatched).await?;
+
+            assert!(polled.ingested.is_empty());
+        }
+
+        fs::write(
+            dir.path().join("2023-02-11-cn.xml"),
+            include_str!("../../tests/fixtures/ted/cn_single_lot.xml"),
+        )?;
+
+        {
+            let polled = poll(&pool, &source, &Weights::default(), &mut unmatched).await?;
+
+            assert_eq!(polled.ingested.len(), 1);
+            assert!(tender::get_by_notice_number(&pool, "00098765-2023")
+                .await
+                .is_ok());
+        }
+
+        {
+            let mark =
+                source_cursor::get(&pool, &format!("directory:{}", dir.path().display())).await?;
+
+            assert_eq!(mark.as_deref(), Some("2023-02-11-cn.xml"));
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_poll_matching_failure(pool: PgPool) -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = DirectorySource::new(dir.path());
+        let name = format!("directory:{}", dir.path().display());
+        let mut unmatched = Vec::new();
+
+        fs::write(
+            dir.path().join("2023-02-10-list.json"),
+            include_str!("fixtures/seap/notice_list.json"),
+        )?;
+
+        pool.execute("ALTER TABLE subscriptions RENAME TO subscriptions_off")
+            .await?;
+
+        {
+            let res = poll(&pool, &source, &Weights::default(), &mut unmatched).await;
+
+            assert!(res.is_err());
+            assert_eq!(unmatched.len(), 3);
+            assert_eq!(source_cursor::get(&pool, &name).await?, None);
+        }
+
+        pool.execute("ALTER TABLE subscriptions_off RENAME TO subscriptions")
+            .await?;
+
+        {
+            let polled = poll(&pool, &source, &Weights::default(), &mut unmatched).await?;
+
+            assert!(polled.ingested.is_empty());
+            assert!(unmatched.is_empty());
+            assert_eq!(
+                source_cursor::get(&pool, &name).await?.as_deref(),
+                Some("2023-02-10-list.json")
+            );
+        }
+
+        Ok(())
+    }
+}
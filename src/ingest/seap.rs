@@ -0,0 +1,590 @@
+//! Notices from the public e-licitatie.ro (SEAP) API.
+//!
+//! Two documents are understood: the notice listing (`{"total": .., "items": [..]}`, one flat
+//! item per notice) and the notice detail (the notice split into its numbered form sections).
+//! Both are parsed leniently: unknown fields are ignored, every field may be missing or
+//! `null`, amounts and dates may come as numbers or text, and a notice is only dropped when it
+//! lacks a notice number, a title or a publication date. The publication date isn't made up,
+//! since a republication would then look like a revision of it; dropped notices are logged and
+//! counted instead.
+
+use crate::{
+    counties, cpv, cui,
+    ingest::{parse_amount, parse_datetime},
+    models::Tender,
+};
+use anyhow::{Context, Error, Result};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use shuttle_service::tracing::warn;
+use std::path::Path;
+
+/// The tenders of a SEAP document, along with how many of its notices were dropped.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Parsed {
+    pub tenders: Vec<Tender>,
+    pub dropped: usize,
+}
+
+impl FromIterator<Result<Tender, String>> for Parsed {
+    fn from_iter<I: IntoIterator<Item = Result<Tender, String>>>(notices: I) -> Self {
+        let mut parsed = Parsed::default();
+
+        for notice in notices {
+            match notice {
+                Ok(tender) => parsed.tenders.push(tender),
+                Err(reason) => {
+                    warn!("Dropping {reason}");
+                    parsed.dropped += 1;
+                }
+            }
+        }
+
+        parsed
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct NoticeList {
+    items: Vec<ListItem>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ListItem {
+    notice_no: Option<String>,
+    contract_title: Option<String>,
+    #[serde(rename = "contractingAuthorityNameAndFN")]
+    contracting_authority_name_and_fn: Option<String>,
+    cpv_code_and_name: Option<String>,
+    estimated_value_ron: Option<Value>,
+    estimated_value_export: Option<String>,
+    currency_code: Option<String>,
+    notice_state_date: Option<String>,
+    tender_receipt_deadline: Option<String>,
+    procedure_type: Option<Value>,
+    county: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct NoticeDetail {
+    notice_number: Option<String>,
+    publication_date: Option<String>,
+    #[serde(deserialize_with = "nullable")]
+    section1: Section1,
+    #[serde(deserialize_with = "nullable")]
+    section2: Section2,
+    #[serde(deserialize_with = "nullable")]
+    section4: Section4,
+    #[serde(deserialize_with = "nullable")]
+    section6: Section6,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Section1 {
+    #[serde(deserialize_with = "nullable")]
+    section1_1: Section1_1,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Section1_1 {
+    #[serde(deserialize_with = "nullable")]
+    ca_address: Address,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Address {
+    official_name: Option<String>,
+    #[serde(rename = "nationalIDNumber")]
+    national_id_number: Option<Value>,
+    county: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Section2 {
+    #[serde(deserialize_with = "nullable")]
+    section2_1: Section2_1,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Section2_1 {
+    contract_title: Option<String>,
+    main_cpv_code: Option<Value>,
+    short_contract_description: Option<String>,
+    estimated_value: Option<Value>,
+    currency: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Section4 {
+    #[serde(deserialize_with = "nullable")]
+    section4_1: Section4_1,
+    #[serde(deserialize_with = "nullable")]
+    section4_2: Section4_2,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Section4_1 {
+    procedure_type: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Section4_2 {
+    tender_receipt_deadline: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Section6 {
+    #[serde(deserialize_with = "nullable")]
+    section6_3: Section6_3,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Section6_3 {
+    additional_information: Option<String>,
+}
+
+/// Treats a `null` section like a missing one.
+fn nullable<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Parses either a notice listing or a notice detail.
+pub fn parse(json: &str) -> Result<Parsed> {
+    let value: Value = serde_json::from_str(json).context("Invalid SEAP notice JSON.")?;
+
+    if value.get("items").is_some() {
+        parse_list(value)
+    } else {
+        Ok([parse_detail(value)?].into_iter().collect())
+    }
+}
+
+pub async fn read(path: impl AsRef<Path>) -> Result<Parsed> {
+    let json = tokio::fs::read_to_string(path).await?;
+
+    parse(&json)
+}
+
+pub async fn fetch(client: &reqwest::Client, url: &str) -> Result<Parsed> {
+    let json = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    parse(&json)
+}
+
+fn parse_list(value: Value) -> Result<Parsed> {
+    let list: NoticeList = serde_json::from_value(value)?;
+
+    Ok(list.items.into_iter().map(list_item).collect())
+}
+
+fn parse_detail(value: Value) -> Result<Result<Tender, String>> {
+    if !value.is_object() {
+        return Err(Error::msg("Expected a SEAP notice object."));
+    }
+
+    let detail: NoticeDetail = serde_json::from_value(value)?;
+
+    let address = detail.section1.section1_1.ca_address;
+    let contract = detail.section2.section2_1;
+
+    Ok(tender(
+        detail.notice_number,
+        contract.contract_title,
+        TenderFields {
+            description: contract.short_contract_description,
+            additional_info: detail.section6.section6_3.additional_information,
+            estimated_value: contract.estimated_value.as_ref().and_then(amount),
+            currency: contract.currency.as_ref().and_then(text),
+            contracting_authority: address.official_name,
+            contracting_authority_cui: address.national_id_number.as_ref().and_then(text),
+            cpv_code: contract.main_cpv_code.as_ref().and_then(cpv_code),
+            publication_date: detail.publication_date,
+            submission_deadline: detail.section4.section4_2.tender_receipt_deadline,
+            procedure_type: detail
+                .section4
+                .section4_1
+                .procedure_type
+                .as_ref()
+                .and_then(text),
+            county: address.county.as_ref().and_then(text),
+        },
+    ))
+}
+
+fn list_item(item: ListItem) -> Result<Tender, String> {
+    // "4305857 - MUNICIPIUL CLUJ-NAPOCA"
+    let (cui, authority) = match item.contracting_authority_name_and_fn {
+        Some(authority) => match authority.split_once(" - ") {
+            Some((fiscal_code, name)) if cui::is_valid(fiscal_code) => {
+                (Some(fiscal_code.to_string()), Some(name.into()))
+            }
+            _ => (None, Some(authority)),
+        },
+        None => (None, None),
+    };
+
+    // `estimatedValueRon` is always in RON, whatever the currency of the notice, while the
+    // export text carries its own currency: "80,000.00 RON"
+    let (estimated_value, currency) = match item.estimated_value_ron.as_ref().and_then(amount) {
+        Some(value) => (Some(value), Some("RON".into())),
+        None => {
+            let export = item.estimated_value_export.as_deref();
+
+            (
+                export.and_then(parse_amount),
+                export.and_then(currency_suffix).or(item.currency_code),
+            )
+        }
+    };
+
+    tender(
+        item.notice_no,
+        item.contract_title,
+        TenderFields {
+            description: None,
+            additional_info: None,
+            estimated_value,
+            currency,
+            contracting_authority: authority,
+            contracting_authority_cui: cui,
+            // "45233120-6 - Lucrari de constructii de drumuri (Rev.2)"
+            cpv_code: item.cpv_code_and_name,
+            publication_date: item.notice_state_date,
+            submission_deadline: item.tender_receipt_deadline,
+            procedure_type: item.procedure_type.as_ref().and_then(text),
+            county: item.county.as_ref().and_then(text),
+        },
+    )
+}
+
+struct TenderFields {
+    description: Option<String>,
+    additional_info: Option<String>,
+    estimated_value: Option<i64>,
+    currency: Option<String>,
+    contracting_authority: Option<String>,
+    contracting_authority_cui: Option<String>,
+    cpv_code: Option<String>,
+    publication_date: Option<String>,
+    submission_deadline: Option<String>,
+    procedure_type: Option<String>,
+    county: Option<String>,
+}
+
+/// Builds the tender of a notice, or says why the notice has to be dropped.
+fn tender(
+    notice_number: Option<String>,
+    title: Option<String>,
+    fields: TenderFields,
+) -> Result<Tender, String> {
+    let notice_number = non_empty(notice_number).ok_or("a SEAP notice without a notice number.")?;
+    let title = non_empty(title)
+        .ok_or_else(|| format!("SEAP notice {notice_number}, which has no title."))?;
+    let publication_date = fields
+        .publication_date
+        .as_deref()
+        .and_then(parse_datetime)
+        .ok_or_else(|| format!("SEAP notice {notice_number}, which has no publication date."))?;
+
+    let cpv_code = fields
+        .cpv_code
+        .as_deref()
+        .and_then(|cpv_code| cpv_code.split_whitespace().next())
+        .filter(|cpv_code| cpv::is_valid(cpv_code))
+        .map(str::to_string);
+
+    let county = fields
+        .county
+        .as_deref()
+        .and_then(counties::find)
+        .map(|county| county.code.to_string());
+
+    Ok(Tender {
+        id: 0,
+        notice_number,
+        title,
+        description: non_empty(fields.description),
+        additional_info: non_empty(fields.additional_info),
+        estimated_value: fields.estimated_value,
+        currency: non_empty(fields.currency).unwrap_or_else(|| "RON".into()),
+        contracting_authority: non_empty(fields.contracting_authority),
+        cpv_code,
+        publication_date,
+        submission_deadline: fields
+            .submission_deadline
+            .as_deref()
+            .and_then(parse_datetime),
+        procedure_type: non_empty(fields.procedure_type),
+        county,
+        contracting_authority_cui: fields
+            .contracting_authority_cui
+            .filter(|c| cui::is_valid(c))
+            .map(|c| cui::normalize(&c)),
+        clarification_deadline: None,
+        opening_date: None,
+    })
+}
+
+/// Lookup values come either as plain strings or as `{"id": .., "text": ".."}` objects.
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Object(object) => ["text", "localeKey", "name"]
+            .iter()
+            .find_map(|key| object.get(*key).and_then(text)),
+        _ => None,
+    }
+}
+
+/// The CPV code of a `{"localeKey": "30213100-6", "text": ".."}` lookup or of plain text
+/// starting with the code.
+fn cpv_code(value: &Value) -> Option<String> {
+    let candidates = match value {
+        Value::Object(object) => ["localeKey", "code", "text"]
+            .iter()
+            .filter_map(|key| object.get(*key).and_then(text))
+            .collect(),
+        value => text(value).into_iter().collect::<Vec<_>>(),
+    };
+
+    candidates.into_iter().find_map(|candidate| {
+        candidate
+            .split_whitespace()
+            .next()
+            .filter(|code| cpv::is_valid(code))
+            .map(str::to_string)
+    })
+}
+
+fn amount(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|value| value.round() as i64),
+        Value::String(text) => parse_amount(text),
+        _ => None,
+    }
+}
+
+/// The currency code an amount is written with, as in "80,000.00 RON".
+fn currency_suffix(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .last()
+        .filter(|code| code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(str::to_uppercase)
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ingest::seap::{fetch, parse, Parsed};
+    use axum::{routing::get, Router};
+    use time::macros::datetime;
+
+    const NOTICE_LIST: &str = include_str!("fixtures/seap/notice_list.json");
+    const NOTICE_DETAIL: &str = include_str!("fixtures/seap/notice_detail.json");
+
+    #[test]
+    fn test_parse_notice_list() {
+        let tenders = parse(NOTICE_LIST).unwrap().tenders;
+
+        assert_eq!(tenders.len(), 3);
+
+        {
+            let t = &tenders[0];
+
+            assert_eq!(t.notice_number, "CN1051234");
+            assert_eq!(t.title, "Modernizare drum judetean DJ 107");
+            assert_eq!(t.contracting_authority.as_deref(), Some("JUDETUL ALBA"));
+            assert_eq!(t.contracting_authority_cui.as_deref(), Some("4562141"));
+            assert_eq!(t.cpv_code.as_deref(), Some("45233120-6"));
+            assert_eq!(t.estimated_value, Some(1_500_000));
+            assert_eq!(t.currency, "RON");
+            assert_eq!(t.publication_date, datetime!(2023-02-01 09:00 UTC));
+            assert_eq!(t.submission_deadline, Some(datetime!(2023-03-01 10:00 UTC)));
+            assert_eq!(t.procedure_type.as_deref(), Some("Licitatie deschisa"));
+            assert_eq!(t.county.as_deref(), Some("AB"));
+        }
+
+        {
+            let t = &tenders[1];
+
+            assert_eq!(t.notice_number, "SCN1090001");
+            assert_eq!(t.estimated_value, Some(80_000));
+            assert_eq!(t.contracting_authority_cui, None);
+            assert_eq!(t.contracting_authority.as_deref(), Some("MUNICIPIUL IASI"));
+            assert_eq!(t.procedure_type.as_deref(), Some("Procedura simplificata"));
+        }
+
+        {
+            let t = &tenders[2];
+
+            assert_eq!(t.notice_number, "CN1051300");
+            assert_eq!(t.estimated_value, None);
+            assert_eq!(t.cpv_code, None);
+            assert_eq!(t.submission_deadline, None);
+            assert_eq!(t.county, None);
+        }
+    }
+
+    #[test]
+    fn test_parse_notice_detail() {
+        let tenders = parse(NOTICE_DETAIL).unwrap().tenders;
+
+        assert_eq!(tenders.len(), 1);
+
+        let t = &tenders[0];
+
+        assert_eq!(t.notice_number, "CN1052000");
+        assert_eq!(t.title, "Furnizare calculatoare portabile");
+        assert_eq!(
+            t.description.as_deref(),
+            Some("Laptopuri pentru unitatile scolare din judet")
+        );
+        assert_eq!(
+            t.additional_info.as_deref(),
+            Some("Garantie minima 36 de luni")
+        );
+        assert_eq!(t.estimated_value, Some(250_000));
+        assert_eq!(t.currency, "RON");
+        assert_eq!(t.contracting_authority.as_deref(), Some("JUDETUL CLUJ"));
+        assert_eq!(t.contracting_authority_cui.as_deref(), Some("4288110"));
+        assert_eq!(t.cpv_code.as_deref(), Some("30213100-6"));
+        assert_eq!(t.publication_date, datetime!(2023-02-10 08:00 UTC));
+        assert_eq!(t.submission_deadline, Some(datetime!(2023-03-01 13:00 UTC)));
+        assert_eq!(t.procedure_type.as_deref(), Some("Licitatie deschisa"));
+        assert_eq!(t.county.as_deref(), Some("CJ"));
+    }
+
+    #[test]
+    fn test_list_value_currency() {
+        let tenders = parse(
+            r#"{"items": [
+                {"noticeNo": "CN1", "contractTitle": "x", "noticeStateDate": "2023-02-10", "estimatedValueRon": 500000, "estimatedValueExport": "100,000.00 EUR", "currencyCode": "EUR"},
+                {"noticeNo": "CN2", "contractTitle": "y", "noticeStateDate": "2023-02-10", "estimatedValueExport": "100,000.00 EUR", "currencyCode": "EUR"},
+                {"noticeNo": "CN3", "contractTitle": "z", "noticeStateDate": "2023-02-10", "currencyCode": "EUR"}
+            ]}"#,
+        )
+        .unwrap()
+        .tenders;
+
+        assert_eq!(tenders[0].estimated_value, Some(500_000));
+        assert_eq!(tenders[0].currency, "RON");
+        assert_eq!(tenders[1].estimated_value, Some(100_000));
+        assert_eq!(tenders[1].currency, "EUR");
+        assert_eq!(tenders[2].estimated_value, None);
+        assert_eq!(tenders[2].currency, "EUR");
+    }
+
+    #[test]
+    fn test_parse_is_lenient() {
+        {
+            let parsed =
+                parse(r#"{"items": [{"noticeNo": "CN1"}, {"contractTitle": "x"}]}"#).unwrap();
+
+            assert!(parsed.tenders.is_empty());
+            assert_eq!(parsed.dropped, 2);
+        }
+
+        {
+            let tenders = parse(r#"{"noticeNumber": "CN1", "publicationDate": "2023-02-10", "section2": {"section2_1": {"contractTitle": "Titlu", "estimatedValue": null}}, "section4": null}"#)
+                .unwrap()
+                .tenders;
+
+            assert_eq!(tenders.len(), 1);
+            assert_eq!(tenders[0].title, "Titlu");
+            assert_eq!(tenders[0].estimated_value, None);
+        }
+
+        {
+            let parsed = parse(
+                r#"{"items": [{"noticeNo": "CN1", "contractTitle": "x"}, {"noticeNo": "CN2", "contractTitle": "y", "noticeStateDate": "ieri"}]}"#,
+            )
+            .unwrap();
+
+            assert!(parsed.tenders.is_empty());
+            assert_eq!(parsed.dropped, 2);
+        }
+
+        {
+            let parsed = parse(r#"{"total": 0, "items": []}"#).unwrap();
+
+            assert_eq!(parsed, Parsed::default());
+        }
+
+        {
+            let parsed = parse(r#"{"noticeNumber": "CN1", "section2": {"section2_1": {"contractTitle": "Titlu"}}}"#).unwrap();
+
+            assert!(parsed.tenders.is_empty());
+            assert_eq!(parsed.dropped, 1);
+        }
+
+        {
+            assert!(parse("[1, 2]").is_err());
+            assert!(parse("<html></html>").is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_from_fixture_server() {
+        let router = Router::new()
+            .route("/notices", get(|| async { NOTICE_LIST }))
+            .route("/notices/1", get(|| async { NOTICE_DETAIL }));
+
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(router.into_make_service());
+        let addr = server.local_addr();
+
+        tokio::spawn(server);
+
+        let client = reqwest::Client::new();
+
+        {
+            let tenders = fetch(&client, &format!("http://{addr}/notices"))
+                .await
+                .unwrap();
+
+            assert_eq!(tenders.tenders.len(), 3);
+        }
+
+        {
+            let tenders = fetch(&client, &format!("http://{addr}/notices/1"))
+                .await
+                .unwrap();
+
+            assert_eq!(tenders.tenders[0].notice_number, "CN1052000");
+        }
+
+        {
+            let res = fetch(&client, &format!("http://{addr}/missing")).await;
+
+            assert!(res.is_err());
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Places the [`poller`](super::poller) fetches new notices from.
+//!
+//! A source remembers how far it got through an opaque high-water mark of its own choosing (a
+//! file name, a publication date, a notice id, ...), which the poller persists between polls.
+
+use crate::ingest::{seap, ted, Notice};
+use anyhow::Result;
+use async_trait::async_trait;
+use shuttle_service::tracing::warn;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fetched {
+    pub notices: Vec<Notice>,
+    /// Notices left out because they lacked a field every tender needs.
+    pub dropped: usize,
+    /// The mark to resume from next time.
+    pub high_water_mark: Option<String>,
+}
+
+#[async_trait]
+pub trait TenderSource: Send + Sync {
+    /// Identifies the source, and the high-water mark persisted for it.
+    fn name(&self) -> String;
+
+    /// Fetches the notices published after `high_water_mark`, or every notice when `None`.
+    async fn fetch(&self, high_water_mark: Option<&str>) -> Result<Fetched>;
+}
+
+/// Notices dropped as files into a directory: SEAP notice listings or details as `.json`,
+/// eForms contract notices as `.xml`. Files are picked up in the order of their names, which
+/// should therefore start with their date (`2023-02-10-notices.json`), and the high-water mark
+/// is the name of the last one read. A file that can't be parsed is skipped.
+#[derive(Debug, Clone)]
+pub struct DirectorySource {
+    dir: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        DirectorySource { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl TenderSource for DirectorySource {
+    fn name(&self) -> String {
+        format!("directory:{}", self.dir.display())
+    }
+
+    async fn fetch(&self, high_water_mark: Option<&str>) -> Result<Fetched> {
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+
+            let is_notice = name.ends_with(".json") || name.ends_with(".xml");
+            let is_new = high_water_mark.is_none_or(|mark| name.as_str() > mark);
+
+            if is_notice && is_new && entry.file_type().await?.is_file() {
+                files.push(name);
+            }
+        }
+
+        files.sort();
+
+        let mut notices = Vec::new();
+        let mut dropped = 0;
+
+        for name in &files {
+            let path = self.dir.join(name);
+
+            let parsed = if name.ends_with(".xml") {
+                ted::read(&path).await.map(|notice| (vec![notice], 0))
+            } else {
+                seap::read(&path).await.map(|parsed| {
+                    let tenders = parsed.tenders.into_iter().map(Notice::from).collect();
+
+                    (tenders, parsed.dropped)
+                })
+            };
+
+            match parsed {
+                Ok((parsed, incomplete)) => {
+                    notices.extend(parsed);
+                    dropped += incomplete;
+                }
+                Err(err) => warn!("Skipping {}: {err:#}", path.display()),
+            }
+        }
+
+        Ok(Fetched {
+            notices,
+            dropped,
+            high_water_mark: files.pop().or_else(|| high_water_mark.map(str::to_string)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ingest::source::{DirectorySource, TenderSource};
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn notices_dir() -> Result<TempDir> {
+        let dir = tempfile::tempdir()?;
+
+        fs::write(
+            dir.path().join("2023-02-10-list.json"),
+            include_str!("fixtures/seap/notice_list.json"),
+        )?;
+        fs::write(
+            dir.path().join("2023-02-11-cn.xml"),
+            include_str!("../../tests/fixtures/ted/cn_multiple_lots.xml"),
+        )?;
+        fs::write(
+            dir.path().join("2023-02-11-undated.json"),
+            r#"{"items": [{"noticeNo": "CN1", "contractTitle": "Fara data"}]}"#,
+        )?;
+        fs::write(dir.path().join("2023-02-12-broken.json"), "{\"items\": 3")?;
+        fs::write(dir.path().join("README.txt"), "not a notice")?;
+        fs::create_dir(dir.path().join("2023-02-13-archive.json"))?;
+
+        Ok(dir)
+    }
+
+    #[tokio::test]
+    async fn test_directory_source() -> Result<()> {
+        let dir = notices_dir()?;
+        let source = DirectorySource::new(dir.path());
+
+        {
+            let fetched = source.fetch(None).await?;
+
+            assert_eq!(fetched.notices.len(), 4);
+            assert_eq!(fetched.dropped, 1);
+            assert_eq!(fetched.notices[3].lots.len(), 3);
+            assert_eq!(
+                fetched.high_water_mark.as_deref(),
+                Some("2023-02-12-broken.json")
+            );
+        }
+
+        {
+            let fetched = source.fetch(Some("2023-02-10-list.json")).await?;
+
+            assert_eq!(fetched.notices.len(), 1);
+            assert_eq!(fetched.dropped, 1);
+        }
+
+        {
+            let fetched = source.fetch(Some("2023-02-12-broken.json")).await?;
+
+            assert!(fetched.notices.is_empty());
+            assert_eq!(
+                fetched.high_water_mark.as_deref(),
+                Some("2023-02-12-broken.json")
+            );
+        }
+
+        {
+            let source = DirectorySource::new(dir.path().join("missing"));
+
+            assert!(source.fetch(None).await.is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,583 @@
+//! eForms contract notices, as published on TED (Tenders Electronic Daily).
+//!
+//! eForms notices are UBL 2.3 XML documents. They are read as a stream of events keeping only
+//! the path to the current element, so a notice is never held in memory as a tree. The notice
+//! becomes a [`Tender`] and each of its `cac:ProcurementProjectLot`s a [`Lot`]; the buyer is
+//! looked up among the organizations listed in the notice extension, and locations are NUTS
+//! codes resolved to counties.
+
+use crate::{
+    counties, cpv, cui,
+    ingest::{parse_datetime, Notice},
+    models::{Lot, Tender},
+};
+use anyhow::{Context, Error, Result};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+use time::OffsetDateTime;
+use tokio::task;
+
+const ROOT: &str = "ContractNotice";
+
+/// Labels of the eForms `procurement-procedure-type` codes.
+const PROCEDURES: [(&str, &str); 8] = [
+    ("open", "Open procedure"),
+    ("restricted", "Restricted procedure"),
+    (
+        "neg-w-call",
+        "Negotiated with prior publication of a call for competition",
+    ),
+    (
+        "neg-wo-call",
+        "Negotiated without prior call for competition",
+    ),
+    ("comp-dial", "Competitive dialogue"),
+    ("innovation", "Innovation partnership"),
+    ("oth-single", "Other single stage procedure"),
+    ("oth-mult", "Other multiple stage procedure"),
+];
+
+type Attributes = Vec<(String, String)>;
+
+#[derive(Debug, Default)]
+struct Project {
+    name: Option<String>,
+    description: Option<String>,
+    estimated_value: Option<i64>,
+    currency: Option<String>,
+    cpv_code: Option<String>,
+    nuts: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct LotFields {
+    id: Option<String>,
+    is_part: bool,
+    project: Project,
+    deadline_date: Option<String>,
+    deadline_time: Option<String>,
+    clarification_date: Option<String>,
+    clarification_time: Option<String>,
+    opening_date: Option<String>,
+    opening_time: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Organization {
+    id: Option<String>,
+    name: Option<String>,
+    company_id: Option<String>,
+    nuts: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct NoticeFields {
+    notice_id: Option<String>,
+    publication_id: Option<String>,
+    issue_date: Option<String>,
+    issue_time: Option<String>,
+    procedure_code: Option<String>,
+    buyers: Vec<String>,
+    organizations: Vec<Organization>,
+    project: Project,
+    lots: Vec<LotFields>,
+}
+
+pub fn parse(xml: &str) -> Result<Notice> {
+    parse_reader(xml.as_bytes())
+}
+
+/// Parses a notice read incrementally, e.g. from a buffered file.
+pub fn parse_reader(reader: impl BufRead) -> Result<Notice> {
+    let mut reader = Reader::from_reader(reader);
+    reader.trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut attributes: Vec<Attributes> = Vec::new();
+    let mut text = String::new();
+    let mut fields = NoticeFields::default();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .context("Invalid eForms notice XML.")?;
+
+        // an empty element is handled as a start immediately followed by its end
+        if let Event::Start(e) | Event::Empty(e) = &event {
+            path.push(local_name(e.local_name().as_ref())?);
+            attributes.push(read_attributes(e)?);
+            text.clear();
+
+            if path.len() == 1 && path[0] != ROOT {
+                return Err(Error::msg(format!(
+                    "Expected an eForms contract notice, found {}.",
+                    path[0]
+                )));
+            }
+
+            fields.start(&names(&path));
+        }
+
+        match event {
+            Event::Text(e) => text.push_str(&e.unescape()?),
+            Event::CData(e) => text.push_str(std::str::from_utf8(&e.into_inner())?),
+            Event::End(_) | Event::Empty(_) => {
+                let attributes = attributes.pop().unwrap_or_default();
+
+                fields.end(&names(&path), &attributes, text.trim());
+
+                path.pop();
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    if !path.is_empty() {
+        return Err(Error::msg("Truncated eForms notice XML."));
+    }
+
+    fields.notice()
+}
+
+/// Streams the notice from the file on a blocking thread.
+pub async fn read(path: impl AsRef<Path>) -> Result<Notice> {
+    let path = path.as_ref().to_path_buf();
+
+    task::spawn_blocking(move || parse_reader(BufReader::new(File::open(path)?))).await?
+}
+
+impl NoticeFields {
+    fn start(&mut self, path: &[&str]) {
+        match path {
+            [_, "ProcurementProjectLot"] => self.lots.push(LotFields::default()),
+            [_, .., "Organizations", "Organization"] => {
+                self.organizations.push(Organization::default())
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, path: &[&str], attributes: &Attributes, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        let text = Some(text.to_string());
+
+        if let Some(rest) = after(path, &["Organizations", "Organization", "Company"]) {
+            if let Some(organization) = self.organizations.last_mut() {
+                organization.end(rest, text);
+            }
+
+            return;
+        }
+
+        match path {
+            [_, "ID"] => self.notice_id = text,
+            [_, "IssueDate"] => self.issue_date = text,
+            [_, "IssueTime"] => self.issue_time = text,
+            [_, "TenderingProcess", "ProcedureCode"] => self.procedure_code = text,
+            [_, "ContractingParty", "Party", "PartyIdentification", "ID"] => {
+                self.buyers.extend(text)
+            }
+            [_, "ProcurementProject", rest @ ..] => self.project.end(rest, attributes, text),
+            [_, "ProcurementProjectLot", rest @ ..] => {
+                if let Some(lot) = self.lots.last_mut() {
+                    lot.end(rest, attributes, text);
+                }
+            }
+            [_, .., "NoticePublicationID"] => self.publication_id = text,
+            _ => {}
+        }
+    }
+
+    fn notice(self) -> Result<Notice> {
+        let notice_number = self
+            .publication_id
+            .or(self.notice_id)
+            .ok_or_else(|| Error::msg("eForms notice without an identifier."))?;
+
+        let lots: Vec<LotFields> = self.lots.into_iter().filter(|l| !l.is_part).collect();
+
+        let title = self
+            .project
+            .name
+            .or_else(|| lots.iter().find_map(|l| l.project.name.clone()))
+            .ok_or_else(|| Error::msg(format!("eForms notice {notice_number} has no title.")))?;
+
+        let publication_date = datetime(self.issue_date.as_deref(), self.issue_time.as_deref())
+            .ok_or_else(|| {
+                Error::msg(format!("eForms notice {notice_number} has no issue date."))
+            })?;
+
+        let buyer = self.buyers.first().and_then(|id| {
+            self.organizations
+                .iter()
+                .find(|o| o.id.as_ref() == Some(id))
+        });
+
+        // a notice without an overall value is worth what its lots are
+        let estimated_value = self.project.estimated_value.or_else(|| {
+            lots.iter()
+                .map(|l| l.project.estimated_value)
+                .sum::<Option<i64>>()
+                .filter(|_| !lots.is_empty())
+        });
+
+        let currency = self
+            .project
+            .currency
+            .or_else(|| lots.iter().find_map(|l| l.project.currency.clone()))
+            .unwrap_or_else(|| "RON".into());
+
+        let cpv_code = self
+            .project
+            .cpv_code
+            .or_else(|| lots.iter().find_map(|l| l.project.cpv_code.clone()));
+
+        let county = self
+            .project
+            .nuts
+            .or_else(|| lots.iter().find_map(|l| l.project.nuts.clone()))
+            .or_else(|| buyer.and_then(|b| b.nuts.clone()))
+            .and_then(|nuts| counties::find_by_nuts(&nuts))
+            .map(|county| county.code.to_string());
+
+        let clarification_deadline = lots
+            .iter()
+            .filter_map(|l| {
+                datetime(
+                    l.clarification_date.as_deref(),
+                    l.clarification_time.as_deref(),
+                )
+            })
+            .min();
+
+        let opening_date = lots
+            .iter()
+            .filter_map(|l| datetime(l.opening_date.as_deref(), l.opening_time.as_deref()))
+            .min();
+
+        let lots: Vec<Lot> = lots.into_iter().filter_map(LotFields::lot).collect();
+
+        let tender = Tender {
+            id: 0,
+            notice_number,
+            title,
+            description: self.project.description,
+            additional_info: None,
+            estimated_value,
+            currency,
+            contracting_authority: buyer.and_then(|b| b.name.clone()),
+            cpv_code,
+            publication_date,
+            submission_deadline: lots.iter().filter_map(|l| l.submission_deadline).min(),
+            procedure_type: self.procedure_code.map(|code| procedure(&code)),
+            county,
+            contracting_authority_cui: buyer
+                .and_then(|b| b.company_id.as_deref())
+                .filter(|c| cui::is_valid(c))
+                .map(cui::normalize),
+            clarification_deadline,
+            opening_date,
+        };
+
+        Ok(Notice { tender, lots })
+    }
+}
+
+impl Project {
+    fn end(&mut self, path: &[&str], attributes: &Attributes, text: Option<String>) {
+        match path {
+            ["Name"] => localized(&mut self.name, attributes, text),
+            ["Description"] => localized(&mut self.description, attributes, text),
+            ["RequestedTenderTotal", "EstimatedOverallContractAmount"] => {
+                self.estimated_value = text.as_deref().and_then(amount);
+                self.currency = attribute(attributes, "currencyID");
+            }
+            ["MainCommodityClassification", "ItemClassificationCode"]
+                if attribute(attributes, "listName").as_deref() == Some("cpv") =>
+            {
+                self.cpv_code = text.as_deref().and_then(cpv_code)
+            }
+            ["RealizedLocation", "Address", "CountrySubentityCode"] if self.nuts.is_none() => {
+                self.nuts = text
+            }
+            _ => {}
+        }
+    }
+}
+
+impl LotFields {
+    fn end(&mut self, path: &[&str], attributes: &Attributes, text: Option<String>) {
+        match path {
+            ["ID"] => {
+                self.id = text;
+                self.is_part = attribute(attributes, "schemeName").as_deref() == Some("Part");
+            }
+            ["ProcurementProject", rest @ ..] => self.project.end(rest, attributes, text),
+            ["TenderingProcess", "TenderSubmissionDeadlinePeriod", "EndDate"] => {
+                self.deadline_date = text
+            }
+            ["TenderingProcess", "TenderSubmissionDeadlinePeriod", "EndTime"] => {
+                self.deadline_time = text
+            }
+            ["TenderingProcess", "AdditionalInformationRequestPeriod", "EndDate"] => {
+                self.clarification_date = text
+            }
+            ["TenderingProcess", "AdditionalInformationRequestPeriod", "EndTime"] => {
+                self.clarification_time = text
+            }
+            ["TenderingProcess", "OpenTenderEvent", "OccurrenceDate"] => self.opening_date = text,
+            ["TenderingProcess", "OpenTenderEvent", "OccurrenceTime"] => self.opening_time = text,
+            _ => {}
+        }
+    }
+
+    fn lot(self) -> Option<Lot> {
+        Some(Lot {
+            id: 0,
+            id_tender: 0,
+            lot_number: self.id?,
+            title: self.project.name,
+            description: self.project.description,
+            estimated_value: self.project.estimated_value,
+            cpv_code: self.project.cpv_code,
+            submission_deadline: datetime(
+                self.deadline_date.as_deref(),
+                self.deadline_time.as_deref(),
+            ),
+        })
+    }
+}
+
+impl Organization {
+    fn end(&mut self, path: &[&str], text: Option<String>) {
+        match path {
+            ["PartyIdentification", "ID"] => self.id = text,
+            ["PartyName", "Name"] if self.name.is_none() => self.name = text,
+            ["PartyLegalEntity", "CompanyID"] if self.company_id.is_none() => {
+                self.company_id = text
+            }
+            ["PostalAddress", "CountrySubentityCode"] => self.nuts = text,
+            _ => {}
+        }
+    }
+}
+
+/// Multilingual texts repeat the element once per language; Romanian wins over the others.
+fn localized(slot: &mut Option<String>, attributes: &Attributes, text: Option<String>) {
+    if slot.is_none() || attribute(attributes, "languageID").as_deref() == Some("RON") {
+        *slot = text;
+    }
+}
+
+fn names(path: &[String]) -> Vec<&str> {
+    path.iter().map(String::as_str).collect()
+}
+
+/// The part of the path below the first occurrence of `elements`.
+fn after<'a>(path: &'a [&'a str], elements: &[&str]) -> Option<&'a [&'a str]> {
+    path.windows(elements.len())
+        .position(|window| window == elements)
+        .map(|i| &path[i + elements.len()..])
+}
+
+fn local_name(name: &[u8]) -> Result<String> {
+    Ok(std::str::from_utf8(name)?.to_string())
+}
+
+fn read_attributes(element: &BytesStart) -> Result<Attributes> {
+    element
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute?;
+
+            Ok((
+                local_name(attribute.key.local_name().as_ref())?,
+                attribute.unescape_value()?.into_owned(),
+            ))
+        })
+        .collect()
+}
+
+fn attribute(attributes: &Attributes, name: &str) -> Option<String> {
+    attributes
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone())
+}
+
+/// Amounts are `xs:decimal`s, always written with a dot and no grouping.
+fn amount(text: &str) -> Option<i64> {
+    text.parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(|value| value.round() as i64)
+}
+
+/// eForms leaves the check digit out of CPV codes (`30213100`).
+fn cpv_code(code: &str) -> Option<String> {
+    if cpv::is_valid(code) {
+        return Some(code.to_string());
+    }
+
+    cpv::check_digit(code).map(|check| format!("{code}-{check}"))
+}
+
+fn procedure(code: &str) -> String {
+    PROCEDURES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map_or(code, |(_, label)| label)
+        .to_string()
+}
+
+/// Timestamps are split into an `xs:date` and an `xs:time` that each carry an optional offset,
+/// as `2023-02-10+02:00` and `10:00:00+02:00`.
+fn datetime(date: Option<&str>, time: Option<&str>) -> Option<OffsetDateTime> {
+    let date = date?;
+    let day = date.get(..10)?;
+    let offset = &date[10..];
+
+    let time = match time {
+        Some(time)
+            if time
+                .get(8..)
+                .is_some_and(|zone| zone.contains(['Z', '+', '-'])) =>
+        {
+            time.to_string()
+        }
+        Some(time) => format!("{time}{offset}"),
+        None => format!("00:00:00{offset}"),
+    };
+
+    parse_datetime(&format!("{day}T{time}"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ingest::ted::{cpv_code, datetime, parse, procedure, read};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_parse() {
+        let notice = parse(include_str!("../../tests/fixtures/ted/cn_single_lot.xml")).unwrap();
+
+        assert_eq!(notice.tender.notice_number, "00098765-2023");
+        assert_eq!(notice.tender.county.as_deref(), Some("CJ"));
+        assert_eq!(
+            notice.tender.contracting_authority_cui.as_deref(),
+            Some("4305857")
+        );
+        assert_eq!(notice.lots.len(), 1);
+        assert_eq!(
+            notice.tender.submission_deadline,
+            notice.lots[0].submission_deadline
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read() {
+        let dir = env!("CARGO_MANIFEST_DIR");
+
+        let notice = read(format!("{dir}/tests/fixtures/ted/cn_multiple_lots.xml"))
+            .await
+            .unwrap();
+        let expected = parse(include_str!(
+            "../../tests/fixtures/ted/cn_multiple_lots.xml"
+        ))
+        .unwrap();
+
+        assert_eq!(notice, expected);
+        assert!(read(format!("{dir}/tests/fixtures/ted/missing.xml"))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_other_documents() {
+        let err = parse(r#"<?xml version="1.0"?><PriorInformationNotice/>"#).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Expected an eForms contract notice, found PriorInformationNotice."
+        );
+
+        let err = parse("<ContractNotice><cbc:ID>1</cbc:ID>").unwrap_err();
+
+        assert_eq!(err.to_string(), "Truncated eForms notice XML.");
+
+        let err = parse("<ContractNotice><cbc:ID>1</cbc:Name>").unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid eForms notice XML.");
+
+        let err = parse("<ContractNotice><cbc:ID>1</cbc:ID><cac:ProcurementProject><cbc:Name>x</cbc:Name></cac:ProcurementProject></ContractNotice>").unwrap_err();
+
+        assert_eq!(err.to_string(), "eForms notice 1 has no issue date.");
+    }
+
+    #[test]
+    fn test_datetime() {
+        let cases = [
+            (
+                ("2023-02-10+02:00", Some("10:00:00+02:00")),
+                Some(datetime!(2023-02-10 08:00 UTC)),
+            ),
+            (
+                ("2023-02-10Z", Some("10:00:00Z")),
+                Some(datetime!(2023-02-10 10:00 UTC)),
+            ),
+            (
+                ("2023-02-10+02:00", Some("10:00:00")),
+                Some(datetime!(2023-02-10 08:00 UTC)),
+            ),
+            (
+                ("2023-02-10+02:00", Some("10:00:00.5+02:00")),
+                Some(datetime!(2023-02-10 08:00:00.5 UTC)),
+            ),
+            (
+                ("2023-02-10+02:00", None),
+                Some(datetime!(2023-02-09 22:00 UTC)),
+            ),
+            (
+                ("2023-07-10", Some("10:00:00")),
+                Some(datetime!(2023-07-10 07:00 UTC)),
+            ),
+            (("10.02.2023", None), None),
+        ];
+
+        for ((date, time), expected) in cases {
+            assert_eq!(datetime(Some(date), time), expected, "{date} {time:?}");
+        }
+
+        assert_eq!(datetime(None, Some("10:00:00")), None);
+    }
+
+    #[test]
+    fn test_cpv_code() {
+        assert_eq!(cpv_code("30213100"), Some("30213100-6".into()));
+        assert_eq!(cpv_code("30213100-6"), Some("30213100-6".into()));
+        assert_eq!(cpv_code("3021310"), None);
+        assert_eq!(cpv_code("3021310X"), None);
+    }
+
+    #[test]
+    fn test_procedure() {
+        assert_eq!(procedure("open"), "Open procedure");
+        assert_eq!(procedure("oth-mult"), "Other multiple stage procedure");
+        assert_eq!(procedure("unknown-code"), "unknown-code");
+    }
+}
@@ -1,15 +1,30 @@
+pub mod calendar;
+pub mod counties;
+pub mod cpv;
+pub mod cui;
+pub mod feed;
 pub mod handlers;
+pub mod ingest;
+pub mod matcher;
+pub mod matching;
 pub mod models;
+pub mod notify;
+pub mod procedure;
+pub mod revision;
 pub mod utils;
+pub mod webhooks;
 
 use axum::{
     routing::{delete, get, post, put},
     Router,
 };
+use ingest::{poller::Schedule, source::DirectorySource};
+use matching::score::Weights;
+use notify::mailer::{Mailer, SmtpConfig};
 use shuttle_service::tracing::info;
-use sqlx::{query, Executor, PgPool};
+use sqlx::{Executor, PgPool};
 use sync_wrapper::SyncWrapper;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::CorsLayer;
 
 #[shuttle_service::main]
 async fn axum(#[shuttle_shared_db::Postgres] pool: PgPool) -> shuttle_service::ShuttleAxum {
@@ -27,20 +42,152 @@ async fn axum(#[shuttle_shared_db::Postgres] pool: PgPool) -> shuttle_service::S
     .await
     .expect("Couldn't execute subscriptions.sql");
 
+    pool.execute(include_str!("../migrations/20230210174012_tenders.sql"))
+        .await
+        .expect("Couldn't execute tenders.sql");
+
+    pool.execute(include_str!("../migrations/20230216090000_tender_lots.sql"))
+        .await
+        .expect("Couldn't execute tender_lots.sql");
+
+    pool.execute(include_str!(
+        "../migrations/20230218100000_tender_revisions.sql"
+    ))
+    .await
+    .expect("Couldn't execute tender_revisions.sql");
+
+    pool.execute(include_str!(
+        "../migrations/20230220080000_source_cursors.sql"
+    ))
+    .await
+    .expect("Couldn't execute source_cursors.sql");
+
+    pool.execute(include_str!("../migrations/20230222090000_matches.sql"))
+        .await
+        .expect("Couldn't execute matches.sql");
+
+    pool.execute(include_str!("../migrations/20230224090000_webhooks.sql"))
+        .await
+        .expect("Couldn't execute webhooks.sql");
+
+    pool.execute(include_str!("../migrations/20230226090000_feed_tokens.sql"))
+        .await
+        .expect("Couldn't execute feed_tokens.sql");
+
+    pool.execute(include_str!("../migrations/20230228090000_bookmarks.sql"))
+        .await
+        .expect("Couldn't execute bookmarks.sql");
+
+    pool.execute(include_str!("../migrations/20230214101500_cpv.sql"))
+        .await
+        .expect("Couldn't execute cpv.sql");
+
+    let vocabulary = cpv::bundled().expect("Invalid bundled CPV vocabulary");
+
+    models::cpv::insert_missing(&pool, &vocabulary)
+        .await
+        .expect("Couldn't load the CPV vocabulary");
+
     info!("Succesfully loaded schema into db.");
 
+    if let Ok(dir) = std::env::var("TENDER_SOURCE_DIR") {
+        ingest::poller::spawn(
+            pool.clone(),
+            DirectorySource::new(dir),
+            Schedule::from_env(),
+            Weights::from_env(),
+        );
+    }
+
+    if let Some(config) = SmtpConfig::from_env().expect("Invalid SMTP configuration") {
+        let mailer = Mailer::new(&config).expect("Couldn't set up the SMTP transport");
+
+        notify::spawn(pool.clone(), mailer, notify::interval_from_env());
+    }
+
+    webhooks::spawn(
+        pool.clone(),
+        webhooks::Retry::from_env(),
+        webhooks::interval_from_env(),
+    );
+
     let router = Router::new()
         .route("/subscriptions", post(handlers::create_subscription))
         .route("/subscriptions", get(handlers::get_subscriptions))
+        .route(
+            "/subscriptions/preview",
+            post(handlers::preview_subscription),
+        )
         .route("/subscriptions/:id", get(handlers::get_subscription_by_id))
         .route("/subscriptions/:id", put(handlers::update_subscription))
         .route("/subscriptions/:id", delete(handlers::delete_subscription))
+        .route(
+            "/subscriptions/:id/pause",
+            post(handlers::pause_subscription),
+        )
+        .route(
+            "/subscriptions/:id/resume",
+            post(handlers::resume_subscription),
+        )
+        .route(
+            "/subscriptions/:id/matches",
+            get(handlers::get_subscription_matches),
+        )
+        .route(
+            "/subscriptions/:id/tenders/:id_tender/explanation",
+            get(handlers::explain_match),
+        )
         //
         .route("/users", post(handlers::create_user))
         .route("/users/:id", get(handlers::get_user_by_id))
         .route("/users", get(handlers::get_users))
         .route("/users/:id", put(handlers::update_user))
         .route("/users/:id", delete(handlers::delete_user))
+        .route("/users/:id/matches", get(handlers::get_user_matches))
+        .route(
+            "/users/:id/preferences",
+            get(handlers::get_user_preferences),
+        )
+        .route(
+            "/users/:id/preferences",
+            put(handlers::update_user_preferences),
+        )
+        .route("/users/:id/webhooks", post(handlers::create_webhook))
+        .route("/users/:id/webhooks", get(handlers::get_user_webhooks))
+        .route("/users/:id/feed_token", post(handlers::create_feed_token))
+        .route("/users/:id/feed.atom", get(handlers::get_atom_feed))
+        .route("/users/:id/feed.rss", get(handlers::get_rss_feed))
+        .route("/users/:id/deadlines.ics", get(handlers::get_calendar))
+        .route("/users/:id/bookmarks", get(handlers::get_user_bookmarks))
+        .route(
+            "/users/:id/bookmarks/:id_tender",
+            post(handlers::create_bookmark),
+        )
+        .route(
+            "/users/:id/bookmarks/:id_tender",
+            delete(handlers::delete_bookmark),
+        )
+        //
+        .route("/webhooks/:id", put(handlers::update_webhook))
+        .route("/webhooks/:id", delete(handlers::delete_webhook))
+        .route(
+            "/webhooks/:id/deliveries",
+            get(handlers::get_webhook_deliveries),
+        )
+        //
+        .route("/tenders", post(handlers::create_tender))
+        .route("/tenders", get(handlers::get_tenders))
+        .route("/tenders/import", post(handlers::import_tenders))
+        .route("/tenders/:id", get(handlers::get_tender_by_id))
+        .route("/tenders/:id/lots", get(handlers::get_tender_lots))
+        .route(
+            "/tenders/:id/revisions",
+            get(handlers::get_tender_revisions),
+        )
+        .route("/tenders/:id", put(handlers::update_tender))
+        .route("/tenders/:id", delete(handlers::delete_tender))
+        //
+        .route("/cpv/:code", get(handlers::get_cpv_code))
         .with_state(pool)
         .layer(CorsLayer::permissive());
 
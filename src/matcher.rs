@@ -0,0 +1,591 @@
+//! Matching of ingested tenders against every subscription.
+//!
+//! Each match is recorded once per subscription and tender, so running the matcher again over
+//! the same tenders only records the matches it didn't find before. The matches of a materially
+//! revised tender are evaluated again: those that still hold get the new score and explanation
+//! and are reset to not notified, since subscribers should hear about the new deadline or value,
+//! while those that no longer hold are dropped. New and updated matches are queued for the
+//! users' [`webhooks`].
+//!
+//! Matches are [`score`]d, and those below the subscription's minimum score aren't recorded.
+//! Paused subscriptions aren't matched until they resume. A [`preview`] runs an unsaved
+//! subscription over the recent tenders without recording anything.
+
+use crate::{
+    ingest::Ingested,
+    matching::{
+        score::{score, Weights},
+        MatchResult, Prepared,
+    },
+    models::{matches, subscription, tender, webhook::WebhookEvent, Match, Subscription, Tender},
+    webhooks,
+};
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+use shuttle_service::tracing::warn;
+use sqlx::{types::Json, PgPool};
+use time::{Duration, OffsetDateTime};
+
+/// How far back and how many tenders a [`preview`] shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PreviewOptions {
+    pub days: u32,
+    pub sample: u32,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        PreviewOptions {
+            days: 30,
+            sample: 10,
+        }
+    }
+}
+
+impl PreviewOptions {
+    pub const MAX_DAYS: u32 = 365;
+    pub const MAX_SAMPLE: u32 = 100;
+
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=Self::MAX_DAYS).contains(&self.days) {
+            return Err(Error::msg(format!(
+                "days must be between 1 and {}.",
+                Self::MAX_DAYS
+            )));
+        }
+
+        if self.sample > Self::MAX_SAMPLE {
+            return Err(Error::msg(format!(
+                "sample can't be greater than {}.",
+                Self::MAX_SAMPLE
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Preview {
+    /// How many tenders published in the previewed period would have matched.
+    pub count: usize,
+    /// The best scoring of them.
+    pub sample: Vec<PreviewMatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PreviewMatch {
+    pub tender: Tender,
+    pub score: i32,
+    pub explanation: MatchResult,
+}
+
+/// Matches the tenders against all subscriptions, returning the matches that weren't recorded
+/// before.
+pub async fn run(pool: &PgPool, tenders: &[Tender], weights: &Weights) -> Result<Vec<Match>> {
+    let subscriptions = subscription::get_all(pool).await?;
+    let now = OffsetDateTime::now_utc();
+
+    let mut prepared = Vec::new();
+
+    for s in subscriptions.iter().filter(|s| !s.is_paused(now)) {
+        match Prepared::new(s) {
+            Ok(p) => prepared.push(p),
+            Err(err) => warn!("The query of subscription {} doesn't parse: {err}", s.id),
+        }
+    }
+
+    let mut created = Vec::new();
+
+    for t in tenders {
+        for p in &prepared {
+            let Some((score, res)) = evaluate(p, t, now, weights) else {
+                continue;
+            };
+            let s = p.sub;
+
+            let m = Match {
+                id: 0,
+                id_subscription: s.id,
+                id_tender: t.id,
+                matched_fields: res.matched_fields().into_iter().map(String::from).collect(),
+                score: Some(score),
+                explanation: Json(res),
+                created_at: now,
+                notified_at: None,
+            };
+
+            created.extend(matches::create(pool, &m).await?);
+        }
+    }
+
+    webhooks::enqueue(pool, WebhookEvent::MatchCreated, &created).await?;
+
+    Ok(created)
+}
+
+/// Runs the matcher over the notifiable tenders an ingestion produced.
+pub async fn record_ingested(
+    pool: &PgPool,
+    ingested: &[Ingested],
+    weights: &Weights,
+) -> Result<Vec<Match>> {
+    let notifiable: Vec<&Ingested> = ingested.iter().filter(|i| i.is_notifiable()).collect();
+
+    let revised: Vec<&Tender> = notifiable
+        .iter()
+        .filter(|i| i.revision.is_some())
+        .map(|i| &i.tender)
+        .collect();
+
+    if !revised.is_empty() {
+        let updated = rematch(pool, &revised, weights).await?;
+
+        webhooks::enqueue(pool, WebhookEvent::MatchUpdated, &updated).await?;
+    }
+
+    let tenders: Vec<Tender> = notifiable.iter().map(|i| i.tender.clone()).collect();
+
+    run(pool, &tenders, weights).await
+}
+
+/// Evaluates the recorded matches of revised tenders again, updating those that still hold and
+/// deleting the others. Returns the updated matches.
+async fn rematch(pool: &PgPool, tenders: &[&Tender], weights: &Weights) -> Result<Vec<Match>> {
+    let subscriptions = subscription::get_all(pool).await?;
+    let now = OffsetDateTime::now_utc();
+
+    let mut updated = Vec::new();
+
+    for t in tenders {
+        for m in matches::get_all_of_tender(pool, t.id).await? {
+            let Some(s) = subscriptions.iter().find(|s| s.id == m.id_subscription) else {
+                continue;
+            };
+
+            let p = match Prepared::new(s) {
+                Ok(p) => p,
+                Err(err) => {
+                    warn!("The query of subscription {} doesn't parse: {err}", s.id);
+                    continue;
+                }
+            };
+
+            match evaluate(&p, t, now, weights) {
+                Some((score, res)) => {
+                    let m = Match {
+                        matched_fields: res
+                            .matched_fields()
+                            .into_iter()
+                            .map(String::from)
+                            .collect(),
+                        score: Some(score),
+                        explanation: Json(res),
+                        ..m
+                    };
+
+                    updated.push(matches::revise(pool, &m).await?);
+                }
+                None => {
+                    matches::delete(pool, m.id).await?;
+                }
+            }
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Matches a subscription that may not be saved yet against the tenders published in the
+/// last `options.days` days, recording nothing.
+pub async fn preview(
+    pool: &PgPool,
+    sub: &Subscription,
+    options: &PreviewOptions,
+    weights: &Weights,
+) -> Result<Preview> {
+    let now = OffsetDateTime::now_utc();
+    let since = now - Duration::days(i64::from(options.days));
+    let prepared = Prepared::new(sub)?;
+
+    let mut matched: Vec<PreviewMatch> = tender::get_published_since(pool, since)
+        .await?
+        .into_iter()
+        .filter_map(|t| {
+            let (score, explanation) = evaluate(&prepared, &t, now, weights)?;
+
+            Some(PreviewMatch {
+                tender: t,
+                score,
+                explanation,
+            })
+        })
+        .collect();
+
+    let count = matched.len();
+
+    // the tenders are newest first, which the stable sort keeps among equal scores
+    matched.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matched.truncate(options.sample as usize);
+
+    Ok(Preview {
+        count,
+        sample: matched,
+    })
+}
+
+/// The score and explanation of a match, or `None` when the tender doesn't match or scores
+/// below the subscription's minimum.
+fn evaluate(
+    prepared: &Prepared,
+    tender: &Tender,
+    now: OffsetDateTime,
+    weights: &Weights,
+) -> Option<(i32, MatchResult)> {
+    let sub = prepared.sub;
+    let res = prepared.matches_at(tender, now);
+
+    if !res.is_match() {
+        return None;
+    }
+
+    let score = score(sub, tender, &res, weights);
+
+    if sub.min_score.is_some_and(|min_score| score < min_score) {
+        return None;
+    }
+
+    Some((score, res))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ingest::store,
+        matcher::{preview, record_ingested, run, PreviewOptions},
+        matching::score::Weights,
+        models::{matches, tender, Subscription, Tender},
+        utils::Pagination,
+    };
+    use anyhow::Result;
+    use sqlx::{query, Executor, PgPool};
+    use time::{macros::datetime, Duration, OffsetDateTime};
+
+    async fn subscriptions(pool: &PgPool) -> Result<()> {
+        pool.execute(include_str!("models/fixtures/users.sql"))
+            .await?;
+        pool.execute(include_str!("models/fixtures/subscriptions.sql"))
+            .await?;
+
+        Ok(())
+    }
+
+    fn tender() -> Tender {
+        Tender {
+            id: 0,
+            description: None,
+            contracting_authority: None,
+            cpv_code: None,
+            submission_deadline: Some(datetime!(2023-03-01 12:00 UTC)),
+            procedure_type: None,
+            county: None,
+            contracting_authority_cui: None,
+            ..tender::sample()
+        }
+    }
+
+    fn pagination() -> Pagination {
+        Pagination {
+            start_index: 0,
+            count: 10,
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_run(pool: PgPool) -> Result<()> {
+        subscriptions(&pool).await?;
+
+        let t = tender::create(&pool, &tender()).await?;
+
+        let created = run(&pool, std::slice::from_ref(&t), &Weights::default()).await?;
+
+        assert_eq!(created.len(), 3);
+        assert!(created.iter().all(|m| m.id_tender == t.id));
+
+        {
+            let again = run(&pool, std::slice::from_ref(&t), &Weights::default()).await?;
+
+            assert!(again.is_empty());
+        }
+
+        {
+            let recorded = matches::get_all_of_user(&pool, 1, &pagination()).await?;
+
+            assert_eq!(recorded.len(), 2);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_run_invalid_query(pool: PgPool) -> Result<()> {
+        subscriptions(&pool).await?;
+
+        query!("UPDATE subscriptions SET query = 'calculatoare AND' WHERE id = 1")
+            .execute(&pool)
+            .await?;
+
+        let t = tender::create(&pool, &tender()).await?;
+        let created = run(&pool, &[t], &Weights::default()).await?;
+
+        let mut subscriptions: Vec<i32> = created.iter().map(|m| m.id_subscription).collect();
+        subscriptions.sort();
+
+        assert_eq!(subscriptions, vec![2, 3]);
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_run_paused(pool: PgPool) -> Result<()> {
+        subscriptions(&pool).await?;
+
+        query!("UPDATE subscriptions SET paused_until = NOW() + INTERVAL '1 day' WHERE id = 1")
+            .execute(&pool)
+            .await?;
+        query!("UPDATE subscriptions SET paused_until = NOW() - INTERVAL '1 day' WHERE id = 2")
+            .execute(&pool)
+            .await?;
+
+        let t = tender::create(&pool, &tender()).await?;
+        let created = run(&pool, &[t], &Weights::default()).await?;
+
+        let mut subscriptions: Vec<i32> = created.iter().map(|m| m.id_subscription).collect();
+        subscriptions.sort();
+
+        assert_eq!(subscriptions, vec![2, 3]);
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_min_score(pool: PgPool) -> Result<()> {
+        subscriptions(&pool).await?;
+
+        query!("INSERT INTO title_keywords (id_subscription, keyword) VALUES (1, 'portabile')")
+            .execute(&pool)
+            .await?;
+
+        query!("UPDATE subscriptions SET min_score = 70 WHERE id IN (1, 2)")
+            .execute(&pool)
+            .await?;
+
+        let t = tender::create(&pool, &tender()).await?;
+        let created = run(&pool, &[t], &Weights::default()).await?;
+
+        let mut scores: Vec<(i32, Option<i32>)> = created
+            .iter()
+            .map(|m| (m.id_subscription, m.score))
+            .collect();
+        scores.sort();
+
+        assert_eq!(scores, vec![(2, Some(100)), (3, Some(100))]);
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_record_ingested(pool: PgPool) -> Result<()> {
+        subscriptions(&pool).await?;
+
+        let t = tender();
+
+        let ingested = store(&pool, std::slice::from_ref(&t)).await?;
+        let created = record_ingested(&pool, &ingested, &Weights::default()).await?;
+
+        assert_eq!(created.len(), 3);
+
+        query!("UPDATE matches SET notified_at = NOW()")
+            .execute(&pool)
+            .await?;
+
+        {
+            let corrected = Tender {
+                title: "Furnizare calculatoare portabile (erata)".into(),
+                ..t.clone()
+            };
+
+            let ingested = store(&pool, &[corrected]).await?;
+
+            assert!(record_ingested(&pool, &ingested, &Weights::default())
+                .await?
+                .is_empty());
+
+            let recorded = matches::get_all_of_user(&pool, 1, &pagination()).await?;
+
+            assert!(recorded.iter().all(|m| m.notified_at.is_some()));
+        }
+
+        {
+            let extended = Tender {
+                submission_deadline: Some(datetime!(2023-03-08 12:00 UTC)),
+                ..t.clone()
+            };
+
+            let ingested = store(&pool, &[extended]).await?;
+
+            assert!(record_ingested(&pool, &ingested, &Weights::default())
+                .await?
+                .is_empty());
+
+            let recorded = matches::get_all_of_user(&pool, 1, &pagination()).await?;
+
+            assert!(recorded.iter().all(|m| m.notified_at.is_none()));
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_record_ingested_revision_breaks_match(pool: PgPool) -> Result<()> {
+        subscriptions(&pool).await?;
+
+        query!("UPDATE subscriptions SET max_price = 300000 WHERE id = 1")
+            .execute(&pool)
+            .await?;
+        query!("INSERT INTO title_keywords (id_subscription, keyword) VALUES (2, 'portabile')")
+            .execute(&pool)
+            .await?;
+
+        let t = tender();
+
+        let ingested = store(&pool, std::slice::from_ref(&t)).await?;
+        let created = record_ingested(&pool, &ingested, &Weights::default()).await?;
+
+        assert_eq!(created.len(), 3);
+
+        query!("UPDATE matches SET notified_at = NOW()")
+            .execute(&pool)
+            .await?;
+
+        let revalued = Tender {
+            estimated_value: Some(400_000),
+            title: "Furnizare calculatoare desktop".into(),
+            ..t
+        };
+
+        let ingested = store(&pool, &[revalued]).await?;
+
+        assert!(ingested[0].is_notifiable());
+        assert!(record_ingested(&pool, &ingested, &Weights::default())
+            .await?
+            .is_empty());
+
+        let recorded = matches::get_all_of_tender(&pool, ingested[0].tender.id).await?;
+
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].id_subscription, 3);
+        assert_eq!(recorded[0].notified_at, None);
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_preview(pool: PgPool) -> Result<()> {
+        let now = OffsetDateTime::now_utc().replace_nanosecond(0)?;
+
+        let published = [
+            ("CN1", "Furnizare calculatoare portabile", 2),
+            ("CN2", "Furnizare calculatoare si calculatoare desktop", 5),
+            ("CN3", "Servicii de curatenie", 3),
+            ("CN4", "Furnizare calculatoare", 40),
+        ];
+
+        for (notice_number, title, days_ago) in published {
+            let t = Tender {
+                notice_number: notice_number.into(),
+                title: title.into(),
+                publication_date: now - Duration::days(days_ago),
+                ..tender()
+            };
+
+            tender::create(&pool, &t).await?;
+        }
+
+        let sub = Subscription {
+            title_keywords: Some(vec!["calculatoare".into()]),
+            ..Default::default()
+        };
+
+        {
+            let res = preview(&pool, &sub, &PreviewOptions::default(), &Weights::default()).await?;
+
+            let sample: Vec<(&str, i32)> = res
+                .sample
+                .iter()
+                .map(|m| (m.tender.notice_number.as_str(), m.score))
+                .collect();
+
+            assert_eq!(res.count, 2);
+            assert_eq!(sample, vec![("CN2", 80), ("CN1", 60)]);
+            assert_eq!(res.sample[0].explanation.hits.len(), 2);
+        }
+
+        {
+            let options = PreviewOptions {
+                days: 60,
+                sample: 1,
+            };
+
+            let res = preview(&pool, &sub, &options, &Weights::default()).await?;
+
+            assert_eq!(res.count, 3);
+            assert_eq!(res.sample.len(), 1);
+            assert_eq!(res.sample[0].tender.notice_number, "CN2");
+        }
+
+        {
+            let sub = Subscription {
+                min_score: Some(70),
+                ..sub.clone()
+            };
+
+            let res = preview(&pool, &sub, &PreviewOptions::default(), &Weights::default()).await?;
+
+            assert_eq!(res.count, 1);
+        }
+
+        {
+            let res = matches::get_all_of_user(&pool, 1, &pagination()).await?;
+
+            assert!(res.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_preview_options() {
+        assert!(PreviewOptions::default().validate().is_ok());
+
+        let cases = [
+            (0, 10, "days must be between 1 and 365."),
+            (366, 10, "days must be between 1 and 365."),
+            (30, 101, "sample can't be greater than 100."),
+        ];
+
+        for (days, sample, expected) in cases {
+            let err = PreviewOptions { days, sample }.validate().unwrap_err();
+
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+}
@@ -0,0 +1,1488 @@
+//! Evaluation of a [`Subscription`] against a [`Tender`].
+//!
+//! Semantics:
+//! - every criterion left as `None` (or an empty keyword list) imposes no constraint;
+//! - `min_price` and `max_price` are inclusive bounds in RON on the tender's estimated value,
+//!   and a tender without an estimated value, or with one in another currency, fails any price
+//!   bound;
+//! - a keyword list is satisfied when *any* of its keywords occurs in the corresponding
+//!   tender field, and a tender missing that field fails the constraint;
+//! - an exclude keyword list fails the field as soon as *any* of its keywords occurs in it,
+//!   using the same match mode as the field's keyword list;
+//! - keywords and tender text are compared after [`normalize`]-ing both, either as plain
+//!   substrings, as whole words, or as whole words reduced by the [`stem`]mer, depending on
+//!   the [`MatchMode`] of the keyword list;
+//! - a list of CPV codes is satisfied when the tender's CPV code equals or falls under any
+//!   of them (see [`cpv::is_within`]), and a tender without a CPV code fails it;
+//! - a list of counties is satisfied when the tender's county is one of them, and a tender
+//!   without a known county fails it;
+//! - an authority include list is satisfied when the tender's contracting authority is one of
+//!   the listed ones, either by CUI or by its name being one of the aliases, while an
+//!   exclude list fails any tender from a listed authority;
+//! - `min_days_to_deadline` fails tenders whose submission deadline is closer than that many
+//!   days (or unknown), and a list of procedure types fails tenders whose procedure is not
+//!   [`classify`](ProcedureType::classify)-ed as one of them;
+//! - an optional boolean [`query`] must evaluate to true over the tender text; a query that
+//!   no longer parses is an error rather than a failed criterion;
+//! - a tender matches when *all* constrained criteria are satisfied.
+//!
+//! Besides the outcome of every criterion, a [`MatchResult`] lists the keyword [`Hit`]s found
+//! in the fields whose keyword criterion passed, located by character offsets in the original
+//! tender text so they can be highlighted.
+
+pub mod normalize;
+pub mod query;
+pub mod score;
+pub mod stem;
+
+use crate::{
+    counties, cpv, cui,
+    models::{AuthorityFilter, Subscription, Tender},
+    procedure::ProcedureType,
+};
+use normalize::{normalize, normalize_with_spans};
+use query::{Document, ParseError, Query};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use stem::stem;
+use time::{Duration, OffsetDateTime};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "keyword_match_mode", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    Substring,
+    WholeWord,
+    Stemmed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Unconstrained,
+    Passed,
+    Failed,
+}
+
+impl From<bool> for Outcome {
+    fn from(passed: bool) -> Self {
+        match passed {
+            true => Outcome::Passed,
+            false => Outcome::Failed,
+        }
+    }
+}
+
+/// The tender text fields subscriptions filter by keywords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextField {
+    Title,
+    Description,
+    AdditionalInfo,
+}
+
+/// An occurrence of a subscription keyword in a tender field. `start` and `end` are the
+/// character (not byte) offsets of the occurrence in the field's original text, end exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hit {
+    pub keyword: String,
+    pub field: TextField,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchResult {
+    pub price: Outcome,
+    pub title: Outcome,
+    pub description: Outcome,
+    pub additional_info: Outcome,
+    pub query: Outcome,
+    pub cpv: Outcome,
+    pub county: Outcome,
+    pub authority: Outcome,
+    pub deadline: Outcome,
+    pub procedure_type: Outcome,
+    pub hits: Vec<Hit>,
+}
+
+impl MatchResult {
+    pub fn is_match(&self) -> bool {
+        self.fields().iter().all(|(_, o)| *o != Outcome::Failed)
+    }
+
+    /// The names of the constrained criteria the tender satisfied.
+    pub fn matched_fields(&self) -> Vec<&'static str> {
+        self.fields()
+            .into_iter()
+            .filter(|(_, o)| *o == Outcome::Passed)
+            .map(|(field, _)| field)
+            .collect()
+    }
+
+    fn fields(&self) -> [(&'static str, Outcome); 10] {
+        [
+            ("price", self.price),
+            ("title", self.title),
+            ("description", self.description),
+            ("additional_info", self.additional_info),
+            ("query", self.query),
+            ("cpv", self.cpv),
+            ("county", self.county),
+            ("authority", self.authority),
+            ("deadline", self.deadline),
+            ("procedure_type", self.procedure_type),
+        ]
+    }
+}
+
+/// A subscription ready to be evaluated against many tenders, with its query parsed once.
+#[derive(Debug, Clone)]
+pub struct Prepared<'a> {
+    pub sub: &'a Subscription,
+    query: Option<Query>,
+}
+
+impl<'a> Prepared<'a> {
+    pub fn new(sub: &'a Subscription) -> Result<Self, ParseError> {
+        let query = sub.query.as_deref().map(Query::parse).transpose()?;
+
+        Ok(Prepared { sub, query })
+    }
+
+    /// Evaluates the subscription, measuring the time left until the deadline from `now`.
+    pub fn matches_at(&self, tender: &Tender, now: OffsetDateTime) -> MatchResult {
+        evaluate(self.sub, self.query.as_ref(), tender, now)
+    }
+}
+
+pub fn matches(sub: &Subscription, tender: &Tender) -> Result<MatchResult, ParseError> {
+    matches_at(sub, tender, OffsetDateTime::now_utc())
+}
+
+/// Like [`matches`], but measures the time left until the deadline from `now`. Prefer
+/// [`Prepared`] to evaluate a subscription against many tenders.
+pub fn matches_at(
+    sub: &Subscription,
+    tender: &Tender,
+    now: OffsetDateTime,
+) -> Result<MatchResult, ParseError> {
+    Ok(Prepared::new(sub)?.matches_at(tender, now))
+}
+
+fn evaluate(
+    sub: &Subscription,
+    query: Option<&Query>,
+    tender: &Tender,
+    now: OffsetDateTime,
+) -> MatchResult {
+    let fields = [
+        (
+            TextField::Title,
+            sub.title_keywords.as_deref(),
+            sub.title_exclude_keywords.as_deref(),
+            sub.title_match_mode,
+            Some(tender.title.as_str()),
+        ),
+        (
+            TextField::Description,
+            sub.desc_keywords.as_deref(),
+            sub.desc_exclude_keywords.as_deref(),
+            sub.desc_match_mode,
+            tender.description.as_deref(),
+        ),
+        (
+            TextField::AdditionalInfo,
+            sub.additional_info_keywords.as_deref(),
+            sub.additional_info_exclude_keywords.as_deref(),
+            sub.additional_info_match_mode,
+            tender.additional_info.as_deref(),
+        ),
+    ];
+
+    let mut hits = Vec::new();
+
+    let [title, description, additional_info] =
+        fields.map(|(field, keywords, exclude_keywords, mode, text)| {
+            let outcome = match_keywords(keywords, exclude_keywords, mode, text);
+
+            if let (Outcome::Passed, Some(keywords), Some(text)) = (outcome, keywords, text) {
+                hits.extend(keyword_hits(field, keywords, mode, text));
+            }
+
+            outcome
+        });
+
+    MatchResult {
+        price: match_price(sub.min_price, sub.max_price, value_in_ron(tender)),
+        title,
+        description,
+        additional_info,
+        query: match_query(query, tender),
+        cpv: match_cpv(sub.cpv_codes.as_deref(), tender.cpv_code.as_deref()),
+        county: match_county(sub.counties.as_deref(), tender.county.as_deref()),
+        authority: match_authority(
+            sub.included_authorities.as_deref().map(Vec::as_slice),
+            sub.excluded_authorities.as_deref().map(Vec::as_slice),
+            tender,
+        ),
+        deadline: match_deadline(sub.min_days_to_deadline, tender.submission_deadline, now),
+        procedure_type: match_procedure_type(
+            sub.procedure_types.as_deref(),
+            tender.procedure_type.as_deref(),
+        ),
+        hits,
+    }
+}
+
+/// The tender's estimated value when it is expressed in RON, the currency of subscription price
+/// bounds. Values in other currencies aren't converted.
+fn value_in_ron(tender: &Tender) -> Option<i64> {
+    tender
+        .estimated_value
+        .filter(|_| tender.currency.eq_ignore_ascii_case("RON"))
+}
+
+fn match_price(min: Option<i32>, max: Option<i32>, value: Option<i64>) -> Outcome {
+    if min.is_none() && max.is_none() {
+        return Outcome::Unconstrained;
+    }
+
+    let Some(value) = value else {
+        return Outcome::Failed;
+    };
+
+    let above_min = min.is_none_or(|min| value >= i64::from(min));
+    let below_max = max.is_none_or(|max| value <= i64::from(max));
+
+    Outcome::from(above_min && below_max)
+}
+
+fn match_keywords(
+    keywords: Option<&[String]>,
+    exclude_keywords: Option<&[String]>,
+    mode: MatchMode,
+    text: Option<&str>,
+) -> Outcome {
+    let keywords = keywords.unwrap_or_default();
+    let exclude_keywords = exclude_keywords.unwrap_or_default();
+
+    if keywords.is_empty() && exclude_keywords.is_empty() {
+        return Outcome::Unconstrained;
+    }
+
+    let Some(text) = text else {
+        return Outcome::from(keywords.is_empty());
+    };
+
+    let text = normalize(text);
+
+    let haystack = match mode {
+        MatchMode::Substring => Vec::new(),
+        MatchMode::WholeWord => words(&text),
+        MatchMode::Stemmed => stems(&text),
+    };
+
+    let contains = |keyword: &String| {
+        let keyword = normalize(keyword);
+
+        match mode {
+            MatchMode::Substring => !keyword.is_empty() && text.contains(&keyword),
+            MatchMode::WholeWord => contains_sequence(&haystack, &words(&keyword)),
+            MatchMode::Stemmed => contains_sequence(&haystack, &stems(&keyword)),
+        }
+    };
+
+    let included = keywords.is_empty() || keywords.iter().any(contains);
+    let excluded = exclude_keywords.iter().any(contains);
+
+    Outcome::from(included && !excluded)
+}
+
+/// Locates every occurrence of the keywords in the field text, in order of appearance.
+fn keyword_hits(field: TextField, keywords: &[String], mode: MatchMode, text: &str) -> Vec<Hit> {
+    let (text, spans) = normalize_with_spans(text);
+
+    let haystack = match mode {
+        MatchMode::Substring => Vec::new(),
+        MatchMode::WholeWord | MatchMode::Stemmed => word_spans(&text, mode),
+    };
+
+    let mut hits = Vec::new();
+
+    for keyword in keywords {
+        let normalized = normalize(keyword);
+
+        // character ranges of the occurrences in the normalized text
+        let occurrences: Vec<Range<usize>> = match mode {
+            MatchMode::Substring if normalized.is_empty() => Vec::new(),
+            MatchMode::Substring => text
+                .match_indices(&normalized)
+                .map(|(i, _)| {
+                    let start = text[..i].chars().count();
+
+                    start..start + normalized.chars().count()
+                })
+                .collect(),
+            MatchMode::WholeWord | MatchMode::Stemmed => {
+                let needle: Vec<String> = word_spans(&normalized, mode)
+                    .into_iter()
+                    .map(|(word, _)| word)
+                    .collect();
+
+                if needle.is_empty() {
+                    Vec::new()
+                } else {
+                    haystack
+                        .windows(needle.len())
+                        .filter(|window| window.iter().map(|(word, _)| word).eq(needle.iter()))
+                        .map(|window| window[0].1.start..window[window.len() - 1].1.end)
+                        .collect()
+                }
+            }
+        };
+
+        hits.extend(occurrences.into_iter().map(|occurrence| Hit {
+            keyword: keyword.clone(),
+            field,
+            start: spans[occurrence.start].start,
+            end: spans[occurrence.end - 1].end,
+        }));
+    }
+
+    hits.sort_by_key(|hit| (hit.start, hit.end));
+
+    hits
+}
+
+fn match_query(query: Option<&Query>, tender: &Tender) -> Outcome {
+    let Some(query) = query else {
+        return Outcome::Unconstrained;
+    };
+
+    let doc = Document {
+        title: &tender.title,
+        description: tender.description.as_deref(),
+        additional_info: tender.additional_info.as_deref(),
+    };
+
+    Outcome::from(query.evaluate(&doc))
+}
+
+fn match_cpv(codes: Option<&[String]>, code: Option<&str>) -> Outcome {
+    let codes = match codes {
+        Some(codes) if !codes.is_empty() => codes,
+        _ => return Outcome::Unconstrained,
+    };
+
+    let Some(code) = code else {
+        return Outcome::Failed;
+    };
+
+    Outcome::from(codes.iter().any(|ancestor| cpv::is_within(code, ancestor)))
+}
+
+fn match_county(sub_counties: Option<&[String]>, county: Option<&str>) -> Outcome {
+    let sub_counties = match sub_counties {
+        Some(sub_counties) if !sub_counties.is_empty() => sub_counties,
+        _ => return Outcome::Unconstrained,
+    };
+
+    let Some(county) = county.and_then(counties::find) else {
+        return Outcome::Failed;
+    };
+
+    Outcome::from(
+        sub_counties
+            .iter()
+            .any(|c| counties::find(c).is_some_and(|c| c == county)),
+    )
+}
+
+fn match_authority(
+    included: Option<&[AuthorityFilter]>,
+    excluded: Option<&[AuthorityFilter]>,
+    tender: &Tender,
+) -> Outcome {
+    let included = included.unwrap_or_default();
+    let excluded = excluded.unwrap_or_default();
+
+    if included.is_empty() && excluded.is_empty() {
+        return Outcome::Unconstrained;
+    }
+
+    let is_included = included.is_empty() || included.iter().any(|a| is_authority(a, tender));
+    let is_excluded = excluded.iter().any(|a| is_authority(a, tender));
+
+    Outcome::from(is_included && !is_excluded)
+}
+
+fn is_authority(authority: &AuthorityFilter, tender: &Tender) -> bool {
+    let same_cui = tender
+        .contracting_authority_cui
+        .as_deref()
+        .is_some_and(|c| cui::normalize(c) == cui::normalize(&authority.cui));
+
+    let same_name = tender.contracting_authority.as_deref().is_some_and(|name| {
+        let name = normalize(name);
+
+        authority
+            .aliases
+            .iter()
+            .any(|alias| normalize(alias) == name)
+    });
+
+    same_cui || same_name
+}
+
+fn match_deadline(
+    min_days: Option<i32>,
+    deadline: Option<OffsetDateTime>,
+    now: OffsetDateTime,
+) -> Outcome {
+    let Some(min_days) = min_days else {
+        return Outcome::Unconstrained;
+    };
+
+    let Some(deadline) = deadline else {
+        return Outcome::Failed;
+    };
+
+    Outcome::from(deadline - now >= Duration::days(i64::from(min_days)))
+}
+
+fn match_procedure_type(
+    procedure_types: Option<&[ProcedureType]>,
+    procedure: Option<&str>,
+) -> Outcome {
+    let procedure_types = match procedure_types {
+        Some(procedure_types) if !procedure_types.is_empty() => procedure_types,
+        _ => return Outcome::Unconstrained,
+    };
+
+    let Some(procedure_type) = procedure.and_then(ProcedureType::classify) else {
+        return Outcome::Failed;
+    };
+
+    Outcome::from(procedure_types.contains(&procedure_type))
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn stems(text: &str) -> Vec<String> {
+    words(text).iter().map(|word| stem(word)).collect()
+}
+
+/// The words of a normalized text, stemmed in [`MatchMode::Stemmed`], with their character
+/// ranges in the text.
+fn word_spans(text: &str, mode: MatchMode) -> Vec<(String, Range<usize>)> {
+    let chars: Vec<char> = text.chars().collect();
+
+    chars
+        .split(|c| !c.is_alphanumeric())
+        .scan(0, |start, word| {
+            let range = *start..*start + word.len();
+            *start = range.end + 1;
+
+            Some((word, range))
+        })
+        .filter(|(word, _)| !word.is_empty())
+        .map(|(word, range)| {
+            let word: String = word.iter().collect();
+
+            match mode {
+                MatchMode::Stemmed => (stem(&word), range),
+                _ => (word, range),
+            }
+        })
+        .collect()
+}
+
+fn contains_sequence(haystack: &[String], needle: &[String]) -> bool {
+    !needle.is_empty()
+        && haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        matching::{matches, matches_at, Hit, MatchMode, Outcome, TextField},
+        models::{tender, AuthorityFilter, Subscription, Tender},
+        procedure::ProcedureType,
+    };
+    use sqlx::types::Json;
+    use time::macros::datetime;
+
+    fn sub() -> Subscription {
+        Subscription {
+            id: 1,
+            id_user: 1,
+            min_price: None,
+            max_price: None,
+            title_keywords: None,
+            desc_keywords: None,
+            additional_info_keywords: None,
+            ..Default::default()
+        }
+    }
+
+    fn tender() -> Tender {
+        Tender {
+            id: 1,
+            description: Some("Laptopuri pentru unitatile scolare din judet".into()),
+            additional_info: Some("Garantie minima 36 de luni".into()),
+            contracting_authority: Some("Consiliul Judetean Cluj".into()),
+            contracting_authority_cui: Some("4288110".into()),
+            ..tender::sample()
+        }
+    }
+
+    fn keywords(keywords: &[&str]) -> Option<Vec<String>> {
+        Some(keywords.iter().map(|k| k.to_string()).collect())
+    }
+
+    fn authorities(authorities: &[(&str, &[&str])]) -> Option<Json<Vec<AuthorityFilter>>> {
+        let authorities = authorities
+            .iter()
+            .map(|(cui, aliases)| AuthorityFilter {
+                cui: cui.to_string(),
+                aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            })
+            .collect();
+
+        Some(Json(authorities))
+    }
+
+    #[test]
+    fn test_unconstrained() {
+        let res = matches(&sub(), &tender()).unwrap();
+
+        assert!(res.is_match());
+        assert_eq!(res.price, Outcome::Unconstrained);
+        assert_eq!(res.title, Outcome::Unconstrained);
+        assert_eq!(res.description, Outcome::Unconstrained);
+        assert_eq!(res.additional_info, Outcome::Unconstrained);
+        assert_eq!(res.query, Outcome::Unconstrained);
+        assert_eq!(res.cpv, Outcome::Unconstrained);
+        assert_eq!(res.county, Outcome::Unconstrained);
+        assert_eq!(res.authority, Outcome::Unconstrained);
+        assert_eq!(res.deadline, Outcome::Unconstrained);
+        assert_eq!(res.procedure_type, Outcome::Unconstrained);
+    }
+
+    #[test]
+    fn test_price_bounds() {
+        let cases = [
+            (Some(100_000), None, Outcome::Passed),
+            (Some(250_000), None, Outcome::Passed),
+            (Some(250_001), None, Outcome::Failed),
+            (None, Some(300_000), Outcome::Passed),
+            (None, Some(250_000), Outcome::Passed),
+            (None, Some(249_999), Outcome::Failed),
+            (Some(250_000), Some(250_000), Outcome::Passed),
+            (Some(100_000), Some(200_000), Outcome::Failed),
+            (Some(300_000), Some(400_000), Outcome::Failed),
+        ];
+
+        for (min_price, max_price, expected) in cases {
+            let s = Subscription {
+                min_price,
+                max_price,
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.price, expected, "{min_price:?}..={max_price:?}");
+            assert_eq!(res.is_match(), expected == Outcome::Passed);
+        }
+    }
+
+    #[test]
+    fn test_price_without_estimated_value() {
+        let t = Tender {
+            estimated_value: None,
+            ..tender()
+        };
+
+        {
+            let res = matches(&sub(), &t).unwrap();
+
+            assert_eq!(res.price, Outcome::Unconstrained);
+        }
+
+        {
+            let s = Subscription {
+                min_price: Some(0),
+                ..sub()
+            };
+
+            let res = matches(&s, &t).unwrap();
+
+            assert_eq!(res.price, Outcome::Failed);
+            assert!(!res.is_match());
+        }
+    }
+
+    #[test]
+    fn test_price_above_i32() {
+        let t = Tender {
+            estimated_value: Some(i64::from(i32::MAX) + 1),
+            ..tender()
+        };
+
+        let s = Subscription {
+            max_price: Some(i32::MAX),
+            ..sub()
+        };
+
+        assert_eq!(matches(&s, &t).unwrap().price, Outcome::Failed);
+    }
+
+    #[test]
+    fn test_price_in_other_currency() {
+        let t = Tender {
+            estimated_value: Some(150_000),
+            currency: "EUR".into(),
+            ..tender()
+        };
+
+        {
+            let s = Subscription {
+                min_price: Some(100_000),
+                max_price: Some(200_000),
+                ..sub()
+            };
+
+            let res = matches(&s, &t).unwrap();
+
+            assert_eq!(res.price, Outcome::Failed);
+            assert!(!res.is_match());
+        }
+
+        {
+            let res = matches(&sub(), &t).unwrap();
+
+            assert_eq!(res.price, Outcome::Unconstrained);
+            assert!(res.is_match());
+        }
+
+        {
+            let t = Tender {
+                currency: "ron".into(),
+                ..t
+            };
+
+            let s = Subscription {
+                min_price: Some(100_000),
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().price, Outcome::Passed);
+        }
+    }
+
+    #[test]
+    fn test_any_keyword_in_field() {
+        {
+            let s = Subscription {
+                title_keywords: keywords(&["mobilier", "calculatoare"]),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.title, Outcome::Passed);
+            assert!(res.is_match());
+        }
+
+        {
+            let s = Subscription {
+                title_keywords: keywords(&["mobilier", "autoturisme"]),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.title, Outcome::Failed);
+            assert!(!res.is_match());
+        }
+    }
+
+    #[test]
+    fn test_keywords_are_case_insensitive() {
+        let s = Subscription {
+            title_keywords: keywords(&["CALCULATOARE"]),
+            desc_keywords: keywords(&["laptopuri"]),
+            ..sub()
+        };
+
+        let res = matches(&s, &tender()).unwrap();
+
+        assert_eq!(res.title, Outcome::Passed);
+        assert_eq!(res.description, Outcome::Passed);
+    }
+
+    #[test]
+    fn test_keywords_ignore_diacritics() {
+        let t = Tender {
+            title: "Servicii de curăţenie şi dezinsecţie".into(),
+            description: Some("Întreținere spații verzi în incinta spitalului".into()),
+            ..tender()
+        };
+
+        let s = Subscription {
+            title_keywords: keywords(&["curățenie"]),
+            desc_keywords: keywords(&["intretinere  spatii"]),
+            ..sub()
+        };
+
+        let res = matches(&s, &t).unwrap();
+
+        assert_eq!(res.title, Outcome::Passed);
+        assert_eq!(res.description, Outcome::Passed);
+    }
+
+    #[test]
+    fn test_substring_mode() {
+        let t = Tender {
+            title: "Inventariere patrimoniu si paturi pentru camine".into(),
+            ..tender()
+        };
+
+        let s = Subscription {
+            title_keywords: keywords(&["pat"]),
+            ..sub()
+        };
+
+        assert_eq!(matches(&s, &t).unwrap().title, Outcome::Passed);
+    }
+
+    #[test]
+    fn test_whole_word_mode() {
+        let s = Subscription {
+            title_keywords: keywords(&["pat"]),
+            title_match_mode: MatchMode::WholeWord,
+            ..sub()
+        };
+
+        {
+            let t = Tender {
+                title: "Inventarierea patrimoniului".into(),
+                ..tender()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().title, Outcome::Failed);
+        }
+
+        {
+            let t = Tender {
+                title: "Furnizare pat de spital (pat rabatabil)".into(),
+                ..tender()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().title, Outcome::Passed);
+        }
+
+        {
+            let t = Tender {
+                title: "Furnizare paturi de spital".into(),
+                ..tender()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().title, Outcome::Failed);
+        }
+    }
+
+    #[test]
+    fn test_whole_word_mode_with_phrases() {
+        let s = Subscription {
+            title_keywords: keywords(&["echipamente IT"]),
+            title_match_mode: MatchMode::WholeWord,
+            ..sub()
+        };
+
+        {
+            let t = Tender {
+                title: "Achiziție echipamente IT, licențe".into(),
+                ..tender()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().title, Outcome::Passed);
+        }
+
+        {
+            let t = Tender {
+                title: "Echipamente pentru ITM".into(),
+                ..tender()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().title, Outcome::Failed);
+        }
+    }
+
+    #[test]
+    fn test_stemmed_mode() {
+        let s = Subscription {
+            title_keywords: keywords(&["calculator"]),
+            desc_keywords: keywords(&["pat"]),
+            desc_match_mode: MatchMode::Stemmed,
+            title_match_mode: MatchMode::Stemmed,
+            ..sub()
+        };
+
+        for title in [
+            "Furnizare calculatoare",
+            "Mentenanța calculatorului central",
+            "Calculatoarele din laboratoare",
+        ] {
+            let t = Tender {
+                title: title.into(),
+                description: Some("Paturi pentru internat".into()),
+                ..tender()
+            };
+
+            let res = matches(&s, &t).unwrap();
+
+            assert_eq!(res.title, Outcome::Passed, "{title}");
+            assert_eq!(res.description, Outcome::Passed, "{title}");
+        }
+
+        {
+            let t = Tender {
+                title: "Servicii de calcul tabelar".into(),
+                description: Some("Evidența patrimoniului".into()),
+                ..tender()
+            };
+
+            let res = matches(&s, &t).unwrap();
+
+            assert_eq!(res.title, Outcome::Failed);
+            assert_eq!(res.description, Outcome::Failed);
+        }
+    }
+
+    #[test]
+    fn test_match_modes_are_per_field() {
+        let s = Subscription {
+            title_keywords: keywords(&["calculator"]),
+            title_match_mode: MatchMode::Stemmed,
+            desc_keywords: keywords(&["laptop"]),
+            desc_match_mode: MatchMode::WholeWord,
+            ..sub()
+        };
+
+        let res = matches(&s, &tender()).unwrap();
+
+        assert_eq!(res.title, Outcome::Passed);
+        assert_eq!(res.description, Outcome::Failed);
+    }
+
+    #[test]
+    fn test_query() {
+        {
+            let s = Subscription {
+                query: Some("laptopuri AND (dell OR calculatoare) NOT info:recondiționate".into()),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.query, Outcome::Passed);
+            assert!(res.is_match());
+        }
+
+        {
+            let s = Subscription {
+                query: Some("title:laptopuri".into()),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.query, Outcome::Failed);
+            assert!(!res.is_match());
+        }
+
+        {
+            let s = Subscription {
+                query: Some("laptopuri AND".into()),
+                ..sub()
+            };
+
+            assert_eq!(
+                matches(&s, &tender()).unwrap_err().message,
+                "unexpected end of query"
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_combines_with_keywords() {
+        let s = Subscription {
+            title_keywords: keywords(&["mobilier"]),
+            query: Some("laptopuri".into()),
+            ..sub()
+        };
+
+        let res = matches(&s, &tender()).unwrap();
+
+        assert_eq!(res.title, Outcome::Failed);
+        assert_eq!(res.query, Outcome::Passed);
+        assert!(!res.is_match());
+    }
+
+    #[test]
+    fn test_cpv_codes_match_by_prefix() {
+        let cases = [
+            (&["30213100-6"][..], Outcome::Passed),
+            (&["30200000-1"], Outcome::Passed),
+            (&["30000000-9"], Outcome::Passed),
+            (&["45000000-7", "30210000-4"], Outcome::Passed),
+            (&["30213300-8"], Outcome::Failed),
+            (&["45000000-7"], Outcome::Failed),
+            (&[], Outcome::Unconstrained),
+        ];
+
+        for (codes, expected) in cases {
+            let s = Subscription {
+                cpv_codes: keywords(codes),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.cpv, expected, "{codes:?}");
+            assert_eq!(res.is_match(), expected != Outcome::Failed);
+        }
+    }
+
+    #[test]
+    fn test_cpv_codes_without_tender_code() {
+        let t = Tender {
+            cpv_code: None,
+            ..tender()
+        };
+
+        let s = Subscription {
+            cpv_codes: keywords(&["30200000-1"]),
+            ..sub()
+        };
+
+        assert_eq!(matches(&s, &t).unwrap().cpv, Outcome::Failed);
+    }
+
+    #[test]
+    fn test_counties() {
+        let cases = [
+            (&["CJ"][..], Outcome::Passed),
+            (&["B", "cj"], Outcome::Passed),
+            (&["Cluj"], Outcome::Passed),
+            (&["B", "IF"], Outcome::Failed),
+            (&[], Outcome::Unconstrained),
+        ];
+
+        for (counties, expected) in cases {
+            let s = Subscription {
+                counties: keywords(counties),
+                ..sub()
+            };
+
+            assert_eq!(
+                matches(&s, &tender()).unwrap().county,
+                expected,
+                "{counties:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_counties_without_tender_county() {
+        let t = Tender {
+            county: None,
+            ..tender()
+        };
+
+        let s = Subscription {
+            counties: keywords(&["CJ"]),
+            ..sub()
+        };
+
+        assert_eq!(matches(&s, &t).unwrap().county, Outcome::Failed);
+    }
+
+    #[test]
+    fn test_included_authorities() {
+        let cases = [
+            (authorities(&[("4288110", &[])]), Outcome::Passed),
+            (authorities(&[("RO 4288110", &[])]), Outcome::Passed),
+            (
+                authorities(&[("16054368", &[]), ("4288110", &[])]),
+                Outcome::Passed,
+            ),
+            (
+                authorities(&[(
+                    "16054368",
+                    &["Compania Nationala de Administrare a Infrastructurii Rutiere"],
+                )]),
+                Outcome::Failed,
+            ),
+            (
+                authorities(&[("4305857", &["Consiliul Județean Cluj"])]),
+                Outcome::Passed,
+            ),
+            (
+                authorities(&[("4305857", &["Primăria Cluj-Napoca"])]),
+                Outcome::Failed,
+            ),
+            (authorities(&[]), Outcome::Unconstrained),
+        ];
+
+        for (included_authorities, expected) in cases {
+            let s = Subscription {
+                included_authorities: included_authorities.clone(),
+                ..sub()
+            };
+
+            assert_eq!(
+                matches(&s, &tender()).unwrap().authority,
+                expected,
+                "{included_authorities:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_excluded_authorities() {
+        {
+            let s = Subscription {
+                excluded_authorities: authorities(&[("4288110", &[])]),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.authority, Outcome::Failed);
+            assert!(!res.is_match());
+        }
+
+        {
+            let s = Subscription {
+                excluded_authorities: authorities(&[("4305857", &["Consiliul Judetean Cluj"])]),
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &tender()).unwrap().authority, Outcome::Failed);
+        }
+
+        {
+            let s = Subscription {
+                excluded_authorities: authorities(&[("16054368", &[])]),
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &tender()).unwrap().authority, Outcome::Passed);
+        }
+
+        {
+            let t = Tender {
+                contracting_authority: None,
+                contracting_authority_cui: None,
+                ..tender()
+            };
+
+            let s = Subscription {
+                excluded_authorities: authorities(&[("16054368", &[])]),
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().authority, Outcome::Passed);
+
+            let s = Subscription {
+                included_authorities: authorities(&[("16054368", &[])]),
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().authority, Outcome::Failed);
+        }
+    }
+
+    #[test]
+    fn test_exclude_keywords() {
+        let t = Tender {
+            title: "Furnizare mobilier urban pentru parcuri".into(),
+            ..tender()
+        };
+
+        {
+            let s = Subscription {
+                title_keywords: keywords(&["mobilier"]),
+                title_exclude_keywords: keywords(&["mobilier urban"]),
+                ..sub()
+            };
+
+            let res = matches(&s, &t).unwrap();
+
+            assert_eq!(res.title, Outcome::Failed);
+            assert!(!res.is_match());
+        }
+
+        {
+            let s = Subscription {
+                title_keywords: keywords(&["mobilier"]),
+                title_exclude_keywords: keywords(&["mobilier urban"]),
+                ..sub()
+            };
+
+            let t = Tender {
+                title: "Furnizare mobilier scolar".into(),
+                ..tender()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().title, Outcome::Passed);
+        }
+
+        {
+            let s = Subscription {
+                desc_exclude_keywords: keywords(&["scolare"]),
+                additional_info_exclude_keywords: keywords(&["garanție"]),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.title, Outcome::Unconstrained);
+            assert_eq!(res.description, Outcome::Failed);
+            assert_eq!(res.additional_info, Outcome::Failed);
+        }
+    }
+
+    #[test]
+    fn test_exclude_keywords_follow_match_mode() {
+        let t = Tender {
+            title: "Furnizare paturi de spital".into(),
+            ..tender()
+        };
+
+        let cases = [
+            (MatchMode::Substring, Outcome::Failed),
+            (MatchMode::WholeWord, Outcome::Passed),
+            (MatchMode::Stemmed, Outcome::Failed),
+        ];
+
+        for (mode, expected) in cases {
+            let s = Subscription {
+                title_exclude_keywords: keywords(&["pat"]),
+                title_match_mode: mode,
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().title, expected, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn test_exclude_keywords_without_field() {
+        let t = Tender {
+            description: None,
+            ..tender()
+        };
+
+        let s = Subscription {
+            desc_exclude_keywords: keywords(&["second hand"]),
+            ..sub()
+        };
+
+        assert_eq!(matches(&s, &t).unwrap().description, Outcome::Passed);
+    }
+
+    #[test]
+    fn test_min_days_to_deadline() {
+        // the tender's deadline is 2023-03-01 15:00 UTC
+        let cases = [
+            (datetime!(2023-02-20 15:00 UTC), 9, Outcome::Passed),
+            (datetime!(2023-02-20 15:00 UTC), 10, Outcome::Failed),
+            (datetime!(2023-02-20 15:01 UTC), 9, Outcome::Failed),
+            (datetime!(2023-03-01 15:00 UTC), 0, Outcome::Passed),
+            (datetime!(2023-03-02 09:00 UTC), 0, Outcome::Failed),
+        ];
+
+        for (now, days, expected) in cases {
+            let s = Subscription {
+                min_days_to_deadline: Some(days),
+                ..sub()
+            };
+
+            let res = matches_at(&s, &tender(), now).unwrap();
+
+            assert_eq!(res.deadline, expected, "{days} days from {now}");
+        }
+    }
+
+    #[test]
+    fn test_min_days_to_deadline_without_deadline() {
+        let t = Tender {
+            submission_deadline: None,
+            ..tender()
+        };
+
+        let s = Subscription {
+            min_days_to_deadline: Some(0),
+            ..sub()
+        };
+
+        let res = matches_at(&s, &t, datetime!(2023-02-20 15:00 UTC)).unwrap();
+
+        assert_eq!(res.deadline, Outcome::Failed);
+    }
+
+    #[test]
+    fn test_procedure_types() {
+        let cases = [
+            (vec![ProcedureType::OpenTender], Outcome::Passed),
+            (
+                vec![
+                    ProcedureType::SimplifiedProcedure,
+                    ProcedureType::OpenTender,
+                ],
+                Outcome::Passed,
+            ),
+            (vec![ProcedureType::DirectPurchase], Outcome::Failed),
+            (vec![], Outcome::Unconstrained),
+        ];
+
+        for (procedure_types, expected) in cases {
+            let s = Subscription {
+                procedure_types: Some(procedure_types.clone()),
+                ..sub()
+            };
+
+            assert_eq!(
+                matches(&s, &tender()).unwrap().procedure_type,
+                expected,
+                "{procedure_types:?}"
+            );
+        }
+
+        {
+            let t = Tender {
+                procedure_type: Some("Dialog competitiv".into()),
+                ..tender()
+            };
+
+            let s = Subscription {
+                procedure_types: Some(vec![ProcedureType::OpenTender]),
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().procedure_type, Outcome::Failed);
+        }
+    }
+
+    #[test]
+    fn test_keywords_only_search_their_field() {
+        let s = Subscription {
+            title_keywords: keywords(&["laptopuri"]),
+            ..sub()
+        };
+
+        let res = matches(&s, &tender()).unwrap();
+
+        assert_eq!(res.title, Outcome::Failed);
+        assert_eq!(res.description, Outcome::Unconstrained);
+    }
+
+    #[test]
+    fn test_empty_keyword_list_is_unconstrained() {
+        let s = Subscription {
+            title_keywords: Some(vec![]),
+            desc_keywords: Some(vec![]),
+            additional_info_keywords: Some(vec![]),
+            ..sub()
+        };
+
+        let res = matches(&s, &tender()).unwrap();
+
+        assert!(res.is_match());
+        assert_eq!(res.title, Outcome::Unconstrained);
+        assert_eq!(res.description, Outcome::Unconstrained);
+        assert_eq!(res.additional_info, Outcome::Unconstrained);
+    }
+
+    #[test]
+    fn test_missing_field_fails_keywords() {
+        let t = Tender {
+            description: None,
+            additional_info: None,
+            ..tender()
+        };
+
+        {
+            let s = Subscription {
+                desc_keywords: keywords(&["laptopuri"]),
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().description, Outcome::Failed);
+        }
+
+        {
+            let s = Subscription {
+                additional_info_keywords: keywords(&["garantie"]),
+                ..sub()
+            };
+
+            assert_eq!(matches(&s, &t).unwrap().additional_info, Outcome::Failed);
+        }
+    }
+
+    #[test]
+    fn test_all_constraints_must_pass() {
+        {
+            let s = Subscription {
+                min_price: Some(100_000),
+                max_price: Some(500_000),
+                title_keywords: keywords(&["calculatoare"]),
+                desc_keywords: keywords(&["scolare"]),
+                additional_info_keywords: keywords(&["garantie"]),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert!(res.is_match());
+        }
+
+        {
+            let s = Subscription {
+                min_price: Some(100_000),
+                max_price: Some(500_000),
+                title_keywords: keywords(&["calculatoare"]),
+                desc_keywords: keywords(&["spitale"]),
+                additional_info_keywords: keywords(&["garantie"]),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert!(!res.is_match());
+            assert_eq!(res.price, Outcome::Passed);
+            assert_eq!(res.title, Outcome::Passed);
+            assert_eq!(res.description, Outcome::Failed);
+            assert_eq!(res.additional_info, Outcome::Passed);
+        }
+    }
+
+    #[test]
+    fn test_matched_fields() {
+        {
+            let res = matches(&sub(), &tender()).unwrap();
+
+            assert!(res.matched_fields().is_empty());
+        }
+
+        {
+            let s = Subscription {
+                max_price: Some(500_000),
+                title_keywords: keywords(&["calculatoare"]),
+                desc_keywords: keywords(&["spitale"]),
+                counties: keywords(&["CJ"]),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(res.matched_fields(), vec!["price", "title", "county"]);
+        }
+    }
+
+    #[test]
+    fn test_hits() {
+        let hit = |keyword: &str, field, start, end| Hit {
+            keyword: keyword.into(),
+            field,
+            start,
+            end,
+        };
+
+        {
+            let s = Subscription {
+                title_keywords: keywords(&["CALCULATOARE", "portabil"]),
+                desc_keywords: keywords(&["școlare"]),
+                additional_info_keywords: keywords(&["garantie"]),
+                ..sub()
+            };
+
+            let t = Tender {
+                description: Some("Laptopuri pentru unitățile  școlare".into()),
+                ..tender()
+            };
+
+            let res = matches(&s, &t).unwrap();
+
+            assert_eq!(
+                res.hits,
+                vec![
+                    hit("CALCULATOARE", TextField::Title, 10, 22),
+                    hit("portabil", TextField::Title, 23, 31),
+                    hit("școlare", TextField::Description, 28, 35),
+                    hit("garantie", TextField::AdditionalInfo, 0, 8),
+                ]
+            );
+        }
+
+        {
+            let s = Subscription {
+                title_keywords: keywords(&["calculator", "calculatoare portabile"]),
+                title_match_mode: MatchMode::Stemmed,
+                desc_keywords: keywords(&["scolar"]),
+                desc_match_mode: MatchMode::WholeWord,
+                ..sub()
+            };
+
+            let t = Tender {
+                title: "Calculatoare și calculatoare portabile".into(),
+                description: Some("Mobilier școlar".into()),
+                ..tender()
+            };
+
+            let res = matches(&s, &t).unwrap();
+
+            assert_eq!(
+                res.hits,
+                vec![
+                    hit("calculator", TextField::Title, 0, 12),
+                    hit("calculator", TextField::Title, 16, 28),
+                    hit("calculatoare portabile", TextField::Title, 16, 38),
+                    hit("scolar", TextField::Description, 9, 15),
+                ]
+            );
+        }
+
+        {
+            let s = Subscription {
+                title_keywords: keywords(&["calculatoare"]),
+                desc_keywords: keywords(&["spitale"]),
+                additional_info_keywords: keywords(&["garantie"]),
+                additional_info_exclude_keywords: keywords(&["36 de luni"]),
+                ..sub()
+            };
+
+            let res = matches(&s, &tender()).unwrap();
+
+            assert_eq!(
+                res.hits,
+                vec![hit("calculatoare", TextField::Title, 10, 22)]
+            );
+        }
+    }
+}
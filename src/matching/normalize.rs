@@ -0,0 +1,214 @@
+//! Text normalisation applied to both keywords and tender text before comparing them.
+//!
+//! SEAP notices freely mix comma-below (ș, ț) and cedilla (ş, ţ) letters, precomposed and
+//! decomposed diacritics, and plain ASCII spellings, so everything is folded to lowercase
+//! ASCII letters with single spaces between words.
+
+use std::ops::Range;
+
+pub fn normalize(text: &str) -> String {
+    let folded: String = text.chars().filter_map(fold_char).collect();
+
+    folded
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Like [`normalize`], but also returns, for every character of the normalized text, the range
+/// of character offsets in `text` it was folded from, so matches found in the normalized text
+/// can be located in the original one.
+pub fn normalize_with_spans(text: &str) -> (String, Vec<Range<usize>>) {
+    let mut normalized = String::new();
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    let mut after_space = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let Some(folded) = fold_char(c) else {
+            // a dropped combining mark belongs to the letter before it
+            if let Some(span) = spans.last_mut().filter(|_| !after_space) {
+                span.end = i + 1;
+            }
+
+            continue;
+        };
+
+        if folded.is_whitespace() {
+            after_space = !normalized.is_empty();
+            continue;
+        }
+
+        if after_space {
+            let start = spans.last().map_or(0, |span| span.end);
+
+            normalized.push(' ');
+            spans.push(start..i);
+            after_space = false;
+        }
+
+        for lower in folded.to_lowercase() {
+            normalized.push(lower);
+            spans.push(i..i + 1);
+        }
+    }
+
+    (normalized, spans)
+}
+
+pub fn normalize_keywords(keywords: Option<&[String]>) -> Option<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::new();
+
+    for keyword in keywords? {
+        let keyword = normalize(keyword);
+
+        if !keyword.is_empty() && !normalized.contains(&keyword) {
+            normalized.push(keyword);
+        }
+    }
+
+    Some(normalized)
+}
+
+fn fold_char(c: char) -> Option<char> {
+    let folded = match c {
+        // combining diacritical marks left over from decomposed input
+        '\u{0300}'..='\u{036F}' => return None,
+        'ă' | 'â' | 'á' | 'à' | 'ä' | 'ã' | 'å' => 'a',
+        'Ă' | 'Â' | 'Á' | 'À' | 'Ä' | 'Ã' | 'Å' => 'A',
+        'î' | 'í' | 'ì' | 'ï' => 'i',
+        'Î' | 'Í' | 'Ì' | 'Ï' => 'I',
+        'ș' | 'ş' | 'š' => 's',
+        'Ș' | 'Ş' | 'Š' => 'S',
+        'ț' | 'ţ' => 't',
+        'Ț' | 'Ţ' => 'T',
+        'é' | 'è' | 'ë' | 'ê' => 'e',
+        'É' | 'È' | 'Ë' | 'Ê' => 'E',
+        'ó' | 'ò' | 'ö' | 'ô' | 'õ' | 'ő' => 'o',
+        'Ó' | 'Ò' | 'Ö' | 'Ô' | 'Õ' | 'Ő' => 'O',
+        'ú' | 'ù' | 'ü' | 'û' | 'ű' => 'u',
+        'Ú' | 'Ù' | 'Ü' | 'Û' | 'Ű' => 'U',
+        'ç' => 'c',
+        'Ç' => 'C',
+        // non-breaking spaces are common in text copied from the SEAP web interface
+        '\u{00A0}' | '\u{202F}' => ' ',
+        c => c,
+    };
+
+    Some(folded)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matching::normalize::{normalize, normalize_keywords, normalize_with_spans};
+
+    #[test]
+    fn test_comma_below_and_cedilla_fold_together() {
+        assert_eq!(normalize("curățenie"), normalize("curăţenie"));
+        assert_eq!(
+            normalize("Prestări servicii"),
+            normalize("Prestãri servicii")
+        );
+        assert_eq!(normalize("ȘCOALĂ"), normalize("ŞCOALĂ"));
+        assert_eq!(normalize("ȚEAVĂ"), normalize("ŢEAVĂ"));
+    }
+
+    #[test]
+    fn test_diacritics_are_stripped() {
+        assert_eq!(
+            normalize("Achiziție echipamente IT"),
+            "achizitie echipamente it"
+        );
+        assert_eq!(
+            normalize("Servicii de pază și protecție"),
+            "servicii de paza si protectie"
+        );
+        assert_eq!(
+            normalize("Întreținere spații verzi"),
+            "intretinere spatii verzi"
+        );
+        assert_eq!(normalize("îmbrăcăminte"), "imbracaminte");
+        assert_eq!(
+            normalize("Lucrări de construcții"),
+            "lucrari de constructii"
+        );
+        assert_eq!(normalize("Mobilier școlar"), "mobilier scolar");
+    }
+
+    #[test]
+    fn test_decomposed_diacritics_are_stripped() {
+        assert_eq!(normalize("s\u{0326}coala\u{0306}"), "scoala");
+        assert_eq!(normalize("t\u{0327}eava\u{0306}"), "teava");
+        assert_eq!(normalize("i\u{0302}ntretinere"), "intretinere");
+    }
+
+    #[test]
+    fn test_case_is_folded() {
+        assert_eq!(normalize("ÎNTREȚINERE"), "intretinere");
+        assert_eq!(normalize("Medicamente"), "medicamente");
+    }
+
+    #[test]
+    fn test_whitespace_is_collapsed() {
+        assert_eq!(
+            normalize("  lucrări   de\tconstrucții \n"),
+            "lucrari de constructii"
+        );
+        assert_eq!(normalize("servicii\u{00A0}medicale"), "servicii medicale");
+        assert_eq!(normalize("   "), "");
+    }
+
+    #[test]
+    fn test_normalize_keywords() {
+        {
+            let keywords = vec![
+                "Curățenie".to_string(),
+                "curăţenie".to_string(),
+                "curatenie".to_string(),
+                "  ".to_string(),
+                "Dezinsecție".to_string(),
+            ];
+
+            let res = normalize_keywords(Some(&keywords));
+
+            assert_eq!(
+                res,
+                Some(vec!["curatenie".to_string(), "dezinsectie".to_string()])
+            );
+        }
+
+        {
+            assert_eq!(normalize_keywords(None), None);
+            assert_eq!(normalize_keywords(Some(&[])), Some(vec![]));
+        }
+    }
+
+    #[test]
+    fn test_normalize_with_spans() {
+        {
+            let texts = [
+                "  Lucrări   de\tconstrucții \n",
+                "s\u{0326}coala\u{0306} gimnazială",
+                "ÎNTREȚINERE spații\u{00A0}verzi",
+                "   ",
+            ];
+
+            for text in texts {
+                let (normalized, spans) = normalize_with_spans(text);
+
+                assert_eq!(normalized, normalize(text), "{text:?}");
+                assert_eq!(spans.len(), normalized.chars().count(), "{text:?}");
+            }
+        }
+
+        {
+            let (normalized, spans) = normalize_with_spans("  Pază și s\u{0326}coli");
+
+            assert_eq!(normalized, "paza si scoli");
+            assert_eq!(spans[0], 2..3);
+            assert_eq!(spans[4], 6..7);
+            assert_eq!(spans[8], 10..12);
+            assert_eq!(spans[12], 15..16);
+        }
+    }
+}
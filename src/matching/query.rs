@@ -0,0 +1,529 @@
+//! Boolean keyword queries stored in [`Subscription::query`](crate::models::Subscription).
+//!
+//! Grammar (operators are case sensitive, terms are not):
+//!
+//! ```text
+//! query   := or
+//! or      := and ("OR" and)*
+//! and     := not ("AND"? not)*        adjacent terms are implicitly AND-ed
+//! not     := "NOT" not | primary
+//! primary := "(" or ")" | field? term
+//! field   := "title:" | "desc:" | "info:"
+//! term    := word | '"' phrase '"'
+//! ```
+//!
+//! A term without a field prefix matches if it occurs in the title, the description or the
+//! additional info. Terms and tender text are both [`normalize`]d and compared as substrings,
+//! so `laptop AND (dell OR lenovo) NOT refurbished` behaves like the equivalent keyword lists.
+//!
+//! Queries nest at most [`MAX_DEPTH`] parentheses and `NOT`s deep and have at most [`MAX_TERMS`]
+//! terms, which keeps parsing, evaluating and dropping them from exhausting the stack.
+
+use super::normalize::normalize;
+use std::fmt;
+
+pub const MAX_DEPTH: usize = 32;
+pub const MAX_TERMS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Description,
+    AdditionalInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Term { field: Option<Field>, text: String },
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub struct Document<'a> {
+    pub title: &'a str,
+    pub description: Option<&'a str>,
+    pub additional_info: Option<&'a str>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, ParseError> {
+        let tokens = tokenize(input)?;
+
+        let mut parser = Parser {
+            tokens,
+            index: 0,
+            end: input.chars().count(),
+            depth: 0,
+            terms: 0,
+        };
+
+        let query = parser.parse_or()?;
+
+        match parser.peek() {
+            None => Ok(query),
+            Some((position, Token::RParen)) => Err(ParseError {
+                position,
+                message: "unmatched `)`".into(),
+            }),
+            Some((position, token)) => Err(ParseError {
+                position,
+                message: format!("unexpected {token}"),
+            }),
+        }
+    }
+
+    pub fn evaluate(&self, doc: &Document) -> bool {
+        let title = normalize(doc.title);
+        let description = doc.description.map(normalize);
+        let additional_info = doc.additional_info.map(normalize);
+
+        self.evaluate_normalized(&title, description.as_deref(), additional_info.as_deref())
+    }
+
+    fn evaluate_normalized(
+        &self,
+        title: &str,
+        description: Option<&str>,
+        additional_info: Option<&str>,
+    ) -> bool {
+        match self {
+            Query::Term { field, text } => {
+                let contains = |haystack: Option<&str>| haystack.is_some_and(|h| h.contains(text));
+
+                match field {
+                    Some(Field::Title) => contains(Some(title)),
+                    Some(Field::Description) => contains(description),
+                    Some(Field::AdditionalInfo) => contains(additional_info),
+                    None => {
+                        contains(Some(title)) || contains(description) || contains(additional_info)
+                    }
+                }
+            }
+            Query::And(lhs, rhs) => {
+                lhs.evaluate_normalized(title, description, additional_info)
+                    && rhs.evaluate_normalized(title, description, additional_info)
+            }
+            Query::Or(lhs, rhs) => {
+                lhs.evaluate_normalized(title, description, additional_info)
+                    || rhs.evaluate_normalized(title, description, additional_info)
+            }
+            Query::Not(inner) => !inner.evaluate_normalized(title, description, additional_info),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Field(Field),
+    Word(String),
+    Phrase(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LParen => write!(f, "`(`"),
+            Token::RParen => write!(f, "`)`"),
+            Token::And => write!(f, "`AND`"),
+            Token::Or => write!(f, "`OR`"),
+            Token::Not => write!(f, "`NOT`"),
+            Token::Field(_) => write!(f, "field prefix"),
+            Token::Word(word) => write!(f, "`{word}`"),
+            Token::Phrase(phrase) => write!(f, "`\"{phrase}\"`"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((i, Token::LParen));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((i, Token::RParen));
+                i += 1;
+            }
+            '"' => {
+                let start = i;
+                let Some(len) = chars[i + 1..].iter().position(|c| *c == '"') else {
+                    return Err(ParseError {
+                        position: start,
+                        message: "unterminated phrase".into(),
+                    });
+                };
+
+                let phrase: String = chars[i + 1..i + 1 + len].iter().collect();
+
+                tokens.push((start, Token::Phrase(phrase)));
+                i += len + 2;
+            }
+            _ => {
+                let start = i;
+
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !matches!(chars[i], '(' | ')' | '"' | ':')
+                {
+                    i += 1;
+                }
+
+                let word: String = chars[start..i].iter().collect();
+
+                if i < chars.len() && chars[i] == ':' {
+                    let field = match word.as_str() {
+                        "title" => Field::Title,
+                        "desc" => Field::Description,
+                        "info" => Field::AdditionalInfo,
+                        _ => {
+                            return Err(ParseError {
+                                position: start,
+                                message: format!(
+                                    "unknown field `{word}`, expected `title`, `desc` or `info`"
+                                ),
+                            })
+                        }
+                    };
+
+                    tokens.push((start, Token::Field(field)));
+                    i += 1;
+                    continue;
+                }
+
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                };
+
+                tokens.push((start, token));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    end: usize,
+    /// Parentheses and `NOT`s open around the current token.
+    depth: usize,
+    terms: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(usize, &Token)> {
+        self.tokens
+            .get(self.index)
+            .map(|(pos, token)| (*pos, token))
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_and()?;
+
+        while let Some((_, Token::Or)) = self.peek() {
+            self.next();
+            query = Query::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_not()?;
+
+        loop {
+            match self.peek() {
+                Some((_, Token::And)) => {
+                    self.next();
+                }
+                Some((_, Token::Or | Token::RParen)) | None => break,
+                Some(_) => {}
+            }
+
+            query = Query::And(Box::new(query), Box::new(self.parse_not()?));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_not(&mut self) -> Result<Query, ParseError> {
+        if let Some((position, Token::Not)) = self.peek() {
+            self.next();
+            self.enter(position)?;
+            let query = Query::Not(Box::new(self.parse_not()?));
+            self.depth -= 1;
+
+            return Ok(query);
+        }
+
+        self.parse_primary()
+    }
+
+    fn enter(&mut self, position: usize) -> Result<(), ParseError> {
+        self.depth += 1;
+
+        if self.depth > MAX_DEPTH {
+            return Err(ParseError {
+                position,
+                message: format!("query nested deeper than {MAX_DEPTH} levels"),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, ParseError> {
+        match self.next() {
+            Some((position, Token::LParen)) => {
+                self.enter(position)?;
+                let query = self.parse_or()?;
+                self.depth -= 1;
+
+                match self.next() {
+                    Some((_, Token::RParen)) => Ok(query),
+                    _ => Err(ParseError {
+                        position,
+                        message: "unclosed `(`".into(),
+                    }),
+                }
+            }
+            Some((position, Token::Field(field))) => match self.next() {
+                Some((position, Token::Word(text) | Token::Phrase(text))) => {
+                    self.term(Some(field), &text, position)
+                }
+                Some((position, token)) => Err(ParseError {
+                    position,
+                    message: format!("expected a term after the field prefix, found {token}"),
+                }),
+                None => Err(ParseError {
+                    position,
+                    message: "expected a term after the field prefix".into(),
+                }),
+            },
+            Some((position, Token::Word(text) | Token::Phrase(text))) => {
+                self.term(None, &text, position)
+            }
+            Some((position, token)) => Err(ParseError {
+                position,
+                message: format!("expected a term, found {token}"),
+            }),
+            None => Err(ParseError {
+                position: self.end,
+                message: "unexpected end of query".into(),
+            }),
+        }
+    }
+
+    fn term(
+        &mut self,
+        field: Option<Field>,
+        text: &str,
+        position: usize,
+    ) -> Result<Query, ParseError> {
+        self.terms += 1;
+
+        if self.terms > MAX_TERMS {
+            return Err(ParseError {
+                position,
+                message: format!("query has more than {MAX_TERMS} terms"),
+            });
+        }
+
+        let text = normalize(text);
+
+        if text.is_empty() {
+            return Err(ParseError {
+                position,
+                message: "empty term".into(),
+            });
+        }
+
+        Ok(Query::Term { field, text })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matching::query::{Document, Field, ParseError, Query, MAX_DEPTH, MAX_TERMS};
+
+    fn doc() -> Document<'static> {
+        Document {
+            title: "Furnizare laptopuri Lenovo ThinkPad",
+            description: Some("Laptopuri noi, în garanție, pentru școli"),
+            additional_info: Some("Nu se acceptă produse recondiționate"),
+        }
+    }
+
+    fn eval(query: &str) -> bool {
+        Query::parse(query).unwrap().evaluate(&doc())
+    }
+
+    fn error(query: &str) -> ParseError {
+        Query::parse(query).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let term = |text: &str| {
+            Box::new(Query::Term {
+                field: None,
+                text: text.into(),
+            })
+        };
+
+        assert_eq!(
+            Query::parse("a OR b c").unwrap(),
+            Query::Or(term("a"), Box::new(Query::And(term("b"), term("c"))))
+        );
+        assert_eq!(
+            Query::parse("NOT a AND b").unwrap(),
+            Query::And(Box::new(Query::Not(term("a"))), term("b"))
+        );
+        assert_eq!(
+            Query::parse("a and b").unwrap(),
+            Query::And(Box::new(Query::And(term("a"), term("and"))), term("b"))
+        );
+        assert_eq!(
+            Query::parse("(a OR b) AND c").unwrap(),
+            Query::And(Box::new(Query::Or(term("a"), term("b"))), term("c"))
+        );
+    }
+
+    #[test]
+    fn test_parse_fields_and_phrases() {
+        assert_eq!(
+            Query::parse(r#"title:"Echipamente  IT" desc:școli info:x"#).unwrap(),
+            Query::And(
+                Box::new(Query::And(
+                    Box::new(Query::Term {
+                        field: Some(Field::Title),
+                        text: "echipamente it".into(),
+                    }),
+                    Box::new(Query::Term {
+                        field: Some(Field::Description),
+                        text: "scoli".into(),
+                    }),
+                )),
+                Box::new(Query::Term {
+                    field: Some(Field::AdditionalInfo),
+                    text: "x".into(),
+                }),
+            )
+        );
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert!(eval("laptop AND (dell OR lenovo) NOT refurbished"));
+        assert!(eval("laptop (dell OR lenovo)"));
+        assert!(!eval("laptop AND (dell OR hp)"));
+        assert!(!eval("laptop NOT recondiționate"));
+        assert!(eval("laptop NOT title:recondiționate"));
+        assert!(eval(r#""laptopuri lenovo""#));
+        assert!(!eval(r#""lenovo laptopuri""#));
+        assert!(eval("title:thinkpad AND desc:garantie"));
+        assert!(!eval("desc:thinkpad"));
+        assert!(eval("NOT NOT scoli"));
+    }
+
+    #[test]
+    fn test_evaluate_missing_fields() {
+        let doc = Document {
+            title: "Furnizare laptopuri",
+            description: None,
+            additional_info: None,
+        };
+
+        assert!(!Query::parse("desc:laptop").unwrap().evaluate(&doc));
+        assert!(Query::parse("NOT info:laptop").unwrap().evaluate(&doc));
+        assert!(Query::parse("laptop").unwrap().evaluate(&doc));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(error("laptop AND").position, 10);
+        assert_eq!(error("laptop AND").message, "unexpected end of query");
+        assert_eq!(error("(laptop OR dell").position, 0);
+        assert_eq!(error("laptop)").position, 6);
+        assert_eq!(error("laptop OR \"dell").position, 10);
+        assert_eq!(error("price:100").position, 0);
+        assert_eq!(error("title: AND x").position, 7);
+        assert_eq!(error("OR laptop").position, 0);
+        assert_eq!(error("\"  \"").message, "empty term");
+        assert_eq!(error("").position, 0);
+        assert_eq!(
+            error("laptop AND").to_string(),
+            "unexpected end of query at position 10"
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(Query::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            error(&nested(MAX_DEPTH + 1)),
+            ParseError {
+                position: MAX_DEPTH,
+                message: "query nested deeper than 32 levels".into(),
+            }
+        );
+        assert_eq!(error(&"(".repeat(100_000)).position, MAX_DEPTH);
+        assert_eq!(error(&"NOT ".repeat(100_000)).position, MAX_DEPTH * 4);
+
+        let terms = |count: usize| vec!["a"; count].join(" OR ");
+
+        assert!(Query::parse(&terms(MAX_TERMS)).is_ok());
+        assert_eq!(
+            error(&terms(MAX_TERMS + 1)),
+            ParseError {
+                position: MAX_TERMS * 5,
+                message: "query has more than 256 terms".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_positions_count_characters() {
+        assert_eq!(error("școală AND").position, 10);
+    }
+}
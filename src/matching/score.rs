@@ -0,0 +1,291 @@
+//! Relevance score of a matched tender, from 0 to 100.
+//!
+//! Every criterion the tender passed contributes a value between 0 and 1, and the score is the
+//! average of these values weighted by the criterion's [`Weights`]:
+//! - a keyword field is worth more the more keyword hits it has, from 0.6 for a single hit up
+//!   to 1, while a field that only passed its exclude keywords counts as a filter;
+//! - a price range is worth 1 when the estimated value sits in its middle and 0.5 at its
+//!   bounds, and a single bound is worth 1;
+//! - a CPV constraint is worth more the deeper the subscription code is compared to the
+//!   tender's, from 0.5 for a division matching a fully specified code up to 1 for the code
+//!   itself;
+//! - every other passed criterion (query, county, authority, deadline, procedure type) is a
+//!   filter worth 1.
+//!
+//! A subscription that constrains nothing scores 100 on every tender.
+
+use super::{value_in_ron, MatchResult, Outcome, TextField};
+use crate::{
+    cpv,
+    models::{Subscription, Tender},
+};
+use std::env;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weights {
+    pub title: u32,
+    pub description: u32,
+    pub additional_info: u32,
+    pub price: u32,
+    pub cpv: u32,
+    pub filters: u32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            title: 5,
+            description: 3,
+            additional_info: 1,
+            price: 2,
+            cpv: 3,
+            filters: 1,
+        }
+    }
+}
+
+impl Weights {
+    /// The default weights, overridden by the `SCORE_WEIGHT_TITLE`, `SCORE_WEIGHT_DESCRIPTION`,
+    /// `SCORE_WEIGHT_ADDITIONAL_INFO`, `SCORE_WEIGHT_PRICE`, `SCORE_WEIGHT_CPV` and
+    /// `SCORE_WEIGHT_FILTERS` environment variables.
+    pub fn from_env() -> Self {
+        let weight = |name: &str, default: u32| {
+            env::var(name)
+                .ok()
+                .and_then(|weight| weight.parse().ok())
+                .unwrap_or(default)
+        };
+
+        let default = Weights::default();
+
+        Weights {
+            title: weight("SCORE_WEIGHT_TITLE", default.title),
+            description: weight("SCORE_WEIGHT_DESCRIPTION", default.description),
+            additional_info: weight("SCORE_WEIGHT_ADDITIONAL_INFO", default.additional_info),
+            price: weight("SCORE_WEIGHT_PRICE", default.price),
+            cpv: weight("SCORE_WEIGHT_CPV", default.cpv),
+            filters: weight("SCORE_WEIGHT_FILTERS", default.filters),
+        }
+    }
+}
+
+pub fn score(sub: &Subscription, tender: &Tender, res: &MatchResult, weights: &Weights) -> i32 {
+    let keywords = |field: TextField, weight: u32| {
+        let hits = res.hits.iter().filter(|hit| hit.field == field).count();
+
+        match hits {
+            0 => (weights.filters, 1.0),
+            hits => (weight, 1.0 - 0.4 * 0.5f64.powi(hits as i32 - 1)),
+        }
+    };
+
+    let components = [
+        (res.price, (weights.price, price(sub, tender))),
+        (res.title, keywords(TextField::Title, weights.title)),
+        (
+            res.description,
+            keywords(TextField::Description, weights.description),
+        ),
+        (
+            res.additional_info,
+            keywords(TextField::AdditionalInfo, weights.additional_info),
+        ),
+        (res.query, (weights.filters, 1.0)),
+        (res.cpv, (weights.cpv, cpv_depth(sub, tender))),
+        (res.county, (weights.filters, 1.0)),
+        (res.authority, (weights.filters, 1.0)),
+        (res.deadline, (weights.filters, 1.0)),
+        (res.procedure_type, (weights.filters, 1.0)),
+    ];
+
+    let (total, weighted) = components
+        .into_iter()
+        .filter(|(outcome, _)| *outcome == Outcome::Passed)
+        .fold((0.0, 0.0), |(total, weighted), (_, (weight, value))| {
+            (
+                total + f64::from(weight),
+                weighted + f64::from(weight) * value,
+            )
+        });
+
+    if total == 0.0 {
+        return 100;
+    }
+
+    (100.0 * weighted / total).round() as i32
+}
+
+fn price(sub: &Subscription, tender: &Tender) -> f64 {
+    let (Some(min), Some(max), Some(value)) = (sub.min_price, sub.max_price, value_in_ron(tender))
+    else {
+        return 1.0;
+    };
+
+    let (min, max) = (f64::from(min), f64::from(max));
+
+    if max <= min {
+        return 1.0;
+    }
+
+    let middle = (min + max) / 2.0;
+    let distance = (value as f64 - middle).abs() / (max - middle);
+
+    1.0 - 0.5 * distance.min(1.0)
+}
+
+fn cpv_depth(sub: &Subscription, tender: &Tender) -> f64 {
+    let (Some(codes), Some(code)) = (&sub.cpv_codes, &tender.cpv_code) else {
+        return 1.0;
+    };
+
+    let depth = cpv::prefix(code).len();
+
+    let Some(matched) = codes
+        .iter()
+        .filter(|ancestor| cpv::is_within(code, ancestor))
+        .map(|ancestor| cpv::prefix(ancestor).len())
+        .max()
+    else {
+        return 1.0;
+    };
+
+    // depths range from 2 (division) to 8 digits
+    let specificity = (matched.saturating_sub(2)) as f64 / (depth.max(3) - 2) as f64;
+
+    0.5 + 0.5 * specificity.min(1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        matching::{
+            matches,
+            score::{score, Weights},
+            MatchMode,
+        },
+        models::{tender, Subscription, Tender},
+    };
+
+    fn sub() -> Subscription {
+        Subscription {
+            id: 1,
+            id_user: 1,
+            ..Default::default()
+        }
+    }
+
+    fn tender() -> Tender {
+        Tender {
+            id: 1,
+            title: "Furnizare calculatoare portabile si calculatoare desktop".into(),
+            description: Some("Calculatoare pentru unitatile scolare din judet".into()),
+            contracting_authority: Some("Consiliul Judetean Cluj".into()),
+            contracting_authority_cui: Some("4288110".into()),
+            ..tender::sample()
+        }
+    }
+
+    fn scored(s: &Subscription, t: &Tender) -> i32 {
+        score(s, t, &matches(s, t).unwrap(), &Weights::default())
+    }
+
+    #[test]
+    fn test_unconstrained() {
+        assert_eq!(scored(&sub(), &tender()), 100);
+    }
+
+    #[test]
+    fn test_filters_score_full() {
+        let s = Subscription {
+            counties: Some(vec!["CJ".into()]),
+            min_days_to_deadline: Some(0),
+            ..sub()
+        };
+
+        assert_eq!(scored(&s, &tender()), 100);
+    }
+
+    #[test]
+    fn test_keyword_frequency() {
+        let s = |keyword: &str| Subscription {
+            title_keywords: Some(vec![keyword.into()]),
+            title_match_mode: MatchMode::WholeWord,
+            ..sub()
+        };
+
+        assert_eq!(scored(&s("portabile"), &tender()), 60);
+        assert_eq!(scored(&s("calculatoare"), &tender()), 80);
+    }
+
+    #[test]
+    fn test_fields_are_weighted() {
+        let title = Subscription {
+            title_keywords: Some(vec!["portabile".into()]),
+            counties: Some(vec!["CJ".into()]),
+            ..sub()
+        };
+
+        let description = Subscription {
+            desc_keywords: Some(vec!["scolare".into()]),
+            counties: Some(vec!["CJ".into()]),
+            ..sub()
+        };
+
+        // (5 * 0.6 + 1) / 6 and (3 * 0.6 + 1) / 4
+        assert_eq!(scored(&title, &tender()), 67);
+        assert_eq!(scored(&description, &tender()), 70);
+
+        let weights = Weights {
+            description: 10,
+            ..Default::default()
+        };
+
+        let res = matches(&description, &tender()).unwrap();
+
+        assert_eq!(score(&description, &tender(), &res, &weights), 64);
+    }
+
+    #[test]
+    fn test_price_proximity() {
+        let cases = [
+            (Some(200_000), Some(300_000), 100),
+            (Some(150_000), Some(250_000), 50),
+            (Some(225_000), Some(300_000), 83),
+            (Some(200_000), None, 100),
+            (None, Some(250_000), 100),
+        ];
+
+        for (min_price, max_price, expected) in cases {
+            let s = Subscription {
+                min_price,
+                max_price,
+                ..sub()
+            };
+
+            assert_eq!(
+                scored(&s, &tender()),
+                expected,
+                "{min_price:?}..={max_price:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_cpv_depth() {
+        let cases = [
+            ("30213100-6", 100),
+            ("30213000-5", 88),
+            ("30200000-1", 63),
+            ("30000000-9", 50),
+        ];
+
+        for (code, expected) in cases {
+            let s = Subscription {
+                cpv_codes: Some(vec!["03000000-1".into(), code.into()]),
+                ..sub()
+            };
+
+            assert_eq!(scored(&s, &tender()), expected, "{code}");
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! A light Romanian stemmer.
+//!
+//! It only strips the definite article / case endings and then the plural or gender ending,
+//! which is enough to conflate "calculator", "calculatoare" and "calculatorului" without
+//! collapsing short, unrelated words onto each other. Input is expected to be
+//! [`normalize`](super::normalize::normalize)d already.
+
+const MIN_STEM_LEN: usize = 3;
+
+const ARTICLE_SUFFIXES: [(&str, &str); 5] = [
+    ("ului", ""),
+    ("lui", ""),
+    ("lor", ""),
+    ("ul", ""),
+    ("le", ""),
+];
+
+const INFLECTION_SUFFIXES: [(&str, &str); 8] = [
+    ("oare", "or"),
+    ("uri", ""),
+    ("ii", ""),
+    ("iu", ""),
+    ("e", ""),
+    ("i", ""),
+    ("a", ""),
+    ("u", ""),
+];
+
+pub fn stem(word: &str) -> String {
+    let word = strip_suffix(word, &ARTICLE_SUFFIXES);
+
+    strip_suffix(&word, &INFLECTION_SUFFIXES)
+}
+
+fn strip_suffix(word: &str, suffixes: &[(&str, &str)]) -> String {
+    for (suffix, replacement) in suffixes {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.len() + replacement.len() >= MIN_STEM_LEN {
+                return format!("{stem}{replacement}");
+            }
+        }
+    }
+
+    word.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matching::stem::stem;
+
+    fn assert_same_stem(words: &[&str]) {
+        let stems: Vec<String> = words.iter().map(|w| stem(w)).collect();
+
+        assert!(
+            stems.windows(2).all(|w| w[0] == w[1]),
+            "{words:?} stemmed to {stems:?}"
+        );
+    }
+
+    #[test]
+    fn test_inflections_share_a_stem() {
+        assert_same_stem(&[
+            "calculator",
+            "calculatorul",
+            "calculatorului",
+            "calculatoare",
+            "calculatoarele",
+            "calculatoarelor",
+        ]);
+        assert_same_stem(&["mobilier", "mobilierul", "mobilierului"]);
+        assert_same_stem(&[
+            "medicament",
+            "medicamente",
+            "medicamentele",
+            "medicamentelor",
+        ]);
+        assert_same_stem(&[
+            "serviciu",
+            "servicii",
+            "serviciile",
+            "serviciului",
+            "serviciilor",
+        ]);
+        assert_same_stem(&["lucrare", "lucrari", "lucrarile", "lucrarilor"]);
+        assert_same_stem(&["autoturism", "autoturisme", "autoturismului"]);
+        assert_same_stem(&["pat", "paturi"]);
+    }
+
+    #[test]
+    fn test_unrelated_words_keep_distinct_stems() {
+        assert_ne!(stem("pat"), stem("patrimoniu"));
+        assert_ne!(stem("pat"), stem("patru"));
+        assert_ne!(stem("calculator"), stem("calcul"));
+    }
+
+    #[test]
+    fn test_short_words_are_left_alone() {
+        assert_eq!(stem("it"), "it");
+        assert_eq!(stem("ale"), "ale");
+        assert_eq!(stem("pe"), "pe");
+    }
+}
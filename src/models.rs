@@ -1,6 +1,45 @@
+pub mod bookmark;
+pub mod cpv;
+pub mod delivery;
+pub mod feed_token;
+pub mod lot;
+pub mod matches;
+pub mod preferences;
+pub mod revision;
+pub mod source_cursor;
 pub mod subscription;
+pub mod tender;
 pub mod user;
+pub mod webhook;
 
 // reexports
-pub use subscription::Subscription;
+pub use bookmark::Bookmark;
+pub use cpv::CpvCode;
+pub use delivery::Delivery;
+pub use feed_token::FeedToken;
+pub use lot::Lot;
+pub use matches::Match;
+pub use preferences::Preferences;
+pub use revision::Revision;
+pub use subscription::{AuthorityFilter, Subscription};
+pub use tender::Tender;
 pub use user::User;
+pub use webhook::Webhook;
+
+/// Loads the users, subscriptions, tenders and matches fixtures, for the tests outside this
+/// module, which `sqlx::test` can't find them for.
+#[cfg(test)]
+pub(crate) async fn load_fixtures(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+    use sqlx::Executor;
+
+    pool.execute(include_str!("models/fixtures/users.sql"))
+        .await?;
+    pool.execute(include_str!("models/fixtures/subscriptions.sql"))
+        .await?;
+    pool.execute(include_str!("models/fixtures/tenders.sql"))
+        .await?;
+    pool.execute(include_str!("models/fixtures/matches.sql"))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use crate::utils::Pagination;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, PgPool};
+use time::OffsetDateTime;
+
+/// A tender the user follows regardless of their subscriptions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Bookmark {
+    pub id_user: i32,
+    pub id_tender: i32,
+    pub created_at: OffsetDateTime,
+}
+
+/// Bookmarks the tender, or returns the existing bookmark.
+pub async fn create(pool: &PgPool, id_user: usize, id_tender: usize) -> Result<Bookmark> {
+    let id_user: i32 = id_user.try_into()?;
+    let id_tender: i32 = id_tender.try_into()?;
+
+    Ok(query_as!(
+        Bookmark,
+        r#"
+            SELECT
+                id_user as "id_user!",
+                id_tender as "id_tender!",
+                created_at as "created_at!"
+            FROM create_bookmark($1, $2)"#,
+        id_user,
+        id_tender
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Newest first.
+pub async fn get_all_of_user(
+    pool: &PgPool,
+    id_user: usize,
+    pagination: &Pagination,
+) -> Result<Vec<Bookmark>> {
+    let id_user: i32 = id_user.try_into()?;
+
+    Ok(query_as!(
+        Bookmark,
+        r#"SELECT * FROM bookmarks
+            WHERE id_user = $1
+            ORDER BY created_at DESC, id_tender DESC
+            LIMIT $2
+            OFFSET $3"#,
+        id_user,
+        i64::from(pagination.count),
+        i64::from(pagination.start_index)
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn delete(pool: &PgPool, id_user: usize, id_tender: usize) -> Result<Bookmark> {
+    let id_user: i32 = id_user.try_into()?;
+    let id_tender: i32 = id_tender.try_into()?;
+
+    Ok(query_as!(
+        Bookmark,
+        "DELETE FROM bookmarks WHERE id_user = $1 AND id_tender = $2 RETURNING *",
+        id_user,
+        id_tender
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::bookmark::{create, delete, get_all_of_user},
+        utils::Pagination,
+    };
+    use anyhow::Result;
+    use sqlx::PgPool;
+
+    fn pagination() -> Pagination {
+        Pagination {
+            start_index: 0,
+            count: 10,
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "tenders"))]
+    async fn test_create_and_delete(pool: PgPool) -> Result<()> {
+        {
+            let first = create(&pool, 1, 2).await?;
+            let again = create(&pool, 1, 2).await?;
+
+            assert_eq!(first.id_tender, 2);
+            assert_eq!(again, first);
+
+            create(&pool, 1, 3).await?;
+            create(&pool, 2, 1).await?;
+        }
+
+        {
+            let res = get_all_of_user(&pool, 1, &pagination()).await?;
+
+            let mut tenders: Vec<i32> = res.iter().map(|b| b.id_tender).collect();
+            tenders.sort();
+
+            assert_eq!(tenders, vec![2, 3]);
+        }
+
+        {
+            assert!(create(&pool, 1, 100).await.is_err());
+            assert!(create(&pool, 100, 1).await.is_err());
+        }
+
+        {
+            let res = delete(&pool, 1, 2).await?;
+
+            assert_eq!(res.id_tender, 2);
+            assert!(delete(&pool, 1, 2).await.is_err());
+
+            let res = get_all_of_user(&pool, 1, &pagination()).await?;
+
+            assert_eq!(res.len(), 1);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use crate::cpv;
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, PgPool};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CpvCode {
+    pub code: String,
+    pub label_ro: String,
+    pub label_en: String,
+}
+
+/// Resolves a code to its labels and the labels of its ancestors, most specific first. A code
+/// missing from the vocabulary resolves to nothing.
+pub async fn resolve(pool: &PgPool, code: &str) -> Result<Vec<CpvCode>> {
+    if !cpv::is_valid(code) {
+        return Err(Error::msg(format!("Invalid CPV code: {code}.")));
+    }
+
+    Ok(query_as!(
+        CpvCode,
+        r#"SELECT code as "code!", label_ro as "label_ro!", label_en as "label_en!"
+            FROM cpv_vocabulary
+            WHERE code = ANY($1)
+                AND EXISTS (SELECT 1 FROM cpv_vocabulary WHERE code = $2)
+            ORDER BY code DESC"#,
+        &cpv::ancestors(code),
+        code
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Inserts the codes, replacing the labels of the ones already known. Returns how many codes
+/// were written.
+pub async fn upsert_many(pool: &PgPool, codes: &[CpvCode]) -> Result<u64> {
+    write_many(pool, codes, true).await
+}
+
+/// Inserts the codes that aren't known yet, keeping the labels of the others. Returns how many
+/// codes were inserted.
+pub async fn insert_missing(pool: &PgPool, codes: &[CpvCode]) -> Result<u64> {
+    write_many(pool, codes, false).await
+}
+
+async fn write_many(pool: &PgPool, codes: &[CpvCode], replace: bool) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut written = 0;
+
+    for chunk in codes.chunks(1000) {
+        let (code, (label_ro, label_en)): (Vec<_>, (Vec<_>, Vec<_>)) = chunk
+            .iter()
+            .map(|c| (c.code.clone(), (c.label_ro.clone(), c.label_en.clone())))
+            .unzip();
+
+        written += query!(
+            r#"INSERT INTO cpv_vocabulary (code, label_ro, label_en)
+                SELECT * FROM UNNEST($1::VARCHAR[], $2::TEXT[], $3::TEXT[])
+                ON CONFLICT (code) DO UPDATE SET
+                    label_ro = EXCLUDED.label_ro,
+                    label_en = EXCLUDED.label_en
+                WHERE $4"#,
+            &code,
+            &label_ro,
+            &label_en,
+            replace
+        )
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+    }
+
+    tx.commit().await?;
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::cpv::{insert_missing, resolve, upsert_many, CpvCode};
+    use anyhow::Result;
+    use sqlx::PgPool;
+
+    #[ignore]
+    #[sqlx::test(fixtures("cpv"))]
+    async fn test_resolve(pool: PgPool) -> Result<()> {
+        {
+            let res = resolve(&pool, "30213100-6").await?;
+
+            let codes: Vec<&str> = res.iter().map(|c| c.code.as_str()).collect();
+
+            assert_eq!(codes, vec!["30213100-6", "30200000-1", "30000000-9"]);
+            assert_eq!(
+                res[0],
+                CpvCode {
+                    code: "30213100-6".into(),
+                    label_ro: "Computere portabile".into(),
+                    label_en: "Portable computers".into(),
+                }
+            );
+        }
+
+        {
+            // known ancestors don't stand in for a code missing from the vocabulary
+            let res = resolve(&pool, "30213300-8").await?;
+
+            assert!(res.is_empty());
+        }
+
+        {
+            let res = resolve(&pool, "45000000-7").await?;
+
+            assert!(res.is_empty());
+        }
+
+        {
+            let res = resolve(&pool, "30213100-1").await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("cpv"))]
+    async fn test_upsert_many(pool: PgPool) -> Result<()> {
+        let codes = vec![
+            CpvCode {
+                code: "30213300-8".into(),
+                label_ro: "Computer de birou".into(),
+                label_en: "Desktop computer".into(),
+            },
+            CpvCode {
+                code: "30213100-6".into(),
+                label_ro: "Calculatoare portabile".into(),
+                label_en: "Portable computers".into(),
+            },
+        ];
+
+        assert_eq!(upsert_many(&pool, &codes).await?, 2);
+
+        let res = resolve(&pool, "30213300-8").await?;
+
+        let codes: Vec<&str> = res.iter().map(|c| c.code.as_str()).collect();
+
+        assert_eq!(codes, vec!["30213300-8", "30200000-1", "30000000-9"]);
+        assert_eq!(
+            resolve(&pool, "30213100-6").await?[0].label_ro,
+            "Calculatoare portabile"
+        );
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("cpv"))]
+    async fn test_insert_missing(pool: PgPool) -> Result<()> {
+        let codes = vec![
+            CpvCode {
+                code: "30213300-8".into(),
+                label_ro: "Computer de birou".into(),
+                label_en: "Desktop computer".into(),
+            },
+            CpvCode {
+                code: "30213100-6".into(),
+                label_ro: "Calculatoare portabile".into(),
+                label_en: "Portable computers".into(),
+            },
+        ];
+
+        assert_eq!(insert_missing(&pool, &codes).await?, 1);
+        assert_eq!(resolve(&pool, "30213300-8").await?.len(), 3);
+        assert_eq!(
+            resolve(&pool, "30213100-6").await?[0].label_ro,
+            "Computere portabile"
+        );
+
+        Ok(())
+    }
+}
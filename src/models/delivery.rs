@@ -0,0 +1,376 @@
+use crate::{models::webhook::WebhookEvent, utils::Pagination};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{query, query_as, types::Json, PgPool};
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "delivery_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Given up on after too many attempts.
+    Failed,
+}
+
+/// A webhook payload queued for delivery, along with the outcome of its last attempt.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Delivery {
+    pub id: i32,
+    pub id_webhook: i32,
+    pub event: WebhookEvent,
+    pub payload: Json<Value>,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: OffsetDateTime,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub delivered_at: Option<OffsetDateTime>,
+}
+
+/// Queues the payload for every enabled webhook of the subscription's owner that listens to
+/// the event, unless the subscription is paused or doesn't send its matches to webhooks.
+/// Returns how many deliveries were queued.
+pub async fn enqueue(
+    pool: &PgPool,
+    id_subscription: i32,
+    event: WebhookEvent,
+    payload: &Value,
+) -> Result<u64> {
+    Ok(query!(
+        r#"INSERT INTO webhook_deliveries (id_webhook, event, payload)
+            SELECT w.id, $2, $3
+            FROM webhooks w
+            JOIN subscriptions s ON s.id_user = w.id_user
+            WHERE s.id = $1 AND w.enabled AND $2 = ANY(w.events)
+                AND subscription_notifies(s.id, 'webhook')"#,
+        id_subscription,
+        event as WebhookEvent,
+        payload
+    )
+    .execute(pool)
+    .await?
+    .rows_affected())
+}
+
+/// Pending deliveries of enabled webhooks whose next attempt is due at `now`, oldest first.
+pub async fn get_due(pool: &PgPool, now: OffsetDateTime, limit: i64) -> Result<Vec<Delivery>> {
+    Ok(query_as!(
+        Delivery,
+        r#"SELECT
+                d.id,
+                d.id_webhook,
+                d.event as "event: WebhookEvent",
+                d.payload as "payload: Json<Value>",
+                d.status as "status: DeliveryStatus",
+                d.attempts,
+                d.next_attempt_at,
+                d.last_status_code,
+                d.last_error,
+                d.created_at,
+                d.delivered_at
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.id_webhook
+            WHERE d.status = 'pending' AND d.next_attempt_at <= $1 AND w.enabled
+            ORDER BY d.next_attempt_at, d.id
+            LIMIT $2"#,
+        now,
+        limit
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// The delivery log of a webhook, newest first.
+pub async fn get_all_of_webhook(
+    pool: &PgPool,
+    id_webhook: usize,
+    pagination: &Pagination,
+) -> Result<Vec<Delivery>> {
+    let id_webhook: i32 = id_webhook.try_into()?;
+
+    Ok(query_as!(
+        Delivery,
+        r#"SELECT
+                id,
+                id_webhook,
+                event as "event: WebhookEvent",
+                payload as "payload: Json<Value>",
+                status as "status: DeliveryStatus",
+                attempts,
+                next_attempt_at,
+                last_status_code,
+                last_error,
+                created_at,
+                delivered_at
+            FROM webhook_deliveries
+            WHERE id_webhook = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            OFFSET $3"#,
+        id_webhook,
+        i64::from(pagination.count),
+        i64::from(pagination.start_index)
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Records a successful attempt.
+pub async fn record_success(
+    pool: &PgPool,
+    id: i32,
+    status_code: i32,
+    at: OffsetDateTime,
+) -> Result<Delivery> {
+    Ok(query_as!(
+        Delivery,
+        r#"UPDATE webhook_deliveries SET
+                status = 'delivered',
+                attempts = attempts + 1,
+                last_status_code = $2,
+                last_error = NULL,
+                delivered_at = $3
+            WHERE id = $1
+            RETURNING
+                id,
+                id_webhook,
+                event as "event: WebhookEvent",
+                payload as "payload: Json<Value>",
+                status as "status: DeliveryStatus",
+                attempts,
+                next_attempt_at,
+                last_status_code,
+                last_error,
+                created_at,
+                delivered_at"#,
+        id,
+        status_code,
+        at
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Records a failed attempt, to be retried at `retry_at`, or given up on when it is `None`.
+pub async fn record_failure(
+    pool: &PgPool,
+    id: i32,
+    status_code: Option<i32>,
+    error: &str,
+    retry_at: Option<OffsetDateTime>,
+) -> Result<Delivery> {
+    Ok(query_as!(
+        Delivery,
+        r#"UPDATE webhook_deliveries SET
+                status = CASE WHEN $4::TIMESTAMPTZ IS NULL
+                    THEN 'failed'::delivery_status
+                    ELSE 'pending'::delivery_status
+                END,
+                attempts = attempts + 1,
+                last_status_code = $2,
+                last_error = $3,
+                next_attempt_at = COALESCE($4, next_attempt_at)
+            WHERE id = $1
+            RETURNING
+                id,
+                id_webhook,
+                event as "event: WebhookEvent",
+                payload as "payload: Json<Value>",
+                status as "status: DeliveryStatus",
+                attempts,
+                next_attempt_at,
+                last_status_code,
+                last_error,
+                created_at,
+                delivered_at"#,
+        id,
+        status_code,
+        error,
+        retry_at
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::{
+            delivery::{
+                enqueue, get_all_of_webhook, get_due, record_failure, record_success,
+                DeliveryStatus,
+            },
+            webhook::{self, WebhookEvent, WebhookParams},
+        },
+        utils::Pagination,
+    };
+    use anyhow::Result;
+    use serde_json::json;
+    use sqlx::{query, PgPool};
+    use time::{Duration, OffsetDateTime};
+
+    async fn webhook(pool: &PgPool, id_user: usize, events: Vec<WebhookEvent>) -> Result<i32> {
+        let params = WebhookParams {
+            url: "http://localhost/hook".into(),
+            secret: None,
+            events,
+        };
+
+        Ok(webhook::create(pool, id_user, &params).await?.id)
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions"))]
+    async fn test_enqueue(pool: PgPool) -> Result<()> {
+        let created = webhook(&pool, 1, vec![WebhookEvent::MatchCreated]).await?;
+        let both = webhook(
+            &pool,
+            1,
+            vec![WebhookEvent::MatchCreated, WebhookEvent::MatchUpdated],
+        )
+        .await?;
+        webhook(&pool, 2, vec![WebhookEvent::MatchCreated]).await?;
+
+        {
+            let res = enqueue(&pool, 1, WebhookEvent::MatchCreated, &json!({"a": 1})).await?;
+
+            assert_eq!(res, 2);
+        }
+
+        {
+            let res = enqueue(&pool, 2, WebhookEvent::MatchUpdated, &json!({"a": 2})).await?;
+
+            assert_eq!(res, 1);
+        }
+
+        {
+            let res = get_due(&pool, OffsetDateTime::now_utc(), 10).await?;
+
+            let webhooks: Vec<i32> = res.iter().map(|d| d.id_webhook).collect();
+
+            assert_eq!(webhooks, vec![created, both, both]);
+            assert_eq!(res[2].payload.0, json!({"a": 2}));
+        }
+
+        {
+            webhook::record_attempt(&pool, created, false, 1).await?;
+
+            let res = get_due(&pool, OffsetDateTime::now_utc(), 10).await?;
+
+            assert_eq!(res.len(), 2);
+        }
+
+        {
+            query!("UPDATE subscriptions SET paused_until = NOW() + INTERVAL '1 day' WHERE id = 1")
+                .execute(&pool)
+                .await?;
+            query!("UPDATE subscriptions SET channels = '{email}' WHERE id = 2")
+                .execute(&pool)
+                .await?;
+
+            assert_eq!(
+                enqueue(&pool, 1, WebhookEvent::MatchCreated, &json!({})).await?,
+                0
+            );
+            assert_eq!(
+                enqueue(&pool, 2, WebhookEvent::MatchCreated, &json!({})).await?,
+                0
+            );
+        }
+
+        {
+            query!("UPDATE subscriptions SET paused_until = NOW() - INTERVAL '1 day' WHERE id = 1")
+                .execute(&pool)
+                .await?;
+            query!(
+                "INSERT INTO notification_preferences (id_user, channels) VALUES (1, '{email}')"
+            )
+            .execute(&pool)
+            .await?;
+
+            assert_eq!(
+                enqueue(&pool, 1, WebhookEvent::MatchCreated, &json!({})).await?,
+                0
+            );
+
+            query!("UPDATE subscriptions SET channels = '{webhook}' WHERE id = 1")
+                .execute(&pool)
+                .await?;
+
+            // the first webhook was disabled by its failed attempt
+            assert_eq!(
+                enqueue(&pool, 1, WebhookEvent::MatchCreated, &json!({})).await?,
+                1
+            );
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions"))]
+    async fn test_record(pool: PgPool) -> Result<()> {
+        let id_webhook = webhook(&pool, 1, vec![WebhookEvent::MatchCreated]).await?;
+
+        enqueue(&pool, 1, WebhookEvent::MatchCreated, &json!({})).await?;
+        enqueue(&pool, 1, WebhookEvent::MatchCreated, &json!({})).await?;
+
+        let now = OffsetDateTime::now_utc();
+        let due = get_due(&pool, now, 10).await?;
+
+        {
+            let res = record_failure(
+                &pool,
+                due[0].id,
+                Some(500),
+                "Internal Server Error",
+                Some(now + Duration::minutes(1)),
+            )
+            .await?;
+
+            assert_eq!(res.status, DeliveryStatus::Pending);
+            assert_eq!(res.attempts, 1);
+            assert_eq!(res.last_status_code, Some(500));
+
+            let res = get_due(&pool, now, 10).await?;
+
+            assert_eq!(res.len(), 1);
+        }
+
+        {
+            let res = record_failure(&pool, due[0].id, None, "timed out", None).await?;
+
+            assert_eq!(res.status, DeliveryStatus::Failed);
+            assert_eq!(res.attempts, 2);
+            assert_eq!(res.last_status_code, None);
+        }
+
+        {
+            let res = record_success(&pool, due[1].id, 204, now).await?;
+
+            assert_eq!(res.status, DeliveryStatus::Delivered);
+            assert!(res.delivered_at.is_some());
+        }
+
+        {
+            let pagination = Pagination {
+                start_index: 0,
+                count: 10,
+            };
+
+            let res = get_all_of_webhook(&pool, id_webhook as usize, &pagination).await?;
+
+            assert_eq!(res.len(), 2);
+            assert_eq!(res[0].id, due[1].id);
+            assert!(get_due(&pool, now + Duration::hours(1), 10)
+                .await?
+                .is_empty());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, PgPool};
+use time::OffsetDateTime;
+
+const TOKEN_LEN: usize = 40;
+
+/// The secret authenticating a user's feeds, since feed readers can't log in.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FeedToken {
+    pub id_user: i32,
+    pub token: String,
+    pub created_at: OffsetDateTime,
+}
+
+/// Issues a new token for the user, invalidating the previous one.
+pub async fn rotate(pool: &PgPool, id_user: usize) -> Result<FeedToken> {
+    let id_user: i32 = id_user.try_into()?;
+
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect();
+
+    Ok(query_as!(
+        FeedToken,
+        r#"
+            SELECT
+                id_user as "id_user!",
+                token as "token!",
+                created_at as "created_at!"
+            FROM rotate_feed_token($1, $2)"#,
+        id_user,
+        token
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+pub async fn get_of_user(pool: &PgPool, id_user: usize) -> Result<FeedToken> {
+    let id_user: i32 = id_user.try_into()?;
+
+    Ok(query_as!(
+        FeedToken,
+        "SELECT * FROM feed_tokens WHERE id_user = $1",
+        id_user
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Whether the token is the user's current one.
+pub async fn verify(pool: &PgPool, id_user: usize, token: &str) -> Result<bool> {
+    let id_user: i32 = id_user.try_into()?;
+
+    Ok(query!(
+        r#"SELECT EXISTS(
+                SELECT 1 FROM feed_tokens WHERE id_user = $1 AND token = $2
+            ) as "exists!""#,
+        id_user,
+        token
+    )
+    .fetch_one(pool)
+    .await?
+    .exists)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::feed_token::{get_of_user, rotate, verify, TOKEN_LEN};
+    use anyhow::Result;
+    use sqlx::PgPool;
+
+    #[ignore]
+    #[sqlx::test(fixtures("users"))]
+    async fn test_rotate_and_verify(pool: PgPool) -> Result<()> {
+        assert!(get_of_user(&pool, 1).await.is_err());
+
+        let first = rotate(&pool, 1).await?;
+
+        assert_eq!(first.id_user, 1);
+        assert_eq!(first.token.len(), TOKEN_LEN);
+        assert_eq!(get_of_user(&pool, 1).await?, first);
+        assert!(verify(&pool, 1, &first.token).await?);
+        assert!(!verify(&pool, 2, &first.token).await?);
+        assert!(!verify(&pool, 1, "").await?);
+
+        let second = rotate(&pool, 1).await?;
+
+        assert_ne!(second.token, first.token);
+        assert!(!verify(&pool, 1, &first.token).await?);
+        assert!(verify(&pool, 1, &second.token).await?);
+
+        assert!(rotate(&pool, 100).await.is_err());
+
+        Ok(())
+    }
+}
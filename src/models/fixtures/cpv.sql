@@ -0,0 +1,5 @@
+DELETE FROM cpv_vocabulary;
+INSERT INTO cpv_vocabulary (code, label_ro, label_en) VALUES
+    ('30000000-9', 'Mașini, echipament și furnituri de birou și computere, cu excepția mobilierului și a pachetelor software', 'Office and computing machinery, equipment and supplies except furniture and software packages'),
+    ('30200000-1', 'Echipament și accesorii pentru computer', 'Computer equipment and supplies'),
+    ('30213100-6', 'Computere portabile', 'Portable computers');
@@ -0,0 +1,2 @@
+SELECT * FROM create_tender_lot(1, 'LOT-0001', 'Tronson Alba Iulia - Sebes', null, 900000, '45233120-6', '2023-03-01 12:00:00+00');
+SELECT * FROM create_tender_lot(1, 'LOT-0002', 'Tronson Sebes - Cugir', null, 600000, '45233120-6', '2023-03-01 12:00:00+00');
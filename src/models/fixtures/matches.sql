@@ -0,0 +1,4 @@
+SELECT * FROM create_match(1, 1, '{cpv,county}', 90, '{"price":"unconstrained","title":"unconstrained","description":"unconstrained","additional_info":"unconstrained","query":"unconstrained","cpv":"passed","county":"passed","authority":"unconstrained","deadline":"unconstrained","procedure_type":"unconstrained","hits":[]}');
+SELECT * FROM create_match(1, 2, '{title}', 60, '{"price":"unconstrained","title":"passed","description":"unconstrained","additional_info":"unconstrained","query":"unconstrained","cpv":"unconstrained","county":"unconstrained","authority":"unconstrained","deadline":"unconstrained","procedure_type":"unconstrained","hits":[{"keyword":"mobilier","field":"title","start":10,"end":18}]}');
+SELECT * FROM create_match(2, 3, '{}', 100, '{"price":"unconstrained","title":"unconstrained","description":"unconstrained","additional_info":"unconstrained","query":"unconstrained","cpv":"unconstrained","county":"unconstrained","authority":"unconstrained","deadline":"unconstrained","procedure_type":"unconstrained","hits":[]}');
+SELECT * FROM create_match(3, 1, '{price}', 75, '{"price":"passed","title":"unconstrained","description":"unconstrained","additional_info":"unconstrained","query":"unconstrained","cpv":"unconstrained","county":"unconstrained","authority":"unconstrained","deadline":"unconstrained","procedure_type":"unconstrained","hits":[]}');
@@ -1,3 +1,3 @@
-SELECT * FROM create_subscription(1, null, null, null, null, null);
-SELECT * FROM create_subscription(1, null, null, null, null, null);
-SELECT * FROM create_subscription(2, null, null, null, null, null);
\ No newline at end of file
+SELECT * FROM create_subscription(1, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null);
+SELECT * FROM create_subscription(1, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null);
+SELECT * FROM create_subscription(2, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null, null);
\ No newline at end of file
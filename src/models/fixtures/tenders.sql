@@ -0,0 +1,3 @@
+SELECT * FROM create_tender('CN1051234', 'Modernizare drum judetean DJ 107', 'Lucrari de reabilitare a drumului judetean', null, 1500000, 'RON', 'Consiliul Judetean Alba', '45233120-6', '2023-02-01 09:00:00+00', '2023-03-01 12:00:00+00', 'Licitatie deschisa', 'AB', '4562141', '2023-02-20 12:00:00+00', '2023-03-01 13:00:00+00');
+SELECT * FROM create_tender('SCN1090001', 'Achizitie mobilier de birou', 'Scaune si birouri pentru sediul administrativ', null, 80000, 'RON', 'Primaria Municipiului Iasi', '39130000-2', '2023-02-03 09:00:00+00', '2023-02-20 12:00:00+00', 'Procedura simplificata', 'IS', '4540712', null, null);
+SELECT * FROM create_tender('CN1051300', 'Servicii de dezvoltare software', null, null, null, 'EUR', 'Compania Nationala de Administrare a Infrastructurii Rutiere', '72000000-5', '2023-02-05 09:00:00+00', null, 'Licitatie deschisa', 'B', '16054368', null, null);
\ No newline at end of file
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, PgExecutor, PgPool};
+use time::OffsetDateTime;
+
+/// A lot of a tender divided into separately awarded parts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Lot {
+    pub id: i32,
+    pub id_tender: i32,
+    pub lot_number: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub estimated_value: Option<i64>,
+    pub cpv_code: Option<String>,
+    pub submission_deadline: Option<OffsetDateTime>,
+}
+
+pub async fn create(executor: impl PgExecutor<'_>, lot: &Lot) -> Result<Lot> {
+    Ok(query_as!(
+        Lot,
+        r#"
+            SELECT
+                id as "id!",
+                id_tender as "id_tender!",
+                lot_number as "lot_number!",
+                title,
+                description,
+                estimated_value,
+                cpv_code,
+                submission_deadline
+            FROM create_tender_lot($1, $2, $3, $4, $5, $6, $7)"#,
+        lot.id_tender,
+        lot.lot_number,
+        lot.title,
+        lot.description,
+        lot.estimated_value,
+        lot.cpv_code,
+        lot.submission_deadline
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn get_all_of_tender(pool: &PgPool, id_tender: usize) -> Result<Vec<Lot>> {
+    let id_tender: i32 = id_tender.try_into()?;
+
+    Ok(query_as!(
+        Lot,
+        "SELECT * FROM tender_lots WHERE id_tender = $1 ORDER BY lot_number",
+        id_tender
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Removes the lots of a tender, returning how many there were.
+pub async fn delete_all_of_tender(executor: impl PgExecutor<'_>, id_tender: i32) -> Result<u64> {
+    Ok(
+        query!("DELETE FROM tender_lots WHERE id_tender = $1", id_tender)
+            .execute(executor)
+            .await?
+            .rows_affected(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::{
+        lot::{create, delete_all_of_tender, get_all_of_tender},
+        Lot,
+    };
+    use anyhow::Result;
+    use sqlx::PgPool;
+    use time::macros::datetime;
+
+    fn lot() -> Lot {
+        Lot {
+            id: 3,
+            id_tender: 2,
+            lot_number: "LOT-0001".into(),
+            title: Some("Scaune ergonomice".into()),
+            description: None,
+            estimated_value: Some(30_000),
+            cpv_code: Some("39130000-2".into()),
+            submission_deadline: Some(datetime!(2023-02-20 12:00 UTC)),
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders", "lots"))]
+    async fn test_create(pool: PgPool) -> Result<()> {
+        {
+            let l = lot();
+
+            let res = create(&pool, &l).await?;
+
+            assert_eq!(res, l);
+        }
+
+        {
+            let res = create(&pool, &lot()).await;
+
+            assert!(res.is_err());
+        }
+
+        {
+            let l = Lot {
+                id_tender: 10,
+                ..lot()
+            };
+
+            let res = create(&pool, &l).await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders", "lots"))]
+    async fn test_get_all_of_tender(pool: PgPool) -> Result<()> {
+        {
+            let res = get_all_of_tender(&pool, 1).await?;
+
+            let numbers: Vec<&str> = res.iter().map(|l| l.lot_number.as_str()).collect();
+
+            assert_eq!(numbers, vec!["LOT-0001", "LOT-0002"]);
+        }
+
+        {
+            let res = get_all_of_tender(&pool, 3).await?;
+
+            assert!(res.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders", "lots"))]
+    async fn test_delete_all_of_tender(pool: PgPool) -> Result<()> {
+        {
+            let res = delete_all_of_tender(&pool, 1).await?;
+
+            assert_eq!(res, 2);
+        }
+
+        {
+            let res = get_all_of_tender(&pool, 1).await?;
+
+            assert!(res.is_empty());
+        }
+
+        Ok(())
+    }
+}
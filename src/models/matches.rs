@@ -0,0 +1,565 @@
+use crate::{matching::MatchResult, utils::Pagination};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, types::Json, PgPool};
+use time::OffsetDateTime;
+
+/// A tender that matched a subscription, and when the subscriber was told about it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Match {
+    pub id: i32,
+    pub id_subscription: i32,
+    pub id_tender: i32,
+    /// The constrained criteria the tender satisfied.
+    pub matched_fields: Vec<String>,
+    /// Relevance from 0 to 100, see [`score`](crate::matching::score).
+    pub score: Option<i32>,
+    /// Why the tender matched: the outcome of every criterion and the keyword hits.
+    pub explanation: Json<MatchResult>,
+    pub created_at: OffsetDateTime,
+    pub notified_at: Option<OffsetDateTime>,
+}
+
+/// Records a match, unless the subscription was already matched to the tender, in which case
+/// `None` is returned.
+pub async fn create(pool: &PgPool, m: &Match) -> Result<Option<Match>> {
+    Ok(query_as!(
+        Match,
+        r#"
+            SELECT
+                id as "id!",
+                id_subscription as "id_subscription!",
+                id_tender as "id_tender!",
+                matched_fields as "matched_fields!",
+                score,
+                explanation as "explanation!: Json<MatchResult>",
+                created_at as "created_at!",
+                notified_at
+            FROM create_match($1, $2, $3, $4, $5)"#,
+        m.id_subscription,
+        m.id_tender,
+        &m.matched_fields,
+        m.score,
+        &m.explanation as _
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Most relevant first, and newest first among equally relevant matches.
+pub async fn get_all_of_subscription(
+    pool: &PgPool,
+    id_subscription: usize,
+    pagination: &Pagination,
+) -> Result<Vec<Match>> {
+    let id_subscription: i32 = id_subscription.try_into()?;
+
+    Ok(query_as!(
+        Match,
+        r#"SELECT
+                id,
+                id_subscription,
+                id_tender,
+                matched_fields,
+                score,
+                explanation as "explanation: Json<MatchResult>",
+                created_at,
+                notified_at
+            FROM matches
+            WHERE id_subscription = $1
+            ORDER BY score DESC NULLS LAST, created_at DESC, id DESC
+            LIMIT $2
+            OFFSET $3"#,
+        id_subscription,
+        i64::from(pagination.count),
+        i64::from(pagination.start_index)
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Matches of all the user's subscriptions, ordered like [`get_all_of_subscription`].
+pub async fn get_all_of_user(
+    pool: &PgPool,
+    id_user: usize,
+    pagination: &Pagination,
+) -> Result<Vec<Match>> {
+    let id_user: i32 = id_user.try_into()?;
+
+    Ok(query_as!(
+        Match,
+        r#"SELECT
+                m.id,
+                m.id_subscription,
+                m.id_tender,
+                m.matched_fields,
+                m.score,
+                m.explanation as "explanation: Json<MatchResult>",
+                m.created_at,
+                m.notified_at
+            FROM matches m
+            JOIN subscriptions s ON s.id = m.id_subscription
+            WHERE s.id_user = $1
+            ORDER BY m.score DESC NULLS LAST, m.created_at DESC, m.id DESC
+            LIMIT $2
+            OFFSET $3"#,
+        id_user,
+        i64::from(pagination.count),
+        i64::from(pagination.start_index)
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// The user's latest matches, each with when it last changed: when it was recorded, or when
+/// its tender was materially revised afterwards. Most recently changed first.
+pub async fn get_recent_of_user(
+    pool: &PgPool,
+    id_user: usize,
+    limit: i64,
+) -> Result<Vec<(Match, OffsetDateTime)>> {
+    let id_user: i32 = id_user.try_into()?;
+
+    let rows = query!(
+        r#"SELECT
+                m.id,
+                m.id_subscription,
+                m.id_tender,
+                m.matched_fields,
+                m.score,
+                m.explanation as "explanation: Json<MatchResult>",
+                m.created_at,
+                m.notified_at,
+                GREATEST(m.created_at, r.revised_at) as "updated_at!"
+            FROM matches m
+            JOIN subscriptions s ON s.id = m.id_subscription
+            LEFT JOIN LATERAL (
+                SELECT MAX(created_at) as revised_at FROM tender_revisions
+                WHERE id_tender = m.id_tender AND material
+            ) r ON TRUE
+            WHERE s.id_user = $1
+            ORDER BY 9 DESC, m.id DESC
+            LIMIT $2"#,
+        id_user,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let m = Match {
+                id: row.id,
+                id_subscription: row.id_subscription,
+                id_tender: row.id_tender,
+                matched_fields: row.matched_fields,
+                score: row.score,
+                explanation: row.explanation,
+                created_at: row.created_at,
+                notified_at: row.notified_at,
+            };
+
+            (m, row.updated_at)
+        })
+        .collect())
+}
+
+/// Users with matches they weren't notified about yet, of subscriptions that send them by email
+/// and aren't paused.
+pub async fn get_users_with_unnotified(pool: &PgPool) -> Result<Vec<i32>> {
+    Ok(query!(
+        r#"SELECT DISTINCT s.id_user FROM matches m
+            JOIN subscriptions s ON s.id = m.id_subscription
+            WHERE m.notified_at IS NULL AND subscription_notifies(s.id, 'email')
+            ORDER BY s.id_user"#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| row.id_user)
+    .collect())
+}
+
+/// The matches the user wasn't notified about yet of all the user's subscriptions that send them
+/// by email and aren't paused, most relevant first.
+pub async fn get_unnotified_of_user(pool: &PgPool, id_user: i32) -> Result<Vec<Match>> {
+    Ok(query_as!(
+        Match,
+        r#"SELECT
+                m.id,
+                m.id_subscription,
+                m.id_tender,
+                m.matched_fields,
+                m.score,
+                m.explanation as "explanation: Json<MatchResult>",
+                m.created_at,
+                m.notified_at
+            FROM matches m
+            JOIN subscriptions s ON s.id = m.id_subscription
+            WHERE s.id_user = $1 AND m.notified_at IS NULL
+                AND subscription_notifies(s.id, 'email')
+            ORDER BY m.score DESC NULLS LAST, m.created_at DESC, m.id DESC"#,
+        id_user
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// When the user was last sent a digest, if ever.
+pub async fn get_last_notified_of_user(
+    pool: &PgPool,
+    id_user: i32,
+) -> Result<Option<OffsetDateTime>> {
+    Ok(query!(
+        r#"SELECT MAX(m.notified_at) as last FROM matches m
+            JOIN subscriptions s ON s.id = m.id_subscription
+            WHERE s.id_user = $1"#,
+        id_user
+    )
+    .fetch_one(pool)
+    .await?
+    .last)
+}
+
+/// Records that the matches were delivered at `at`. Returns how many matches were affected.
+pub async fn mark_notified(pool: &PgPool, ids: &[i32], at: OffsetDateTime) -> Result<u64> {
+    Ok(query!(
+        "UPDATE matches SET notified_at = $2 WHERE id = ANY($1)",
+        ids,
+        at
+    )
+    .execute(pool)
+    .await?
+    .rows_affected())
+}
+
+/// The matches recorded for a tender, oldest first.
+pub async fn get_all_of_tender(pool: &PgPool, id_tender: i32) -> Result<Vec<Match>> {
+    Ok(query_as!(
+        Match,
+        r#"SELECT
+                id,
+                id_subscription,
+                id_tender,
+                matched_fields,
+                score,
+                explanation as "explanation: Json<MatchResult>",
+                created_at,
+                notified_at
+            FROM matches
+            WHERE id_tender = $1
+            ORDER BY id"#,
+        id_tender
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Replaces the matched fields, score and explanation of a match whose tender was materially
+/// revised, and marks it as not notified yet so the revision is sent to the subscriber again.
+pub async fn revise(pool: &PgPool, m: &Match) -> Result<Match> {
+    Ok(query_as!(
+        Match,
+        r#"UPDATE matches
+            SET matched_fields = $2, score = $3, explanation = $4, notified_at = NULL
+            WHERE id = $1
+            RETURNING
+                id,
+                id_subscription,
+                id_tender,
+                matched_fields,
+                score,
+                explanation as "explanation: Json<MatchResult>",
+                created_at,
+                notified_at"#,
+        m.id,
+        &m.matched_fields,
+        m.score,
+        &m.explanation as _
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Deletes a match whose tender no longer matches the subscription. Returns how many matches
+/// were deleted.
+pub async fn delete(pool: &PgPool, id: i32) -> Result<u64> {
+    Ok(query!("DELETE FROM matches WHERE id = $1", id)
+        .execute(pool)
+        .await?
+        .rows_affected())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        matching::{Hit, MatchResult, Outcome, TextField},
+        models::matches::{
+            create, delete, get_all_of_subscription, get_all_of_tender, get_all_of_user,
+            get_last_notified_of_user, get_recent_of_user, get_unnotified_of_user,
+            get_users_with_unnotified, mark_notified, revise, Match,
+        },
+        utils::Pagination,
+    };
+    use anyhow::Result;
+    use sqlx::{query, types::Json, PgPool};
+    use time::OffsetDateTime;
+
+    fn explanation() -> MatchResult {
+        MatchResult {
+            price: Outcome::Unconstrained,
+            title: Outcome::Passed,
+            description: Outcome::Unconstrained,
+            additional_info: Outcome::Unconstrained,
+            query: Outcome::Unconstrained,
+            cpv: Outcome::Passed,
+            county: Outcome::Unconstrained,
+            authority: Outcome::Unconstrained,
+            deadline: Outcome::Unconstrained,
+            procedure_type: Outcome::Unconstrained,
+            hits: vec![Hit {
+                keyword: "drum".into(),
+                field: TextField::Title,
+                start: 11,
+                end: 15,
+            }],
+        }
+    }
+
+    fn pagination(start_index: u32, count: u32) -> Pagination {
+        Pagination { start_index, count }
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions", "tenders"))]
+    async fn test_create(pool: PgPool) -> Result<()> {
+        let m = Match {
+            id: 0,
+            id_subscription: 2,
+            id_tender: 1,
+            matched_fields: vec!["title".into(), "cpv".into()],
+            score: Some(80),
+            explanation: Json(explanation()),
+            created_at: OffsetDateTime::now_utc(),
+            notified_at: None,
+        };
+
+        {
+            let res = create(&pool, &m).await?.unwrap();
+
+            assert_eq!(res.id_subscription, 2);
+            assert_eq!(res.id_tender, 1);
+            assert_eq!(res.matched_fields, m.matched_fields);
+            assert_eq!(res.score, Some(80));
+            assert_eq!(res.explanation.0, explanation());
+        }
+
+        {
+            let res = create(&pool, &m).await?;
+
+            assert_eq!(res, None);
+        }
+
+        {
+            let m = Match {
+                id_tender: 10,
+                ..m.clone()
+            };
+
+            let res = create(&pool, &m).await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions", "tenders", "matches"))]
+    async fn test_get_all_of_subscription(pool: PgPool) -> Result<()> {
+        {
+            let res = get_all_of_subscription(&pool, 1, &pagination(0, 10)).await?;
+
+            let tenders: Vec<i32> = res.iter().map(|m| m.id_tender).collect();
+
+            assert_eq!(tenders, vec![1, 2]);
+            assert_eq!(res[1].explanation.hits[0].keyword, "mobilier");
+        }
+
+        {
+            let res = get_all_of_subscription(&pool, 1, &pagination(1, 10)).await?;
+
+            assert_eq!(res.len(), 1);
+            assert_eq!(res[0].matched_fields, vec!["title"]);
+            assert_eq!(res[0].score, Some(60));
+            assert_eq!(res[0].explanation.title, Outcome::Passed);
+        }
+
+        {
+            let res = get_all_of_subscription(&pool, 10, &pagination(0, 10)).await?;
+
+            assert!(res.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions", "tenders", "matches"))]
+    async fn test_get_all_of_user(pool: PgPool) -> Result<()> {
+        {
+            let res = get_all_of_user(&pool, 1, &pagination(0, 10)).await?;
+
+            let subscriptions: Vec<i32> = res.iter().map(|m| m.id_subscription).collect();
+
+            assert_eq!(subscriptions, vec![2, 1, 1]);
+        }
+
+        {
+            let res = get_all_of_user(&pool, 2, &pagination(0, 1)).await?;
+
+            assert_eq!(res.len(), 1);
+            assert_eq!(res[0].id_subscription, 3);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions", "tenders", "matches"))]
+    async fn test_get_recent_of_user(pool: PgPool) -> Result<()> {
+        query!("UPDATE matches SET created_at = '2023-02-10 10:00Z'::TIMESTAMPTZ + id * INTERVAL '1 hour'")
+            .execute(&pool)
+            .await?;
+
+        {
+            let res = get_recent_of_user(&pool, 1, 10).await?;
+
+            let tenders: Vec<i32> = res.iter().map(|(m, _)| m.id_tender).collect();
+
+            assert_eq!(tenders, vec![3, 2, 1]);
+            assert!(res
+                .iter()
+                .all(|(m, updated_at)| m.created_at == *updated_at));
+        }
+
+        query!(
+            r#"INSERT INTO tender_revisions (id_tender, revision, changes, material, created_at)
+                VALUES (1, 1, '[]', TRUE, '2023-02-11 10:00Z'), (2, 1, '[]', FALSE, '2023-02-12 10:00Z')"#
+        )
+        .execute(&pool)
+        .await?;
+
+        {
+            let res = get_recent_of_user(&pool, 1, 2).await?;
+
+            let tenders: Vec<i32> = res.iter().map(|(m, _)| m.id_tender).collect();
+
+            assert_eq!(tenders, vec![1, 3]);
+            assert_eq!(res[0].1, time::macros::datetime!(2023-02-11 10:00 UTC));
+        }
+
+        {
+            let res = get_recent_of_user(&pool, 2, 10).await?;
+
+            assert_eq!(res.len(), 1);
+            assert_eq!(res[0].0.id_subscription, 3);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions", "tenders", "matches"))]
+    async fn test_revise_and_delete(pool: PgPool) -> Result<()> {
+        query!("UPDATE matches SET notified_at = NOW()")
+            .execute(&pool)
+            .await?;
+
+        let recorded = get_all_of_tender(&pool, 1).await?;
+
+        assert_eq!(recorded.len(), 2);
+        assert!(recorded.iter().all(|m| m.id_tender == 1));
+
+        {
+            let m = Match {
+                matched_fields: vec!["title".into()],
+                score: Some(40),
+                explanation: Json(explanation()),
+                ..recorded[0].clone()
+            };
+
+            let res = revise(&pool, &m).await?;
+
+            assert_eq!(res.id, m.id);
+            assert_eq!(res.matched_fields, m.matched_fields);
+            assert_eq!(res.score, Some(40));
+            assert_eq!(res.explanation.0, explanation());
+            assert_eq!(res.notified_at, None);
+        }
+
+        {
+            assert_eq!(delete(&pool, recorded[1].id).await?, 1);
+            assert_eq!(delete(&pool, recorded[1].id).await?, 0);
+        }
+
+        {
+            let res = get_all_of_subscription(&pool, 1, &pagination(0, 10)).await?;
+
+            assert!(res
+                .iter()
+                .any(|m| m.id_tender == 1 && m.notified_at.is_none()));
+            assert!(res
+                .iter()
+                .any(|m| m.id_tender == 2 && m.notified_at.is_some()));
+            assert_eq!(get_all_of_tender(&pool, 1).await?.len(), 1);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions", "tenders", "matches"))]
+    async fn test_unnotified(pool: PgPool) -> Result<()> {
+        {
+            let res = get_users_with_unnotified(&pool).await?;
+
+            assert_eq!(res, vec![1, 2]);
+        }
+
+        {
+            let res = get_unnotified_of_user(&pool, 1).await?;
+
+            let scores: Vec<Option<i32>> = res.iter().map(|m| m.score).collect();
+
+            assert_eq!(scores, vec![Some(100), Some(90), Some(60)]);
+        }
+
+        {
+            let ids: Vec<i32> = get_unnotified_of_user(&pool, 2)
+                .await?
+                .iter()
+                .map(|m| m.id)
+                .collect();
+
+            assert_eq!(get_last_notified_of_user(&pool, 2).await?, None);
+
+            let at = time::macros::datetime!(2023-02-15 08:00 UTC);
+            let res = mark_notified(&pool, &ids, at).await?;
+
+            assert_eq!(res, 1);
+            assert_eq!(get_last_notified_of_user(&pool, 2).await?, Some(at));
+            assert_eq!(get_last_notified_of_user(&pool, 1).await?, None);
+        }
+
+        {
+            let res = get_users_with_unnotified(&pool).await?;
+
+            assert_eq!(res, vec![1]);
+            assert!(get_unnotified_of_user(&pool, 2).await?.is_empty());
+        }
+
+        Ok(())
+    }
+}
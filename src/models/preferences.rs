@@ -0,0 +1,285 @@
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    postgres::{PgHasArrayType, PgTypeInfo},
+    query_as, PgPool,
+};
+use time::{macros::time, Time};
+use time_tz::{timezones, Tz};
+
+pub const DEFAULT_TIMEZONE: &str = "Europe/Bucharest";
+
+// times of day are written as "08:30"
+time::serde::format_description!(clock, Time, "[hour]:[minute]");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "digest_frequency", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    /// As soon as matches are found.
+    Instant,
+    /// At most once an hour.
+    Hourly,
+    /// Once a day at the delivery time.
+    Daily,
+    /// On Mondays at the delivery time.
+    Weekly,
+}
+
+/// Where matches are sent. Subscriptions can pick their own, overriding the user's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "notification_channel", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    /// The digest email.
+    Email,
+    /// The user's [`Webhook`](crate::models::Webhook)s, as soon as matches are found.
+    Webhook,
+}
+
+impl PgHasArrayType for Channel {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_notification_channel")
+    }
+}
+
+/// How and when a user wants their digests. Times of day are in the user's timezone.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Preferences {
+    pub id_user: i32,
+    pub frequency: Frequency,
+    /// When daily and weekly digests are sent.
+    #[serde(with = "clock")]
+    pub delivery_time: Time,
+    /// No digest is sent from the start of the quiet hours until their end, which may be on the
+    /// next day.
+    #[serde(with = "clock::option")]
+    pub quiet_hours_start: Option<Time>,
+    #[serde(with = "clock::option")]
+    pub quiet_hours_end: Option<Time>,
+    /// IANA name, like `Europe/Bucharest`.
+    pub timezone: String,
+    /// Channels of the subscriptions that don't pick their own.
+    pub channels: Vec<Channel>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            id_user: 0,
+            frequency: Frequency::Instant,
+            delivery_time: time!(08:00),
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            timezone: DEFAULT_TIMEZONE.into(),
+            channels: vec![Channel::Email, Channel::Webhook],
+        }
+    }
+}
+
+impl Preferences {
+    /// The user's timezone, or the default one for a name that isn't known anymore.
+    pub fn tz(&self) -> &'static Tz {
+        timezones::get_by_name(&self.timezone).unwrap_or(timezones::db::europe::BUCHAREST)
+    }
+}
+
+pub fn validate(prefs: &Preferences) -> Result<()> {
+    if timezones::get_by_name(&prefs.timezone).is_none() {
+        return Err(Error::msg(format!(
+            "Unknown timezone {}, expected an IANA name like {DEFAULT_TIMEZONE}.",
+            prefs.timezone
+        )));
+    }
+
+    match (prefs.quiet_hours_start, prefs.quiet_hours_end) {
+        (Some(start), Some(end)) if start == end => Err(Error::msg(
+            "The quiet hours must start and end at different times.",
+        )),
+        (Some(_), None) | (None, Some(_)) => Err(Error::msg(
+            "Expected both the start and the end of the quiet hours, or neither.",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The user's preferences, or the defaults if they never set any.
+pub async fn get_of_user(pool: &PgPool, id_user: usize) -> Result<Preferences> {
+    let id_user: i32 = id_user.try_into()?;
+
+    let prefs = query_as!(
+        Preferences,
+        r#"SELECT
+                id_user,
+                frequency as "frequency: Frequency",
+                delivery_time,
+                quiet_hours_start,
+                quiet_hours_end,
+                timezone,
+                channels as "channels: Vec<Channel>"
+            FROM notification_preferences WHERE id_user = $1"#,
+        id_user
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(prefs.unwrap_or(Preferences {
+        id_user,
+        ..Default::default()
+    }))
+}
+
+pub async fn set(pool: &PgPool, id_user: usize, prefs: &Preferences) -> Result<Preferences> {
+    let id_user: i32 = id_user.try_into()?;
+
+    Ok(query_as!(
+        Preferences,
+        r#"
+            SELECT
+                id_user as "id_user!",
+                frequency as "frequency!: Frequency",
+                delivery_time as "delivery_time!",
+                quiet_hours_start,
+                quiet_hours_end,
+                timezone as "timezone!",
+                channels as "channels!: Vec<Channel>"
+            FROM set_notification_preferences($1, $2, $3, $4, $5, $6, $7)"#,
+        id_user,
+        prefs.frequency as _,
+        prefs.delivery_time,
+        prefs.quiet_hours_start,
+        prefs.quiet_hours_end,
+        prefs.timezone,
+        &prefs.channels as &[Channel]
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::preferences::{get_of_user, set, validate, Channel, Frequency, Preferences};
+    use anyhow::Result;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use time::macros::time;
+
+    fn prefs() -> Preferences {
+        Preferences {
+            id_user: 1,
+            frequency: Frequency::Daily,
+            delivery_time: time!(07:30),
+            quiet_hours_start: Some(time!(22:00)),
+            quiet_hours_end: Some(time!(07:00)),
+            timezone: "Europe/London".into(),
+            channels: vec![Channel::Email],
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let value = serde_json::to_value(prefs()).unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "id_user": 1,
+                "frequency": "daily",
+                "delivery_time": "07:30",
+                "quiet_hours_start": "22:00",
+                "quiet_hours_end": "07:00",
+                "timezone": "Europe/London",
+                "channels": ["email"]
+            })
+        );
+
+        let prefs: Preferences =
+            serde_json::from_value(json!({"frequency": "weekly", "delivery_time": "18:15"}))
+                .unwrap();
+
+        assert_eq!(prefs.frequency, Frequency::Weekly);
+        assert_eq!(prefs.delivery_time, time!(18:15));
+        assert_eq!(prefs.quiet_hours_start, None);
+        assert_eq!(prefs.timezone, "Europe/Bucharest");
+        assert_eq!(prefs.channels, vec![Channel::Email, Channel::Webhook]);
+
+        assert!(serde_json::from_value::<Preferences>(json!({"delivery_time": "25:00"})).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&prefs()).is_ok());
+        assert!(validate(&Preferences::default()).is_ok());
+
+        let cases = [
+            (
+                Preferences {
+                    timezone: "Europe/Atlantis".into(),
+                    ..prefs()
+                },
+                "Unknown timezone Europe/Atlantis, expected an IANA name like Europe/Bucharest.",
+            ),
+            (
+                Preferences {
+                    quiet_hours_end: None,
+                    ..prefs()
+                },
+                "Expected both the start and the end of the quiet hours, or neither.",
+            ),
+            (
+                Preferences {
+                    quiet_hours_end: Some(time!(22:00)),
+                    ..prefs()
+                },
+                "The quiet hours must start and end at different times.",
+            ),
+        ];
+
+        for (prefs, expected) in cases {
+            assert_eq!(validate(&prefs).unwrap_err().to_string(), expected);
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users"))]
+    async fn test_get_and_set(pool: PgPool) -> Result<()> {
+        {
+            let res = get_of_user(&pool, 1).await?;
+
+            assert_eq!(
+                res,
+                Preferences {
+                    id_user: 1,
+                    ..Default::default()
+                }
+            );
+        }
+
+        {
+            let res = set(&pool, 1, &prefs()).await?;
+
+            assert_eq!(res, prefs());
+            assert_eq!(get_of_user(&pool, 1).await?, prefs());
+        }
+
+        {
+            let update = Preferences {
+                frequency: Frequency::Instant,
+                channels: vec![Channel::Webhook],
+                quiet_hours_start: None,
+                quiet_hours_end: None,
+                ..prefs()
+            };
+
+            let res = set(&pool, 1, &update).await?;
+
+            assert_eq!(res, update);
+            assert_eq!(get_of_user(&pool, 2).await?.frequency, Frequency::Instant);
+        }
+
+        assert!(set(&pool, 100, &prefs()).await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use crate::revision::{self, Change};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, types::Json, PgExecutor, PgPool};
+use time::OffsetDateTime;
+
+/// The fields changed by a republication of a tender's notice, numbered from 1.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Revision {
+    pub id: i32,
+    pub id_tender: i32,
+    pub revision: i32,
+    pub changes: Json<Vec<Change>>,
+    pub material: bool,
+    pub created_at: OffsetDateTime,
+}
+
+pub async fn create(
+    executor: impl PgExecutor<'_>,
+    id_tender: i32,
+    changes: &[Change],
+) -> Result<Revision> {
+    Ok(query_as!(
+        Revision,
+        r#"
+            SELECT
+                id as "id!",
+                id_tender as "id_tender!",
+                revision as "revision!",
+                changes as "changes!: Json<Vec<Change>>",
+                material as "material!",
+                created_at as "created_at!"
+            FROM create_tender_revision($1, $2, $3)"#,
+        id_tender,
+        Json(changes) as _,
+        revision::is_material(changes)
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn get_all_of_tender(pool: &PgPool, id_tender: usize) -> Result<Vec<Revision>> {
+    let id_tender: i32 = id_tender.try_into()?;
+
+    Ok(query_as!(
+        Revision,
+        r#"
+            SELECT
+                id,
+                id_tender,
+                revision,
+                changes as "changes: Json<Vec<Change>>",
+                material,
+                created_at
+            FROM tender_revisions
+            WHERE id_tender = $1
+            ORDER BY revision"#,
+        id_tender
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::revision::{create, get_all_of_tender},
+        revision::Change,
+    };
+    use anyhow::Result;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    fn change(field: &str) -> Change {
+        Change {
+            field: field.into(),
+            old: json!(null),
+            new: json!("2023-03-08 12:00:00.0 +00:00:00"),
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_create(pool: PgPool) -> Result<()> {
+        {
+            let res = create(&pool, 1, &[change("description")]).await?;
+
+            assert_eq!(res.revision, 1);
+            assert!(!res.material);
+            assert_eq!(res.changes.0, vec![change("description")]);
+        }
+
+        {
+            let res = create(&pool, 1, &[change("submission_deadline")]).await?;
+
+            assert_eq!(res.revision, 2);
+            assert!(res.material);
+        }
+
+        {
+            let res = create(&pool, 2, &[change("title")]).await?;
+
+            assert_eq!(res.revision, 1);
+        }
+
+        {
+            let res = create(&pool, 10, &[change("title")]).await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_get_all_of_tender(pool: PgPool) -> Result<()> {
+        create(&pool, 1, &[change("description")]).await?;
+        create(&pool, 1, &[change("submission_deadline")]).await?;
+
+        {
+            let res = get_all_of_tender(&pool, 1).await?;
+
+            let revisions: Vec<i32> = res.iter().map(|r| r.revision).collect();
+
+            assert_eq!(revisions, vec![1, 2]);
+        }
+
+        {
+            let res = get_all_of_tender(&pool, 2).await?;
+
+            assert!(res.is_empty());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use sqlx::{query, PgPool};
+
+/// The high-water mark a tender source resumes polling from, if it was polled before.
+pub async fn get(pool: &PgPool, source: &str) -> Result<Option<String>> {
+    Ok(query!(
+        "SELECT high_water_mark FROM source_cursors WHERE source = $1",
+        source
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|row| row.high_water_mark))
+}
+
+pub async fn set(pool: &PgPool, source: &str, high_water_mark: &str) -> Result<()> {
+    query!(
+        r#"
+            INSERT INTO source_cursors (source, high_water_mark)
+            VALUES ($1, $2)
+            ON CONFLICT (source) DO UPDATE
+            SET high_water_mark = EXCLUDED.high_water_mark, updated_at = NOW()"#,
+        source,
+        high_water_mark
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::source_cursor::{get, set};
+    use anyhow::Result;
+    use sqlx::PgPool;
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_get_and_set(pool: PgPool) -> Result<()> {
+        {
+            let res = get(&pool, "directory:notices").await?;
+
+            assert_eq!(res, None);
+        }
+
+        {
+            set(&pool, "directory:notices", "2023-02-10.json").await?;
+            set(&pool, "directory:notices", "2023-02-11.json").await?;
+            set(&pool, "seap", "1090001").await?;
+
+            let res = get(&pool, "directory:notices").await?;
+
+            assert_eq!(res.as_deref(), Some("2023-02-11.json"));
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,13 @@
+use crate::matching::{normalize::normalize_keywords, query::Query, MatchMode};
+use crate::models::preferences::Channel;
 use crate::utils::{Email, Pagination};
-use anyhow::Result;
+use crate::{counties, cpv, cui, procedure::ProcedureType};
+use anyhow::{Error, Result};
 use serde::{Deserialize, Serialize};
-use sqlx::{query_as, PgPool};
+use sqlx::{query, query_as, types::Json, PgPool};
+use time::OffsetDateTime;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Subscription {
     // #[serde(default)]
     pub id: i32,
@@ -13,9 +17,146 @@ pub struct Subscription {
     pub title_keywords: Option<Vec<String>>,
     pub desc_keywords: Option<Vec<String>>,
     pub additional_info_keywords: Option<Vec<String>>,
+    #[serde(default)]
+    pub title_match_mode: MatchMode,
+    #[serde(default)]
+    pub desc_match_mode: MatchMode,
+    #[serde(default)]
+    pub additional_info_match_mode: MatchMode,
+    pub query: Option<String>,
+    pub cpv_codes: Option<Vec<String>>,
+    pub counties: Option<Vec<String>>,
+    pub included_authorities: Option<Json<Vec<AuthorityFilter>>>,
+    pub excluded_authorities: Option<Json<Vec<AuthorityFilter>>>,
+    pub title_exclude_keywords: Option<Vec<String>>,
+    pub desc_exclude_keywords: Option<Vec<String>>,
+    pub additional_info_exclude_keywords: Option<Vec<String>>,
+    pub min_days_to_deadline: Option<i32>,
+    pub procedure_types: Option<Vec<ProcedureType>>,
+    pub min_score: Option<i32>,
+    /// Where the matches are sent, the user's
+    /// [`Preferences::channels`](crate::models::Preferences::channels) if `None`.
+    pub channels: Option<Vec<Channel>>,
+    /// Nothing is matched nor sent before this time.
+    pub paused_until: Option<OffsetDateTime>,
+}
+
+impl Subscription {
+    pub fn is_paused(&self, now: OffsetDateTime) -> bool {
+        self.paused_until.is_some_and(|until| until > now)
+    }
+}
+
+/// A contracting authority identified by its CUI. Tenders that only carry the authority's
+/// name are recognised by any of the aliases.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuthorityFilter {
+    pub cui: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+pub fn validate(sub: &Subscription) -> Result<()> {
+    if let Some(query) = &sub.query {
+        Query::parse(query)?;
+    }
+
+    if let Some(cpv_codes) = &sub.cpv_codes {
+        cpv::validate(cpv_codes)?;
+    }
+
+    if sub.min_days_to_deadline.is_some_and(|days| days < 0) {
+        return Err(Error::msg("min_days_to_deadline can't be negative."));
+    }
+
+    if sub
+        .min_score
+        .is_some_and(|score| !(0..=100).contains(&score))
+    {
+        return Err(Error::msg("min_score must be between 0 and 100."));
+    }
+
+    if let Some(sub_counties) = &sub.counties {
+        counties::validate(sub_counties)?;
+    }
+
+    let included = sub
+        .included_authorities
+        .as_deref()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let excluded = sub
+        .excluded_authorities
+        .as_deref()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    for authority in included.iter().chain(excluded) {
+        if !cui::is_valid(&authority.cui) {
+            return Err(Error::msg(format!("Invalid CUI: {}.", authority.cui)));
+        }
+    }
+
+    let cui = included.iter().map(|a| cui::normalize(&a.cui)).find(|cui| {
+        excluded
+            .iter()
+            .any(|authority| cui::normalize(&authority.cui) == *cui)
+    });
+
+    if let Some(cui) = cui {
+        return Err(Error::msg(format!(
+            "Authority {cui} is both included and excluded."
+        )));
+    }
+
+    Ok(())
+}
+
+fn normalize_authorities(
+    authorities: Option<&[AuthorityFilter]>,
+) -> Option<Json<Vec<AuthorityFilter>>> {
+    let mut normalized: Vec<AuthorityFilter> = Vec::new();
+
+    for authority in authorities? {
+        let cui = cui::normalize(&authority.cui);
+
+        let index = match normalized.iter().position(|a| a.cui == cui) {
+            Some(index) => index,
+            None => {
+                normalized.push(AuthorityFilter {
+                    cui,
+                    aliases: Vec::new(),
+                });
+                normalized.len() - 1
+            }
+        };
+
+        for alias in &authority.aliases {
+            let alias = alias.trim();
+
+            if !alias.is_empty() && !normalized[index].aliases.iter().any(|a| a == alias) {
+                normalized[index].aliases.push(alias.to_string());
+            }
+        }
+    }
+
+    Some(Json(normalized))
 }
 
 pub async fn create(pool: &PgPool, sub: &Subscription) -> Result<Subscription> {
+    let title_keywords = normalize_keywords(sub.title_keywords.as_deref());
+    let desc_keywords = normalize_keywords(sub.desc_keywords.as_deref());
+    let additional_info_keywords = normalize_keywords(sub.additional_info_keywords.as_deref());
+    let title_exclude_keywords = normalize_keywords(sub.title_exclude_keywords.as_deref());
+    let desc_exclude_keywords = normalize_keywords(sub.desc_exclude_keywords.as_deref());
+    let additional_info_exclude_keywords =
+        normalize_keywords(sub.additional_info_exclude_keywords.as_deref());
+    let counties = counties::canonicalize(sub.counties.as_deref());
+    let included_authorities =
+        normalize_authorities(sub.included_authorities.as_deref().map(Vec::as_slice));
+    let excluded_authorities =
+        normalize_authorities(sub.excluded_authorities.as_deref().map(Vec::as_slice));
+
     Ok(query_as!(
         Subscription,
         r#"
@@ -26,14 +167,46 @@ pub async fn create(pool: &PgPool, sub: &Subscription) -> Result<Subscription> {
                 max_price, 
                 title_keywords,
                 desc_keywords,
-                additional_info_keywords
-            FROM create_subscription($1,$2,$3,$4,$5,$6)"#,
+                additional_info_keywords,
+                title_match_mode as "title_match_mode!: MatchMode",
+                desc_match_mode as "desc_match_mode!: MatchMode",
+                additional_info_match_mode as "additional_info_match_mode!: MatchMode",
+                query,
+                cpv_codes,
+                counties,
+                included_authorities as "included_authorities: Json<Vec<AuthorityFilter>>",
+                excluded_authorities as "excluded_authorities: Json<Vec<AuthorityFilter>>",
+                title_exclude_keywords,
+                desc_exclude_keywords,
+                additional_info_exclude_keywords,
+                min_days_to_deadline,
+                procedure_types as "procedure_types: Vec<ProcedureType>",
+                min_score,
+                channels as "channels: Vec<Channel>",
+                paused_until
+            FROM create_subscription($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,$20,$21,$22)"#,
         sub.id_user,
         sub.min_price,
         sub.max_price,
-        sub.title_keywords.as_deref(),
-        sub.desc_keywords.as_deref(),
-        sub.additional_info_keywords.as_deref()
+        title_keywords.as_deref(),
+        desc_keywords.as_deref(),
+        additional_info_keywords.as_deref(),
+        sub.title_match_mode as MatchMode,
+        sub.desc_match_mode as MatchMode,
+        sub.additional_info_match_mode as MatchMode,
+        sub.query,
+        sub.cpv_codes.as_deref(),
+        counties.as_deref(),
+        included_authorities as _,
+        excluded_authorities as _,
+        title_exclude_keywords.as_deref(),
+        desc_exclude_keywords.as_deref(),
+        additional_info_exclude_keywords.as_deref(),
+        sub.min_days_to_deadline,
+        sub.procedure_types.as_deref() as Option<&[ProcedureType]>,
+        sub.min_score,
+        sub.channels.as_deref() as Option<&[Channel]>,
+        sub.paused_until
     )
     .fetch_one(pool)
     .await?)
@@ -51,7 +224,23 @@ pub async fn get_one(pool: &PgPool, id: usize) -> Result<Subscription> {
                 max_price, 
                 title_keywords,
                 desc_keywords,
-                additional_info_keywords
+                additional_info_keywords,
+                title_match_mode as "title_match_mode!: MatchMode",
+                desc_match_mode as "desc_match_mode!: MatchMode",
+                additional_info_match_mode as "additional_info_match_mode!: MatchMode",
+                query,
+                cpv_codes,
+                counties,
+                included_authorities as "included_authorities: Json<Vec<AuthorityFilter>>",
+                excluded_authorities as "excluded_authorities: Json<Vec<AuthorityFilter>>",
+                title_exclude_keywords,
+                desc_exclude_keywords,
+                additional_info_exclude_keywords,
+                min_days_to_deadline,
+                procedure_types as "procedure_types: Vec<ProcedureType>",
+                min_score,
+                channels as "channels: Vec<Channel>",
+                paused_until
             FROM get_subscriptions() WHERE id=$1"#,
         id
     )
@@ -69,7 +258,23 @@ pub async fn get_paginated(pool: &PgPool, pagination: &Pagination) -> Result<Vec
                 max_price, 
                 title_keywords,
                 desc_keywords,
-                additional_info_keywords
+                additional_info_keywords,
+                title_match_mode as "title_match_mode!: MatchMode",
+                desc_match_mode as "desc_match_mode!: MatchMode",
+                additional_info_match_mode as "additional_info_match_mode!: MatchMode",
+                query,
+                cpv_codes,
+                counties,
+                included_authorities as "included_authorities: Json<Vec<AuthorityFilter>>",
+                excluded_authorities as "excluded_authorities: Json<Vec<AuthorityFilter>>",
+                title_exclude_keywords,
+                desc_exclude_keywords,
+                additional_info_exclude_keywords,
+                min_days_to_deadline,
+                procedure_types as "procedure_types: Vec<ProcedureType>",
+                min_score,
+                channels as "channels: Vec<Channel>",
+                paused_until
             FROM get_subscriptions()
             LIMIT $1
             OFFSET $2"#,
@@ -80,6 +285,39 @@ pub async fn get_paginated(pool: &PgPool, pagination: &Pagination) -> Result<Vec
     .await?)
 }
 
+pub async fn get_all(pool: &PgPool) -> Result<Vec<Subscription>> {
+    Ok(query_as!(
+        Subscription,
+        r#"SELECT
+                id as "id!", 
+                id_user as "id_user!",
+                min_price, 
+                max_price, 
+                title_keywords,
+                desc_keywords,
+                additional_info_keywords,
+                title_match_mode as "title_match_mode!: MatchMode",
+                desc_match_mode as "desc_match_mode!: MatchMode",
+                additional_info_match_mode as "additional_info_match_mode!: MatchMode",
+                query,
+                cpv_codes,
+                counties,
+                included_authorities as "included_authorities: Json<Vec<AuthorityFilter>>",
+                excluded_authorities as "excluded_authorities: Json<Vec<AuthorityFilter>>",
+                title_exclude_keywords,
+                desc_exclude_keywords,
+                additional_info_exclude_keywords,
+                min_days_to_deadline,
+                procedure_types as "procedure_types: Vec<ProcedureType>",
+                min_score,
+                channels as "channels: Vec<Channel>",
+                paused_until
+            FROM get_subscriptions()"#
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
 pub async fn get_all_of_email(pool: &PgPool, email: Email) -> Result<Vec<Subscription>> {
     let email: String = email.try_into()?;
 
@@ -92,7 +330,23 @@ pub async fn get_all_of_email(pool: &PgPool, email: Email) -> Result<Vec<Subscri
                 max_price, 
                 title_keywords,
                 desc_keywords,
-                additional_info_keywords
+                additional_info_keywords,
+                title_match_mode as "title_match_mode!: MatchMode",
+                desc_match_mode as "desc_match_mode!: MatchMode",
+                additional_info_match_mode as "additional_info_match_mode!: MatchMode",
+                query,
+                cpv_codes,
+                counties,
+                included_authorities as "included_authorities: Json<Vec<AuthorityFilter>>",
+                excluded_authorities as "excluded_authorities: Json<Vec<AuthorityFilter>>",
+                title_exclude_keywords,
+                desc_exclude_keywords,
+                additional_info_exclude_keywords,
+                min_days_to_deadline,
+                procedure_types as "procedure_types: Vec<ProcedureType>",
+                min_score,
+                channels as "channels: Vec<Channel>",
+                paused_until
             FROM get_subscriptions()
             WHERE id_user IN 
                 (SELECT id FROM users WHERE email = $1)"#,
@@ -103,6 +357,19 @@ pub async fn get_all_of_email(pool: &PgPool, email: Email) -> Result<Vec<Subscri
 }
 
 pub async fn update(pool: &PgPool, sub: Subscription) -> Result<Subscription> {
+    let title_keywords = normalize_keywords(sub.title_keywords.as_deref());
+    let desc_keywords = normalize_keywords(sub.desc_keywords.as_deref());
+    let additional_info_keywords = normalize_keywords(sub.additional_info_keywords.as_deref());
+    let title_exclude_keywords = normalize_keywords(sub.title_exclude_keywords.as_deref());
+    let desc_exclude_keywords = normalize_keywords(sub.desc_exclude_keywords.as_deref());
+    let additional_info_exclude_keywords =
+        normalize_keywords(sub.additional_info_exclude_keywords.as_deref());
+    let counties = counties::canonicalize(sub.counties.as_deref());
+    let included_authorities =
+        normalize_authorities(sub.included_authorities.as_deref().map(Vec::as_slice));
+    let excluded_authorities =
+        normalize_authorities(sub.excluded_authorities.as_deref().map(Vec::as_slice));
+
     Ok(query_as!(
         Subscription,
         r#"SELECT
@@ -112,21 +379,73 @@ pub async fn update(pool: &PgPool, sub: Subscription) -> Result<Subscription> {
                 max_price, 
                 title_keywords,
                 desc_keywords,
-                additional_info_keywords
-            FROM update_subscription($1,$2,$3,$4,$5,$6,$7)
+                additional_info_keywords,
+                title_match_mode as "title_match_mode!: MatchMode",
+                desc_match_mode as "desc_match_mode!: MatchMode",
+                additional_info_match_mode as "additional_info_match_mode!: MatchMode",
+                query,
+                cpv_codes,
+                counties,
+                included_authorities as "included_authorities: Json<Vec<AuthorityFilter>>",
+                excluded_authorities as "excluded_authorities: Json<Vec<AuthorityFilter>>",
+                title_exclude_keywords,
+                desc_exclude_keywords,
+                additional_info_exclude_keywords,
+                min_days_to_deadline,
+                procedure_types as "procedure_types: Vec<ProcedureType>",
+                min_score,
+                channels as "channels: Vec<Channel>",
+                paused_until
+            FROM update_subscription($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,$20,$21,$22,$23)
             "#,
         sub.id,
         sub.id_user,
         sub.min_price,
         sub.max_price,
-        sub.title_keywords.as_deref(),
-        sub.desc_keywords.as_deref(),
-        sub.additional_info_keywords.as_deref()
+        title_keywords.as_deref(),
+        desc_keywords.as_deref(),
+        additional_info_keywords.as_deref(),
+        sub.title_match_mode as MatchMode,
+        sub.desc_match_mode as MatchMode,
+        sub.additional_info_match_mode as MatchMode,
+        sub.query,
+        sub.cpv_codes.as_deref(),
+        counties.as_deref(),
+        included_authorities as _,
+        excluded_authorities as _,
+        title_exclude_keywords.as_deref(),
+        desc_exclude_keywords.as_deref(),
+        additional_info_exclude_keywords.as_deref(),
+        sub.min_days_to_deadline,
+        sub.procedure_types.as_deref() as Option<&[ProcedureType]>,
+        sub.min_score,
+        sub.channels.as_deref() as Option<&[Channel]>,
+        sub.paused_until
     )
     .fetch_one(pool)
     .await?)
 }
 
+/// Pauses the subscription until the given time, or resumes it with `None`, keeping the rest of
+/// its configuration.
+pub async fn set_paused_until(
+    pool: &PgPool,
+    id: usize,
+    until: Option<OffsetDateTime>,
+) -> Result<Subscription> {
+    let id_i32: i32 = id.try_into()?;
+
+    query!(
+        "UPDATE subscriptions SET paused_until = $2 WHERE id = $1",
+        id_i32,
+        until
+    )
+    .execute(pool)
+    .await?;
+
+    get_one(pool, id).await
+}
+
 pub async fn delete(pool: &PgPool, id: usize) -> Result<Subscription> {
     let id: i32 = id.try_into()?;
 
@@ -139,7 +458,23 @@ pub async fn delete(pool: &PgPool, id: usize) -> Result<Subscription> {
                 max_price, 
                 title_keywords,
                 desc_keywords,
-                additional_info_keywords 
+                additional_info_keywords,
+                title_match_mode as "title_match_mode!: MatchMode",
+                desc_match_mode as "desc_match_mode!: MatchMode",
+                additional_info_match_mode as "additional_info_match_mode!: MatchMode",
+                query,
+                cpv_codes,
+                counties,
+                included_authorities as "included_authorities: Json<Vec<AuthorityFilter>>",
+                excluded_authorities as "excluded_authorities: Json<Vec<AuthorityFilter>>",
+                title_exclude_keywords,
+                desc_exclude_keywords,
+                additional_info_exclude_keywords,
+                min_days_to_deadline,
+                procedure_types as "procedure_types: Vec<ProcedureType>",
+                min_score,
+                channels as "channels: Vec<Channel>",
+                paused_until
             FROM delete_subscription($1)"#,
         id
     )
@@ -150,14 +485,21 @@ pub async fn delete(pool: &PgPool, id: usize) -> Result<Subscription> {
 #[cfg(test)]
 mod test {
     use crate::{
+        matching::MatchMode,
         models::{
-            subscription::{create, delete, get_all_of_email, get_one, get_paginated, update},
-            Subscription,
+            preferences::Channel,
+            subscription::{
+                create, delete, get_all_of_email, get_one, get_paginated, set_paused_until, update,
+                validate,
+            },
+            AuthorityFilter, Subscription,
         },
+        procedure::ProcedureType,
         utils::{Email, Pagination},
     };
     use anyhow::Result;
-    use sqlx::PgPool;
+    use sqlx::{types::Json, PgPool};
+    use time::macros::datetime;
 
     #[ignore]
     #[sqlx::test(fixtures("users"))]
@@ -171,6 +513,7 @@ mod test {
                 title_keywords: Some(vec!["kw1".into(), "kw2".into(), "kw3".into()]),
                 desc_keywords: None,
                 additional_info_keywords: None,
+                ..Default::default()
             };
 
             let res = create(&pool, &s).await?;
@@ -187,6 +530,7 @@ mod test {
                 title_keywords: None,
                 desc_keywords: Some(vec![]),
                 additional_info_keywords: None,
+                ..Default::default()
             };
 
             let res = create(&pool, &s).await?;
@@ -194,6 +538,103 @@ mod test {
             assert_eq!(res.desc_keywords, None);
         }
 
+        {
+            let s = Subscription {
+                id: 3,
+                id_user: 1,
+                min_price: None,
+                max_price: None,
+                title_keywords: Some(vec![
+                    "Curățenie".into(),
+                    "curăţenie".into(),
+                    " Dezinsecție  spații ".into(),
+                ]),
+                desc_keywords: None,
+                additional_info_keywords: None,
+                ..Default::default()
+            };
+
+            let res = create(&pool, &s).await?;
+
+            assert_eq!(
+                res.title_keywords,
+                Some(vec!["curatenie".into(), "dezinsectie spatii".into()])
+            );
+        }
+
+        {
+            let s = Subscription {
+                id: 4,
+                id_user: 1,
+                cpv_codes: Some(vec!["30200000-1".into(), "45000000-7".into()]),
+                ..Default::default()
+            };
+
+            let res = create(&pool, &s).await?;
+
+            assert_eq!(res, s);
+        }
+
+        {
+            let s = Subscription {
+                id: 5,
+                id_user: 1,
+                counties: Some(vec!["CJ".into(), "bucurești".into(), "Cluj".into()]),
+                ..Default::default()
+            };
+
+            let res = create(&pool, &s).await?;
+
+            assert_eq!(res.counties, Some(vec!["CJ".into(), "B".into()]));
+        }
+
+        {
+            let s = Subscription {
+                id: 6,
+                id_user: 1,
+                included_authorities: Some(Json(vec![
+                    AuthorityFilter {
+                        cui: "RO16054368".into(),
+                        aliases: vec!["CNAIR".into(), " CNAIR ".into()],
+                    },
+                    AuthorityFilter {
+                        cui: "4305857".into(),
+                        aliases: vec![],
+                    },
+                ])),
+                excluded_authorities: Some(Json(vec![AuthorityFilter {
+                    cui: "4540712".into(),
+                    aliases: vec!["Primaria Municipiului Iasi".into()],
+                }])),
+                ..Default::default()
+            };
+
+            let res = create(&pool, &s).await?;
+
+            assert_eq!(
+                res.included_authorities,
+                Some(Json(vec![
+                    AuthorityFilter {
+                        cui: "16054368".into(),
+                        aliases: vec!["CNAIR".into()],
+                    },
+                    AuthorityFilter {
+                        cui: "4305857".into(),
+                        aliases: vec![],
+                    },
+                ]))
+            );
+            assert_eq!(res.excluded_authorities, s.excluded_authorities);
+
+            let res = get_one(&pool, 6).await?;
+
+            assert_eq!(res.excluded_authorities, s.excluded_authorities);
+
+            let res = delete(&pool, 6).await?;
+
+            assert_eq!(res.excluded_authorities, s.excluded_authorities);
+        }
+
         Ok(())
     }
 
@@ -209,6 +650,7 @@ mod test {
                 title_keywords: None,
                 desc_keywords: None,
                 additional_info_keywords: None,
+                ..Default::default()
             };
 
             let res = get_one(&pool, 1).await?;
@@ -225,6 +667,7 @@ mod test {
                 title_keywords: None,
                 desc_keywords: None,
                 additional_info_keywords: None,
+                ..Default::default()
             };
 
             let res = get_one(&pool, 2).await?;
@@ -253,6 +696,7 @@ mod test {
                 title_keywords: None,
                 desc_keywords: None,
                 additional_info_keywords: None,
+                ..Default::default()
             };
 
             let res = get_paginated(
@@ -326,6 +770,7 @@ mod test {
                 title_keywords: Some(vec!["kw1".into(), "kw2".into(), "kw3".into()]),
                 desc_keywords: None,
                 additional_info_keywords: None,
+                ..Default::default()
             };
 
             let res = update(&pool, sub.clone()).await?;
@@ -342,6 +787,7 @@ mod test {
                 title_keywords: None,
                 desc_keywords: Some(vec![]),
                 additional_info_keywords: None,
+                ..Default::default()
             };
 
             let res = update(&pool, sub.clone()).await?;
@@ -349,6 +795,118 @@ mod test {
             assert_eq!(res.desc_keywords, None);
         }
 
+        {
+            let sub = Subscription {
+                id: 3,
+                id_user: 2,
+                title_keywords: Some(vec!["calculator".into()]),
+                title_match_mode: MatchMode::Stemmed,
+                desc_match_mode: MatchMode::WholeWord,
+                query: Some("laptop AND (dell OR lenovo) NOT refurbished".into()),
+                ..Default::default()
+            };
+
+            let res = update(&pool, sub.clone()).await?;
+
+            assert_eq!(res, sub);
+        }
+
+        {
+            let sub = Subscription {
+                id: 1,
+                id_user: 1,
+                title_keywords: Some(vec!["mobilier".into()]),
+                title_exclude_keywords: Some(vec!["Mobilier urban".into()]),
+                desc_exclude_keywords: Some(vec!["second hand".into(), "SECOND HAND".into()]),
+                additional_info_exclude_keywords: Some(vec![]),
+                ..Default::default()
+            };
+
+            let res = update(&pool, sub.clone()).await?;
+
+            assert_eq!(
+                res.title_exclude_keywords,
+                Some(vec!["mobilier urban".into()])
+            );
+            assert_eq!(res.desc_exclude_keywords, Some(vec!["second hand".into()]));
+            assert_eq!(res.additional_info_exclude_keywords, None);
+        }
+
+        {
+            let sub = Subscription {
+                id: 2,
+                id_user: 1,
+                min_days_to_deadline: Some(7),
+                procedure_types: Some(vec![
+                    ProcedureType::OpenTender,
+                    ProcedureType::SimplifiedProcedure,
+                ]),
+                ..Default::default()
+            };
+
+            let res = update(&pool, sub.clone()).await?;
+
+            assert_eq!(res, sub);
+
+            let res = delete(&pool, 2).await?;
+
+            assert_eq!(res, sub);
+        }
+
+        {
+            let sub = Subscription {
+                id: 3,
+                id_user: 2,
+                title_keywords: Some(vec!["calculator".into()]),
+                channels: Some(vec![Channel::Webhook]),
+                paused_until: Some(datetime!(2023-03-01 00:00 UTC)),
+                ..Default::default()
+            };
+
+            let res = update(&pool, sub.clone()).await?;
+
+            assert_eq!(res, sub);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions"))]
+    async fn test_set_paused_until(pool: PgPool) -> Result<()> {
+        let sub = Subscription {
+            id: 1,
+            id_user: 1,
+            title_keywords: Some(vec!["mobilier".into()]),
+            channels: Some(vec![Channel::Email]),
+            ..Default::default()
+        };
+
+        update(&pool, sub.clone()).await?;
+
+        {
+            let until = datetime!(2023-03-01 00:00 UTC);
+            let res = set_paused_until(&pool, 1, Some(until)).await?;
+
+            assert_eq!(
+                res,
+                Subscription {
+                    paused_until: Some(until),
+                    ..sub.clone()
+                }
+            );
+            assert!(res.is_paused(datetime!(2023-02-28 23:59 UTC)));
+            assert!(!res.is_paused(until));
+        }
+
+        {
+            let res = set_paused_until(&pool, 1, None).await?;
+
+            assert_eq!(res, sub);
+        }
+
+        assert!(set_paused_until(&pool, 10, None).await.is_err());
+
         Ok(())
     }
 
@@ -364,6 +922,7 @@ mod test {
                 title_keywords: None,
                 desc_keywords: None,
                 additional_info_keywords: None,
+                ..Default::default()
             };
 
             let res = delete(&pool, 1).await?;
@@ -379,4 +938,106 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_validate() {
+        {
+            let sub = Subscription {
+                query: Some("title:laptop NOT \"second hand\"".into()),
+                ..Default::default()
+            };
+
+            assert!(validate(&sub).is_ok());
+        }
+
+        {
+            let sub = Subscription {
+                query: Some("laptop AND (dell".into()),
+                ..Default::default()
+            };
+
+            let err = validate(&sub).unwrap_err();
+
+            assert_eq!(err.to_string(), "unclosed `(` at position 11");
+        }
+
+        {
+            let sub = Subscription {
+                cpv_codes: Some(vec!["30200000-1".into(), "30200000-2".into()]),
+                ..Default::default()
+            };
+
+            let err = validate(&sub).unwrap_err();
+
+            assert_eq!(err.to_string(), "Invalid CPV code: 30200000-2.");
+        }
+
+        {
+            let sub = Subscription {
+                counties: Some(vec!["CJ".into(), "ZZ".into()]),
+                ..Default::default()
+            };
+
+            let err = validate(&sub).unwrap_err();
+
+            assert_eq!(err.to_string(), "Unknown county: ZZ.");
+        }
+
+        {
+            let sub = Subscription {
+                min_days_to_deadline: Some(-1),
+                ..Default::default()
+            };
+
+            let err = validate(&sub).unwrap_err();
+
+            assert_eq!(err.to_string(), "min_days_to_deadline can't be negative.");
+        }
+
+        for min_score in [-1, 101] {
+            let sub = Subscription {
+                min_score: Some(min_score),
+                ..Default::default()
+            };
+
+            let err = validate(&sub).unwrap_err();
+
+            assert_eq!(err.to_string(), "min_score must be between 0 and 100.");
+        }
+
+        {
+            let sub = Subscription {
+                included_authorities: Some(Json(vec![AuthorityFilter {
+                    cui: "16054367".into(),
+                    aliases: vec![],
+                }])),
+                ..Default::default()
+            };
+
+            let err = validate(&sub).unwrap_err();
+
+            assert_eq!(err.to_string(), "Invalid CUI: 16054367.");
+        }
+
+        {
+            let sub = Subscription {
+                included_authorities: Some(Json(vec![AuthorityFilter {
+                    cui: "16054368".into(),
+                    aliases: vec![],
+                }])),
+                excluded_authorities: Some(Json(vec![AuthorityFilter {
+                    cui: "RO16054368".into(),
+                    aliases: vec![],
+                }])),
+                ..Default::default()
+            };
+
+            let err = validate(&sub).unwrap_err();
+
+            assert_eq!(
+                err.to_string(),
+                "Authority 16054368 is both included and excluded."
+            );
+        }
+    }
 }
@@ -0,0 +1,642 @@
+use crate::{counties, cui, utils::Pagination};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, PgExecutor, PgPool, Postgres, Transaction};
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Tender {
+    pub id: i32,
+    pub notice_number: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub additional_info: Option<String>,
+    pub estimated_value: Option<i64>,
+    pub currency: String,
+    pub contracting_authority: Option<String>,
+    pub cpv_code: Option<String>,
+    pub publication_date: OffsetDateTime,
+    pub submission_deadline: Option<OffsetDateTime>,
+    pub procedure_type: Option<String>,
+    pub county: Option<String>,
+    pub contracting_authority_cui: Option<String>,
+    pub clarification_deadline: Option<OffsetDateTime>,
+    pub opening_date: Option<OffsetDateTime>,
+}
+
+pub async fn create(executor: impl PgExecutor<'_>, tender: &Tender) -> Result<Tender> {
+    let county = canonical_county(tender.county.as_deref());
+    let contracting_authority_cui = canonical_cui(tender.contracting_authority_cui.as_deref());
+
+    Ok(query_as!(
+        Tender,
+        r#"
+            SELECT
+                id as "id!",
+                notice_number as "notice_number!",
+                title as "title!",
+                description,
+                additional_info,
+                estimated_value,
+                currency as "currency!",
+                contracting_authority,
+                cpv_code,
+                publication_date as "publication_date!",
+                submission_deadline,
+                procedure_type,
+                county,
+                contracting_authority_cui,
+                clarification_deadline,
+                opening_date
+            FROM create_tender($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15)"#,
+        tender.notice_number,
+        tender.title,
+        tender.description,
+        tender.additional_info,
+        tender.estimated_value,
+        tender.currency,
+        tender.contracting_authority,
+        tender.cpv_code,
+        tender.publication_date,
+        tender.submission_deadline,
+        tender.procedure_type,
+        county,
+        contracting_authority_cui,
+        tender.clarification_deadline,
+        tender.opening_date
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+/// Inserts the tenders whose notice number isn't stored yet with a single statement, returning
+/// how many were inserted.
+pub async fn create_many(tx: &mut Transaction<'_, Postgres>, tenders: &[Tender]) -> Result<u64> {
+    let column = |field: fn(&Tender) -> Option<String>| tenders.iter().map(field).collect();
+
+    let notice_numbers: Vec<String> = tenders.iter().map(|t| t.notice_number.clone()).collect();
+    let titles: Vec<String> = tenders.iter().map(|t| t.title.clone()).collect();
+    let descriptions: Vec<Option<String>> = column(|t| t.description.clone());
+    let additional_infos: Vec<Option<String>> = column(|t| t.additional_info.clone());
+    let estimated_values: Vec<Option<i64>> = tenders.iter().map(|t| t.estimated_value).collect();
+    let currencies: Vec<String> = tenders.iter().map(|t| t.currency.clone()).collect();
+    let contracting_authorities: Vec<Option<String>> = column(|t| t.contracting_authority.clone());
+    let cpv_codes: Vec<Option<String>> = column(|t| t.cpv_code.clone());
+    let publication_dates: Vec<OffsetDateTime> =
+        tenders.iter().map(|t| t.publication_date).collect();
+    let submission_deadlines: Vec<Option<OffsetDateTime>> =
+        tenders.iter().map(|t| t.submission_deadline).collect();
+    let procedure_types: Vec<Option<String>> = column(|t| t.procedure_type.clone());
+    let counties: Vec<Option<String>> =
+        column(|t| canonical_county(t.county.as_deref()).map(str::to_string));
+    let contracting_authority_cuis: Vec<Option<String>> =
+        column(|t| canonical_cui(t.contracting_authority_cui.as_deref()));
+    let clarification_deadlines: Vec<Option<OffsetDateTime>> =
+        tenders.iter().map(|t| t.clarification_deadline).collect();
+    let opening_dates: Vec<Option<OffsetDateTime>> =
+        tenders.iter().map(|t| t.opening_date).collect();
+
+    let res = query!(
+        r#"
+            INSERT INTO tenders (
+                notice_number,
+                title,
+                description,
+                additional_info,
+                estimated_value,
+                currency,
+                contracting_authority,
+                cpv_code,
+                publication_date,
+                submission_deadline,
+                procedure_type,
+                county,
+                contracting_authority_cui,
+                clarification_deadline,
+                opening_date
+            )
+            SELECT * FROM UNNEST(
+                $1::VARCHAR(50)[],
+                $2::TEXT[],
+                $3::TEXT[],
+                $4::TEXT[],
+                $5::BIGINT[],
+                $6::VARCHAR(3)[],
+                $7::VARCHAR(255)[],
+                $8::VARCHAR(10)[],
+                $9::TIMESTAMPTZ[],
+                $10::TIMESTAMPTZ[],
+                $11::VARCHAR(100)[],
+                $12::VARCHAR(2)[],
+                $13::VARCHAR(12)[],
+                $14::TIMESTAMPTZ[],
+                $15::TIMESTAMPTZ[]
+            )
+            ON CONFLICT (notice_number) DO NOTHING"#,
+        &notice_numbers,
+        &titles,
+        &descriptions as &[Option<String>],
+        &additional_infos as &[Option<String>],
+        &estimated_values as &[Option<i64>],
+        &currencies,
+        &contracting_authorities as &[Option<String>],
+        &cpv_codes as &[Option<String>],
+        &publication_dates,
+        &submission_deadlines as &[Option<OffsetDateTime>],
+        &procedure_types as &[Option<String>],
+        &counties as &[Option<String>],
+        &contracting_authority_cuis as &[Option<String>],
+        &clarification_deadlines as &[Option<OffsetDateTime>],
+        &opening_dates as &[Option<OffsetDateTime>]
+    )
+    .execute(tx)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+pub async fn get_one(pool: &PgPool, id: usize) -> Result<Tender> {
+    let id: i32 = id.try_into()?;
+
+    Ok(query_as!(Tender, "SELECT * FROM tenders WHERE id = $1", id)
+        .fetch_one(pool)
+        .await?)
+}
+
+pub async fn get_by_notice_number(
+    executor: impl PgExecutor<'_>,
+    notice_number: &str,
+) -> Result<Tender> {
+    Ok(query_as!(
+        Tender,
+        "SELECT * FROM tenders WHERE notice_number = $1",
+        notice_number
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn get_paginated(pool: &PgPool, pagination: &Pagination) -> Result<Vec<Tender>> {
+    Ok(query_as!(
+        Tender,
+        r#"SELECT * FROM tenders
+            ORDER BY publication_date DESC, id DESC
+            LIMIT $1
+            OFFSET $2"#,
+        i64::from(pagination.count),
+        i64::from(pagination.start_index)
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Tenders published at or after `since`, newest first.
+pub async fn get_published_since(pool: &PgPool, since: OffsetDateTime) -> Result<Vec<Tender>> {
+    Ok(query_as!(
+        Tender,
+        r#"SELECT * FROM tenders
+            WHERE publication_date >= $1
+            ORDER BY publication_date DESC, id DESC"#,
+        since
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Tenders the user was matched to or bookmarked with a deadline or opening date at or after
+/// `since`, soonest first.
+pub async fn get_followed_by_user(
+    pool: &PgPool,
+    id_user: usize,
+    since: OffsetDateTime,
+) -> Result<Vec<Tender>> {
+    let id_user: i32 = id_user.try_into()?;
+
+    Ok(query_as!(
+        Tender,
+        r#"SELECT t.* FROM tenders t
+            WHERE (
+                t.id IN (
+                    SELECT m.id_tender FROM matches m
+                    JOIN subscriptions s ON s.id = m.id_subscription
+                    WHERE s.id_user = $1
+                )
+                OR t.id IN (SELECT b.id_tender FROM bookmarks b WHERE b.id_user = $1)
+            )
+            AND GREATEST(t.submission_deadline, t.clarification_deadline, t.opening_date) >= $2
+            ORDER BY LEAST(t.submission_deadline, t.clarification_deadline, t.opening_date), t.id"#,
+        id_user,
+        since
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn update(executor: impl PgExecutor<'_>, tender: Tender) -> Result<Tender> {
+    let county = canonical_county(tender.county.as_deref());
+    let contracting_authority_cui = canonical_cui(tender.contracting_authority_cui.as_deref());
+
+    Ok(query_as!(
+        Tender,
+        r#"
+            SELECT
+                id as "id!",
+                notice_number as "notice_number!",
+                title as "title!",
+                description,
+                additional_info,
+                estimated_value,
+                currency as "currency!",
+                contracting_authority,
+                cpv_code,
+                publication_date as "publication_date!",
+                submission_deadline,
+                procedure_type,
+                county,
+                contracting_authority_cui,
+                clarification_deadline,
+                opening_date
+            FROM update_tender($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16)"#,
+        tender.id,
+        tender.notice_number,
+        tender.title,
+        tender.description,
+        tender.additional_info,
+        tender.estimated_value,
+        tender.currency,
+        tender.contracting_authority,
+        tender.cpv_code,
+        tender.publication_date,
+        tender.submission_deadline,
+        tender.procedure_type,
+        county,
+        contracting_authority_cui,
+        tender.clarification_deadline,
+        tender.opening_date
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn delete(pool: &PgPool, id: usize) -> Result<Tender> {
+    let id: i32 = id.try_into()?;
+
+    Ok(query_as!(
+        Tender,
+        r#"
+            SELECT
+                id as "id!",
+                notice_number as "notice_number!",
+                title as "title!",
+                description,
+                additional_info,
+                estimated_value,
+                currency as "currency!",
+                contracting_authority,
+                cpv_code,
+                publication_date as "publication_date!",
+                submission_deadline,
+                procedure_type,
+                county,
+                contracting_authority_cui,
+                clarification_deadline,
+                opening_date
+            FROM delete_tender($1)"#,
+        id
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Tenders only keep the code of a known county; anything else is dropped.
+fn canonical_county(county: Option<&str>) -> Option<&'static str> {
+    county.and_then(counties::find).map(|county| county.code)
+}
+
+/// Tenders only keep a CUI that passes its checksum, without the `RO` prefix.
+fn canonical_cui(cui: Option<&str>) -> Option<String> {
+    cui.filter(|cui| cui::is_valid(cui)).map(cui::normalize)
+}
+
+/// A tender for tests to build on, overriding the fields they care about.
+#[cfg(test)]
+pub(crate) fn sample() -> Tender {
+    Tender {
+        id: 4,
+        notice_number: "CN1052000".into(),
+        title: "Furnizare calculatoare portabile".into(),
+        description: Some("Laptopuri pentru unitatile scolare".into()),
+        additional_info: None,
+        estimated_value: Some(250_000),
+        currency: "RON".into(),
+        contracting_authority: Some("Primaria Municipiului Cluj-Napoca".into()),
+        cpv_code: Some("30213100-6".into()),
+        publication_date: time::macros::datetime!(2023-02-10 10:00 UTC),
+        submission_deadline: Some(time::macros::datetime!(2023-03-01 15:00 UTC)),
+        procedure_type: Some("Licitatie deschisa".into()),
+        county: Some("CJ".into()),
+        contracting_authority_cui: Some("4305857".into()),
+        clarification_deadline: None,
+        opening_date: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::{
+            tender::{
+                create, create_many, delete, get_by_notice_number, get_followed_by_user, get_one,
+                get_paginated, get_published_since, sample, update,
+            },
+            Tender,
+        },
+        utils::Pagination,
+    };
+    use anyhow::Result;
+    use sqlx::{query, PgPool};
+    use time::macros::datetime;
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_create(pool: PgPool) -> Result<()> {
+        {
+            let t = sample();
+
+            let res = create(&pool, &t).await?;
+
+            assert_eq!(res, t);
+        }
+
+        {
+            let res = create(&pool, &sample()).await;
+
+            assert!(res.is_err());
+        }
+
+        {
+            let t = Tender {
+                notice_number: "CN1052001".into(),
+                county: Some("Iași".into()),
+                ..sample()
+            };
+
+            let res = create(&pool, &t).await?;
+
+            assert_eq!(res.county, Some("IS".into()));
+        }
+
+        {
+            let t = Tender {
+                notice_number: "CN1052002".into(),
+                county: Some("Ardeal".into()),
+                ..sample()
+            };
+
+            let res = create(&pool, &t).await?;
+
+            assert_eq!(res.county, None);
+        }
+
+        {
+            let t = Tender {
+                notice_number: "CN1052003".into(),
+                contracting_authority_cui: Some("RO 4305857".into()),
+                ..sample()
+            };
+
+            let res = create(&pool, &t).await?;
+
+            assert_eq!(res.contracting_authority_cui, Some("4305857".into()));
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_create_many(pool: PgPool) -> Result<()> {
+        let tenders = vec![
+            sample(),
+            Tender {
+                notice_number: "CN1051234".into(),
+                title: "Already stored".into(),
+                ..sample()
+            },
+            Tender {
+                notice_number: "CN1052001".into(),
+                county: Some("Iași".into()),
+                contracting_authority_cui: Some("RO 4305857".into()),
+                ..sample()
+            },
+        ];
+
+        {
+            let mut tx = pool.begin().await?;
+
+            let created = create_many(&mut tx, &tenders).await?;
+
+            tx.commit().await?;
+
+            assert_eq!(created, 2);
+        }
+
+        {
+            let res = get_by_notice_number(&pool, "CN1051234").await?;
+
+            assert_eq!(res.title, "Modernizare drum judetean DJ 107");
+        }
+
+        {
+            let res = get_by_notice_number(&pool, "CN1052001").await?;
+
+            assert_eq!(res.county, Some("IS".into()));
+            assert_eq!(res.contracting_authority_cui, Some("4305857".into()));
+        }
+
+        {
+            let mut tx = pool.begin().await?;
+
+            let created = create_many(&mut tx, &tenders).await?;
+
+            assert_eq!(created, 0);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_get_one(pool: PgPool) -> Result<()> {
+        {
+            let res = get_one(&pool, 1).await?;
+
+            assert_eq!(res.notice_number, "CN1051234");
+            assert_eq!(res.estimated_value, Some(1_500_000));
+        }
+
+        {
+            let res = get_one(&pool, 10).await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_get_by_notice_number(pool: PgPool) -> Result<()> {
+        {
+            let res = get_by_notice_number(&pool, "SCN1090001").await?;
+
+            assert_eq!(res.id, 2);
+        }
+
+        {
+            let res = get_by_notice_number(&pool, "CN0000000").await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_get_paginated(pool: PgPool) -> Result<()> {
+        {
+            let res = get_paginated(
+                &pool,
+                &Pagination {
+                    start_index: 0,
+                    count: 5,
+                },
+            )
+            .await?;
+
+            assert_eq!(res.len(), 3);
+            assert_eq!(res[0].id, 3);
+        }
+
+        {
+            let res = get_paginated(
+                &pool,
+                &Pagination {
+                    start_index: 0,
+                    count: 0,
+                },
+            )
+            .await?;
+
+            assert_eq!(res.len(), 0);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_get_published_since(pool: PgPool) -> Result<()> {
+        {
+            let res = get_published_since(&pool, datetime!(2023-02-03 09:00 UTC)).await?;
+
+            let ids: Vec<i32> = res.iter().map(|t| t.id).collect();
+
+            assert_eq!(ids, vec![3, 2]);
+        }
+
+        {
+            let res = get_published_since(&pool, datetime!(2023-03-01 00:00 UTC)).await?;
+
+            assert!(res.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users", "subscriptions", "tenders", "matches"))]
+    async fn test_get_followed_by_user(pool: PgPool) -> Result<()> {
+        {
+            let res = get_followed_by_user(&pool, 1, datetime!(2023-01-01 00:00 UTC)).await?;
+
+            let ids: Vec<i32> = res.iter().map(|t| t.id).collect();
+
+            assert_eq!(ids, vec![1, 2]);
+            assert_eq!(
+                res[0].clarification_deadline,
+                Some(datetime!(2023-02-20 12:00 UTC))
+            );
+            assert_eq!(res[0].opening_date, Some(datetime!(2023-03-01 13:00 UTC)));
+        }
+
+        {
+            let res = get_followed_by_user(&pool, 1, datetime!(2023-02-25 00:00 UTC)).await?;
+
+            let ids: Vec<i32> = res.iter().map(|t| t.id).collect();
+
+            assert_eq!(ids, vec![1]);
+        }
+
+        {
+            let res = get_followed_by_user(&pool, 2, datetime!(2023-01-01 00:00 UTC)).await?;
+
+            let ids: Vec<i32> = res.iter().map(|t| t.id).collect();
+
+            assert_eq!(ids, vec![1]);
+
+            query!("INSERT INTO bookmarks (id_user, id_tender) VALUES (2, 1), (2, 2)")
+                .execute(&pool)
+                .await?;
+
+            let res = get_followed_by_user(&pool, 2, datetime!(2023-01-01 00:00 UTC)).await?;
+
+            let ids: Vec<i32> = res.iter().map(|t| t.id).collect();
+
+            assert_eq!(ids, vec![1, 2]);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_update(pool: PgPool) -> Result<()> {
+        {
+            let t = Tender { id: 1, ..sample() };
+
+            let res = update(&pool, t.clone()).await?;
+
+            assert_eq!(res, t);
+        }
+
+        {
+            let t = Tender { id: 10, ..sample() };
+
+            let res = update(&pool, t).await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("tenders"))]
+    async fn test_delete(pool: PgPool) -> Result<()> {
+        {
+            let res = delete(&pool, 1).await?;
+
+            assert_eq!(res.notice_number, "CN1051234");
+        }
+
+        {
+            let res = delete(&pool, 1).await;
+
+            assert!(res.is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use crate::utils::{Email, Pagination};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sqlx::{query_as, PgPool};
-use time::{OffsetDateTime, PrimitiveDateTime};
+use time::OffsetDateTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct User {
@@ -139,9 +139,7 @@ mod test {
     use sqlx::PgPool;
     use time::OffsetDateTime;
 
-    use crate::models::user::{
-        create, delete, delete_by_email, get_by_email, get_one, get_paginated, update,
-    };
+    use crate::models::user::{create, delete, get_by_email, get_one, get_paginated, update};
     use crate::models::User;
     use crate::utils::{Email, Pagination};
 
@@ -0,0 +1,500 @@
+use anyhow::{Error, Result};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    postgres::{PgHasArrayType, PgTypeInfo},
+    query_as, PgPool,
+};
+use std::net::IpAddr;
+use time::OffsetDateTime;
+use tokio::net::lookup_host;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "webhook_event", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A tender matched one of the user's subscriptions.
+    MatchCreated,
+    /// A matched tender was materially revised.
+    MatchUpdated,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::MatchCreated => "match_created",
+            WebhookEvent::MatchUpdated => "match_updated",
+        }
+    }
+}
+
+impl PgHasArrayType for WebhookEvent {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_webhook_event")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Webhook {
+    pub id: i32,
+    pub id_user: i32,
+    pub url: String,
+    /// Key of the HMAC-SHA256 signature of every payload, only shown when the webhook is
+    /// created.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    /// Cleared after too many consecutive failed deliveries.
+    pub enabled: bool,
+    pub consecutive_failures: i32,
+    pub created_at: OffsetDateTime,
+}
+
+/// What users send to create or update a webhook. A secret is generated when none is given.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WebhookParams {
+    pub url: String,
+    pub secret: Option<String>,
+    pub events: Vec<WebhookEvent>,
+}
+
+pub const MIN_SECRET_LEN: usize = 16;
+
+pub fn validate(params: &WebhookParams) -> Result<()> {
+    let url = Url::parse(&params.url).map_err(|_| Error::msg("Invalid webhook URL."))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::msg("Webhook URLs must use http or https."));
+    }
+
+    if params.events.is_empty() {
+        return Err(Error::msg("Expected at least one webhook event."));
+    }
+
+    if params
+        .secret
+        .as_ref()
+        .is_some_and(|secret| secret.len() < MIN_SECRET_LEN)
+    {
+        return Err(Error::msg(format!(
+            "Webhook secrets must have at least {MIN_SECRET_LEN} characters."
+        )));
+    }
+
+    Ok(())
+}
+
+/// Whether deliveries may be sent to the address. Loopback, private, link-local and other
+/// special-purpose addresses are refused so webhooks can't reach the service's own network.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // "this network", shared address space, IETF protocol assignments,
+                // benchmarking and reserved
+                || a == 0
+                || (a == 100 && b & 0xc0 == 64)
+                || (a == 192 && b == 0 && c == 0)
+                || (a == 198 && b & 0xfe == 18)
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    // documentation
+                    || ip.segments()[..2] == [0x2001, 0xdb8])
+            }
+        },
+    }
+}
+
+/// Resolves the host of the webhook's URL and checks every address it resolves to is public.
+pub async fn validate_host(params: &WebhookParams) -> Result<()> {
+    let url = Url::parse(&params.url).map_err(|_| Error::msg("Invalid webhook URL."))?;
+
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err(Error::msg("Invalid webhook URL."));
+    };
+
+    // IPv6 hosts are bracketed in URLs
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let addrs: Vec<_> = lookup_host((host, port))
+        .await
+        .map_err(|_| Error::msg(format!("Couldn't resolve the webhook host {host}.")))?
+        .collect();
+
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public(addr.ip())) {
+        return Err(Error::msg("Webhook URLs must point to a public address."));
+    }
+
+    Ok(())
+}
+
+pub async fn create(pool: &PgPool, id_user: usize, params: &WebhookParams) -> Result<Webhook> {
+    let id_user: i32 = id_user.try_into()?;
+
+    let secret = params.secret.clone().unwrap_or_else(|| {
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect()
+    });
+
+    Ok(query_as!(
+        Webhook,
+        r#"
+            SELECT
+                id as "id!",
+                id_user as "id_user!",
+                url as "url!",
+                secret as "secret!",
+                events as "events!: Vec<WebhookEvent>",
+                enabled as "enabled!",
+                consecutive_failures as "consecutive_failures!",
+                created_at as "created_at!"
+            FROM create_webhook($1, $2, $3, $4)"#,
+        id_user,
+        params.url,
+        secret,
+        &params.events as &[WebhookEvent]
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+pub async fn get_one(pool: &PgPool, id: usize) -> Result<Webhook> {
+    let id: i32 = id.try_into()?;
+
+    Ok(query_as!(
+        Webhook,
+        r#"SELECT
+                id,
+                id_user,
+                url,
+                secret,
+                events as "events: Vec<WebhookEvent>",
+                enabled,
+                consecutive_failures,
+                created_at
+            FROM webhooks WHERE id = $1"#,
+        id
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+pub async fn get_all_of_user(pool: &PgPool, id_user: usize) -> Result<Vec<Webhook>> {
+    let id_user: i32 = id_user.try_into()?;
+
+    Ok(query_as!(
+        Webhook,
+        r#"SELECT
+                id,
+                id_user,
+                url,
+                secret,
+                events as "events: Vec<WebhookEvent>",
+                enabled,
+                consecutive_failures,
+                created_at
+            FROM webhooks WHERE id_user = $1 ORDER BY id"#,
+        id_user
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Replaces the webhook's settings and enables it again.
+pub async fn update(pool: &PgPool, id: usize, params: &WebhookParams) -> Result<Webhook> {
+    let id: i32 = id.try_into()?;
+
+    Ok(query_as!(
+        Webhook,
+        r#"
+            SELECT
+                id as "id!",
+                id_user as "id_user!",
+                url as "url!",
+                secret as "secret!",
+                events as "events!: Vec<WebhookEvent>",
+                enabled as "enabled!",
+                consecutive_failures as "consecutive_failures!",
+                created_at as "created_at!"
+            FROM update_webhook($1, $2, $3, $4)"#,
+        id,
+        params.url,
+        params.secret,
+        &params.events as &[WebhookEvent]
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+pub async fn delete(pool: &PgPool, id: usize) -> Result<Webhook> {
+    let id: i32 = id.try_into()?;
+
+    Ok(query_as!(
+        Webhook,
+        r#"DELETE FROM webhooks WHERE id = $1
+            RETURNING
+                id,
+                id_user,
+                url,
+                secret,
+                events as "events: Vec<WebhookEvent>",
+                enabled,
+                consecutive_failures,
+                created_at"#,
+        id
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Counts a delivery attempt towards the webhook's consecutive failures, which a successful
+/// one resets, and disables the webhook once they reach `disable_after`.
+pub async fn record_attempt(
+    pool: &PgPool,
+    id: i32,
+    success: bool,
+    disable_after: i32,
+) -> Result<Webhook> {
+    Ok(query_as!(
+        Webhook,
+        r#"UPDATE webhooks SET
+                consecutive_failures = CASE WHEN $2 THEN 0 ELSE consecutive_failures + 1 END,
+                enabled = enabled AND ($2 OR consecutive_failures + 1 < $3)
+            WHERE id = $1
+            RETURNING
+                id,
+                id_user,
+                url,
+                secret,
+                events as "events: Vec<WebhookEvent>",
+                enabled,
+                consecutive_failures,
+                created_at"#,
+        id,
+        success,
+        disable_after
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::webhook::{
+        create, delete, get_all_of_user, get_one, is_public, record_attempt, update, validate,
+        validate_host, WebhookEvent, WebhookParams,
+    };
+    use anyhow::Result;
+    use sqlx::PgPool;
+
+    fn params() -> WebhookParams {
+        WebhookParams {
+            url: "https://bids.example.com/hooks/seap".into(),
+            secret: Some("s3cr3t-s3cr3t-s3cr3t".into()),
+            events: vec![WebhookEvent::MatchCreated],
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&params()).is_ok());
+
+        let cases = [
+            (
+                WebhookParams {
+                    url: "not a url".into(),
+                    ..params()
+                },
+                "Invalid webhook URL.",
+            ),
+            (
+                WebhookParams {
+                    url: "ftp://bids.example.com/".into(),
+                    ..params()
+                },
+                "Webhook URLs must use http or https.",
+            ),
+            (
+                WebhookParams {
+                    events: vec![],
+                    ..params()
+                },
+                "Expected at least one webhook event.",
+            ),
+            (
+                WebhookParams {
+                    secret: Some("short".into()),
+                    ..params()
+                },
+                "Webhook secrets must have at least 16 characters.",
+            ),
+        ];
+
+        for (params, expected) in cases {
+            assert_eq!(validate(&params).unwrap_err().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["93.184.216.34", "2606:2800:220:1:248:1893:25c8:1946"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+
+        for ip in [
+            "0.0.0.0",
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_host() {
+        let with_url = |url: &str| WebhookParams {
+            url: url.into(),
+            ..params()
+        };
+
+        assert!(validate_host(&with_url("https://93.184.216.34/hooks"))
+            .await
+            .is_ok());
+
+        for url in [
+            "http://127.0.0.1:8000/hook",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://192.168.1.1/",
+            "http://[::1]/hook",
+            "http://localhost/hook",
+        ] {
+            assert_eq!(
+                validate_host(&with_url(url)).await.unwrap_err().to_string(),
+                "Webhook URLs must point to a public address.",
+                "{url}"
+            );
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users"))]
+    async fn test_create(pool: PgPool) -> Result<()> {
+        {
+            let res = create(&pool, 1, &params()).await?;
+
+            assert_eq!(res.id_user, 1);
+            assert_eq!(res.secret, "s3cr3t-s3cr3t-s3cr3t");
+            assert_eq!(res.events, vec![WebhookEvent::MatchCreated]);
+            assert!(res.enabled);
+        }
+
+        {
+            let p = WebhookParams {
+                secret: None,
+                ..params()
+            };
+
+            let res = create(&pool, 1, &p).await?;
+
+            assert_eq!(res.secret.len(), 32);
+        }
+
+        {
+            let res = create(&pool, 10, &params()).await;
+
+            assert!(res.is_err());
+        }
+
+        {
+            let res = get_all_of_user(&pool, 1).await?;
+
+            assert_eq!(res.len(), 2);
+            assert!(get_all_of_user(&pool, 2).await?.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test(fixtures("users"))]
+    async fn test_record_attempt_and_update(pool: PgPool) -> Result<()> {
+        let webhook = create(&pool, 1, &params()).await?;
+
+        {
+            record_attempt(&pool, webhook.id, false, 3).await?;
+            let res = record_attempt(&pool, webhook.id, true, 3).await?;
+
+            assert_eq!(res.consecutive_failures, 0);
+        }
+
+        {
+            record_attempt(&pool, webhook.id, false, 3).await?;
+            let res = record_attempt(&pool, webhook.id, false, 3).await?;
+
+            assert_eq!(res.consecutive_failures, 2);
+            assert!(res.enabled);
+
+            let res = record_attempt(&pool, webhook.id, false, 3).await?;
+
+            assert_eq!(res.consecutive_failures, 3);
+            assert!(!res.enabled);
+
+            let res = record_attempt(&pool, webhook.id, true, 3).await?;
+
+            assert!(!res.enabled);
+        }
+
+        {
+            let p = WebhookParams {
+                secret: None,
+                events: vec![WebhookEvent::MatchCreated, WebhookEvent::MatchUpdated],
+                ..params()
+            };
+
+            let res = update(&pool, webhook.id as usize, &p).await?;
+
+            assert!(res.enabled);
+            assert_eq!(res.consecutive_failures, 0);
+            assert_eq!(res.secret, "s3cr3t-s3cr3t-s3cr3t");
+            assert_eq!(res.events.len(), 2);
+        }
+
+        {
+            delete(&pool, webhook.id as usize).await?;
+
+            assert!(get_one(&pool, webhook.id as usize).await.is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,379 @@
+//! Notification of users about their new matches.
+//!
+//! Matches are recorded without a `notified_at` timestamp; every run groups the ones a user
+//! wasn't notified about yet into a single [`Digest`], mails it and stamps them as delivered.
+//! Matches of paused subscriptions and of those that only send to webhooks are left out. A user
+//! whose digest couldn't be sent keeps the matches pending for the next run, as does a user
+//! whose [`Preferences`](crate::models::Preferences) say it isn't time for one yet, see
+//! [`schedule`].
+
+#[cfg(test)]
+pub(crate) mod capture;
+pub mod digest;
+pub mod mailer;
+pub mod schedule;
+
+use crate::models::{matches, preferences, tender, user};
+use anyhow::Result;
+use digest::{Digest, Entry};
+use mailer::Mailer;
+use shuttle_service::tracing::{info, warn};
+use sqlx::PgPool;
+use std::{env, time::Duration};
+use time::OffsetDateTime;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Digests delivered.
+    pub sent: usize,
+    /// Digests that couldn't be delivered.
+    pub failed: usize,
+    /// Digests held back until the time the user asked for.
+    pub deferred: usize,
+    /// Matches delivered in all the digests.
+    pub matches: usize,
+}
+
+/// Sends a digest to every user with pending matches whose digest is due at `now`.
+pub async fn send_digests(pool: &PgPool, mailer: &Mailer, now: OffsetDateTime) -> Result<Report> {
+    let mut report = Report::default();
+
+    for id_user in matches::get_users_with_unnotified(pool).await? {
+        match send_digest(pool, mailer, id_user, now).await {
+            Ok(Some(delivered)) => {
+                report.sent += 1;
+                report.matches += delivered;
+            }
+            Ok(None) => report.deferred += 1,
+            Err(err) => {
+                report.failed += 1;
+
+                warn!("Sending the digest of user {id_user} failed: {err:#}");
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Returns how many matches were delivered, or `None` if no digest is due yet.
+async fn send_digest(
+    pool: &PgPool,
+    mailer: &Mailer,
+    id_user: i32,
+    now: OffsetDateTime,
+) -> Result<Option<usize>> {
+    let user = user::get_one(pool, id_user.try_into()?).await?;
+    let prefs = preferences::get_of_user(pool, id_user.try_into()?).await?;
+
+    let mut pending = matches::get_unnotified_of_user(pool, id_user).await?;
+    let last_sent = matches::get_last_notified_of_user(pool, id_user).await?;
+
+    let Some(oldest) = pending.iter().map(|m| m.created_at).min() else {
+        return Ok(None);
+    };
+
+    if !schedule::is_due(&prefs, now, last_sent, oldest) {
+        return Ok(None);
+    }
+
+    if let Some(cutoff) = schedule::cutoff(&prefs, now) {
+        pending.retain(|m| m.created_at < cutoff);
+    }
+
+    let mut entries = Vec::new();
+
+    for m in pending {
+        let tender = tender::get_one(pool, m.id_tender.try_into()?).await?;
+
+        entries.push(Entry { m, tender });
+    }
+
+    let digest = Digest { entries };
+
+    mailer.send(&user.email, &digest).await?;
+
+    let ids: Vec<i32> = digest.entries.iter().map(|e| e.m.id).collect();
+
+    matches::mark_notified(pool, &ids, now).await?;
+
+    Ok(Some(ids.len()))
+}
+
+/// How often due digests are looked for, `DIGEST_INTERVAL_SECS` or every minute. Users choose how
+/// often they actually get one.
+pub fn interval_from_env() -> Duration {
+    env::var("DIGEST_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map_or(Duration::from_secs(60), Duration::from_secs)
+}
+
+/// Sends digests forever, every `interval`.
+pub fn spawn(pool: PgPool, mailer: Mailer, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match send_digests(&pool, &mailer, OffsetDateTime::now_utc()).await {
+                Ok(report) if report.sent + report.failed > 0 => info!(
+                    "Sent {} digests with {} matches, {} failed.",
+                    report.sent, report.matches, report.failed
+                ),
+                Ok(_) => {}
+                Err(err) => warn!("Sending digests failed: {err:#}"),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::{
+            load_fixtures, matches,
+            preferences::{self, Frequency, Preferences},
+        },
+        notify::{
+            capture::CaptureServer,
+            mailer::{Mailer, SmtpConfig, Tls},
+            send_digests, Report,
+        },
+        utils::Pagination,
+    };
+    use anyhow::Result;
+    use sqlx::{Executor, PgPool};
+    use time::{macros::datetime, OffsetDateTime};
+
+    fn config(port: u16) -> SmtpConfig {
+        SmtpConfig {
+            host: "127.0.0.1".into(),
+            port,
+            tls: Tls::None,
+            username: None,
+            password: None,
+            from: "SEAP Subscriptions <noreply@seap.test>".into(),
+        }
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_send_digests(pool: PgPool) -> Result<()> {
+        load_fixtures(&pool).await?;
+
+        let server = CaptureServer::start().await?;
+        let mailer = Mailer::new(&config(server.port))?;
+
+        {
+            let report = send_digests(&pool, &mailer, OffsetDateTime::now_utc()).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    sent: 2,
+                    matches: 4,
+                    ..Default::default()
+                }
+            );
+        }
+
+        {
+            let messages = server.messages();
+
+            assert_eq!(messages.len(), 2);
+            assert_eq!(messages[0].from, "noreply@seap.test");
+            assert_eq!(messages[0].to, vec!["test@test.test"]);
+            assert_eq!(messages[1].to, vec!["foo@bar.com"]);
+
+            let data = &messages[0].data;
+
+            assert!(data.contains("Subject: 3 new tenders match your subscriptions"));
+            assert!(data.contains("Content-Type: multipart/alternative"));
+            assert!(data.contains("Content-Type: text/plain"));
+            assert!(data.contains("Content-Type: text/html"));
+            assert!(data.contains("Modernizare drum judetean DJ 107 (CN1051234)"));
+            assert!(data.contains("Achizitie mobilier de birou (SCN1090001)"));
+        }
+
+        {
+            let pagination = Pagination {
+                start_index: 0,
+                count: 10,
+            };
+
+            let res = matches::get_all_of_user(&pool, 1, &pagination).await?;
+
+            assert!(res.iter().all(|m| m.notified_at.is_some()));
+        }
+
+        {
+            let report = send_digests(&pool, &mailer, OffsetDateTime::now_utc()).await?;
+
+            assert_eq!(report, Report::default());
+            assert_eq!(server.messages().len(), 2);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_send_digests_failure(pool: PgPool) -> Result<()> {
+        load_fixtures(&pool).await?;
+
+        let port = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+
+            listener.local_addr()?.port()
+        };
+
+        let mailer = Mailer::new(&config(port))?;
+
+        {
+            let report = send_digests(&pool, &mailer, OffsetDateTime::now_utc()).await?;
+
+            assert_eq!(report.sent, 0);
+            assert_eq!(report.failed, 2);
+        }
+
+        {
+            let res = matches::get_unnotified_of_user(&pool, 1).await?;
+
+            assert_eq!(res.len(), 3);
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_send_digests_deferred(pool: PgPool) -> Result<()> {
+        load_fixtures(&pool).await?;
+
+        pool.execute("UPDATE matches SET created_at = '2023-02-15 10:00Z'")
+            .await?;
+
+        let daily = Preferences {
+            frequency: Frequency::Daily,
+            ..Default::default()
+        };
+
+        preferences::set(&pool, 1, &daily).await?;
+
+        let server = CaptureServer::start().await?;
+        let mailer = Mailer::new(&config(server.port))?;
+
+        {
+            let report = send_digests(&pool, &mailer, datetime!(2023-02-15 12:00 UTC)).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    sent: 1,
+                    deferred: 1,
+                    matches: 1,
+                    ..Default::default()
+                }
+            );
+            assert_eq!(server.messages()[0].to, vec!["foo@bar.com"]);
+        }
+
+        // found after the 08:00 digest, so left for the next one
+        pool.execute(
+            "UPDATE matches SET created_at = '2023-02-16 06:30Z' WHERE id_subscription = 2",
+        )
+        .await?;
+
+        {
+            // 09:00 in Bucharest
+            let report = send_digests(&pool, &mailer, datetime!(2023-02-16 07:00 UTC)).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    sent: 1,
+                    matches: 2,
+                    ..Default::default()
+                }
+            );
+            assert_eq!(server.messages()[1].to, vec!["test@test.test"]);
+        }
+
+        {
+            let report = send_digests(&pool, &mailer, datetime!(2023-02-16 12:00 UTC)).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    deferred: 1,
+                    ..Default::default()
+                }
+            );
+        }
+
+        {
+            let report = send_digests(&pool, &mailer, datetime!(2023-02-17 06:00 UTC)).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    sent: 1,
+                    matches: 1,
+                    ..Default::default()
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_send_digests_channels(pool: PgPool) -> Result<()> {
+        load_fixtures(&pool).await?;
+
+        // subscription 1 only sends to webhooks, 2 is paused, 3 is left to its owner
+        pool.execute(
+            "UPDATE subscriptions SET channels = '{webhook}' WHERE id = 1;
+            UPDATE subscriptions SET paused_until = NOW() + INTERVAL '1 day' WHERE id = 2;
+            INSERT INTO notification_preferences (id_user, channels) VALUES (2, '{webhook}');",
+        )
+        .await?;
+
+        let server = CaptureServer::start().await?;
+        let mailer = Mailer::new(&config(server.port))?;
+
+        {
+            let report = send_digests(&pool, &mailer, OffsetDateTime::now_utc()).await?;
+
+            assert_eq!(report, Report::default());
+            assert!(server.messages().is_empty());
+        }
+
+        pool.execute("UPDATE subscriptions SET paused_until = NULL WHERE id = 2")
+            .await?;
+
+        {
+            let report = send_digests(&pool, &mailer, OffsetDateTime::now_utc()).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    sent: 1,
+                    matches: 1,
+                    ..Default::default()
+                }
+            );
+
+            let messages = server.messages();
+
+            assert_eq!(messages[0].to, vec!["test@test.test"]);
+            assert!(messages[0]
+                .data
+                .contains("Subject: 1 new tender matches your subscriptions"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+//! A minimal in-process SMTP server that accepts every message and keeps it in memory, so
+//! mail delivery can be tested end to end without a real relay.
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Captured {
+    pub from: String,
+    pub to: Vec<String>,
+    /// The message as sent after `DATA`, headers included and dot-stuffing removed.
+    pub data: String,
+}
+
+pub struct CaptureServer {
+    pub port: u16,
+    messages: Arc<Mutex<Vec<Captured>>>,
+}
+
+impl CaptureServer {
+    /// Listens on a random local port until the server is dropped along with the runtime.
+    pub async fn start() -> io::Result<CaptureServer> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+
+        let captured = messages.clone();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(session(stream, captured.clone()));
+            }
+        });
+
+        Ok(CaptureServer { port, messages })
+    }
+
+    pub fn messages(&self) -> Vec<Captured> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+async fn session(stream: TcpStream, messages: Arc<Mutex<Vec<Captured>>>) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    writer.write_all(b"220 capture ESMTP\r\n").await?;
+
+    let mut message = Captured::default();
+
+    while let Some(line) = lines.next_line().await? {
+        let command = line.to_uppercase();
+
+        let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+            b"250 capture\r\n"
+        } else if command.starts_with("MAIL FROM:") {
+            message = Captured {
+                from: address(&line),
+                ..Default::default()
+            };
+
+            b"250 OK\r\n"
+        } else if command.starts_with("RCPT TO:") {
+            message.to.push(address(&line));
+
+            b"250 OK\r\n"
+        } else if command == "DATA" {
+            writer
+                .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                .await?;
+
+            while let Some(line) = lines.next_line().await? {
+                if line == "." {
+                    break;
+                }
+
+                let line = line.strip_prefix('.').unwrap_or(&line);
+
+                message.data.push_str(line);
+                message.data.push_str("\r\n");
+            }
+
+            messages.lock().unwrap().push(std::mem::take(&mut message));
+
+            b"250 OK\r\n"
+        } else if command == "QUIT" {
+            writer.write_all(b"221 Bye\r\n").await?;
+
+            return Ok(());
+        } else if command == "RSET" {
+            message = Captured::default();
+
+            b"250 OK\r\n"
+        } else if command == "NOOP" {
+            b"250 OK\r\n"
+        } else {
+            b"502 Command not implemented\r\n"
+        };
+
+        writer.write_all(reply).await?;
+    }
+
+    Ok(())
+}
+
+fn address(line: &str) -> String {
+    let start = line.find('<').map_or(0, |i| i + 1);
+    let end = line.rfind('>').unwrap_or(line.len());
+
+    line.get(start..end).unwrap_or_default().to_string()
+}
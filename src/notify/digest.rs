@@ -0,0 +1,286 @@
+//! The digest email listing a user's new matches.
+
+use crate::models::{Match, Tender};
+use time::{macros::format_description, OffsetDateTime};
+
+/// A match together with the tender it points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub m: Match,
+    pub tender: Tender,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    pub entries: Vec<Entry>,
+}
+
+impl Digest {
+    pub fn subject(&self) -> String {
+        match self.entries.len() {
+            1 => "1 new tender matches your subscriptions".into(),
+            n => format!("{n} new tenders match your subscriptions"),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = format!("{}.\n", self.subject());
+
+        for Entry { m, tender } in &self.entries {
+            text.push_str(&format!("\n{} ({})\n", tender.title, tender.notice_number));
+
+            for (label, value) in details(m, tender) {
+                text.push_str(&format!("  {label}: {value}\n"));
+            }
+        }
+
+        text
+    }
+
+    pub fn html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<body>\n<p>{}.</p>\n",
+            escape(&self.subject())
+        );
+
+        for Entry { m, tender } in &self.entries {
+            html.push_str(&format!(
+                "<h3>{} <small>({})</small></h3>\n<ul>\n",
+                escape(&tender.title),
+                escape(&tender.notice_number)
+            ));
+
+            for (label, value) in details(m, tender) {
+                html.push_str(&format!(
+                    "<li><b>{}:</b> {}</li>\n",
+                    escape(label),
+                    escape(&value)
+                ));
+            }
+
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+
+        html
+    }
+}
+
+pub(crate) fn details(m: &Match, tender: &Tender) -> Vec<(&'static str, String)> {
+    let mut details = Vec::new();
+
+    if let Some(authority) = &tender.contracting_authority {
+        details.push(("Contracting authority", authority.clone()));
+    }
+
+    if let Some(value) = tender.estimated_value {
+        details.push((
+            "Estimated value",
+            format!("{} {}", amount(value), tender.currency),
+        ));
+    }
+
+    if let Some(deadline) = tender.submission_deadline {
+        details.push(("Submission deadline", datetime(deadline)));
+    }
+
+    let mut keywords: Vec<&str> = Vec::new();
+
+    for hit in &m.explanation.hits {
+        if !keywords.contains(&hit.keyword.as_str()) {
+            keywords.push(&hit.keyword);
+        }
+    }
+
+    if !keywords.is_empty() {
+        details.push(("Keywords", keywords.join(", ")));
+    }
+
+    if let Some(score) = m.score {
+        details.push(("Relevance", format!("{score}/100")));
+    }
+
+    details
+}
+
+/// Groups thousands the Romanian way, `1.500.000`.
+pub(crate) fn amount(value: i64) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let mut grouped = String::new();
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+
+        grouped.push(digit);
+    }
+
+    match value < 0 {
+        true => format!("-{grouped}"),
+        false => grouped,
+    }
+}
+
+pub(crate) fn datetime(datetime: OffsetDateTime) -> String {
+    let format = format_description!("[year]-[month]-[day] [hour]:[minute] UTC");
+
+    datetime
+        .to_offset(time::UtcOffset::UTC)
+        .format(format)
+        .unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        matching::{Hit, MatchResult, Outcome, TextField},
+        models::{tender, Match, Tender},
+        notify::digest::{amount, Digest, Entry},
+    };
+    use sqlx::types::Json;
+    use time::macros::datetime;
+
+    fn entry() -> Entry {
+        let outcome = Outcome::Unconstrained;
+
+        Entry {
+            m: Match {
+                id: 1,
+                id_subscription: 1,
+                id_tender: 1,
+                matched_fields: vec!["title".into()],
+                score: Some(80),
+                explanation: Json(MatchResult {
+                    price: outcome,
+                    title: Outcome::Passed,
+                    description: outcome,
+                    additional_info: outcome,
+                    query: outcome,
+                    cpv: outcome,
+                    county: outcome,
+                    authority: outcome,
+                    deadline: outcome,
+                    procedure_type: outcome,
+                    hits: vec![
+                        Hit {
+                            keyword: "calculatoare".into(),
+                            field: TextField::Title,
+                            start: 10,
+                            end: 22,
+                        },
+                        Hit {
+                            keyword: "calculatoare".into(),
+                            field: TextField::Title,
+                            start: 36,
+                            end: 48,
+                        },
+                    ],
+                }),
+                created_at: datetime!(2023-02-10 11:00 UTC),
+                notified_at: None,
+            },
+            tender: Tender {
+                id: 1,
+                title: "Furnizare calculatoare <portabile> & calculatoare".into(),
+                description: None,
+                estimated_value: Some(1_250_000),
+                contracting_authority: Some("Consiliul Judetean Cluj".into()),
+                cpv_code: None,
+                submission_deadline: Some(datetime!(2023-03-01 17:00 +02:00)),
+                procedure_type: None,
+                county: None,
+                contracting_authority_cui: None,
+                ..tender::sample()
+            },
+        }
+    }
+
+    #[test]
+    fn test_subject() {
+        {
+            let digest = Digest {
+                entries: vec![entry()],
+            };
+
+            assert_eq!(digest.subject(), "1 new tender matches your subscriptions");
+        }
+
+        {
+            let digest = Digest {
+                entries: vec![entry(), entry()],
+            };
+
+            assert_eq!(digest.subject(), "2 new tenders match your subscriptions");
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        let digest = Digest {
+            entries: vec![entry()],
+        };
+
+        assert_eq!(
+            digest.text(),
+            "1 new tender matches your subscriptions.\n\
+             \n\
+             Furnizare calculatoare <portabile> & calculatoare (CN1052000)\n\
+             \x20 Contracting authority: Consiliul Judetean Cluj\n\
+             \x20 Estimated value: 1.250.000 RON\n\
+             \x20 Submission deadline: 2023-03-01 15:00 UTC\n\
+             \x20 Keywords: calculatoare\n\
+             \x20 Relevance: 80/100\n"
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let digest = Digest {
+            entries: vec![entry()],
+        };
+
+        let html = digest.html();
+
+        assert!(html.contains(
+            "<h3>Furnizare calculatoare &lt;portabile&gt; &amp; calculatoare \
+             <small>(CN1052000)</small></h3>"
+        ));
+        assert!(html.contains("<li><b>Estimated value:</b> 1.250.000 RON</li>"));
+        assert!(!html.contains("<portabile>"));
+    }
+
+    #[test]
+    fn test_amount() {
+        let cases = [
+            (0, "0"),
+            (999, "999"),
+            (1_000, "1.000"),
+            (250_000, "250.000"),
+            (1_250_000, "1.250.000"),
+            (-80_000, "-80.000"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(amount(value), expected);
+        }
+    }
+}
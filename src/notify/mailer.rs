@@ -0,0 +1,148 @@
+//! Delivery of digests over SMTP.
+
+use super::digest::Digest;
+use anyhow::{Error, Result};
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::env;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tls {
+    /// Plain text, only meant for a relay on the same host or tests.
+    None,
+    /// Upgrades the connection with STARTTLS, usually on port 587.
+    #[default]
+    StartTls,
+    /// Connects over TLS right away, usually on port 465.
+    Wrapper,
+}
+
+impl Tls {
+    pub fn parse(text: &str) -> Result<Tls> {
+        match text.trim().to_lowercase().as_str() {
+            "none" => Ok(Tls::None),
+            "starttls" => Ok(Tls::StartTls),
+            "tls" | "wrapper" => Ok(Tls::Wrapper),
+            other => Err(Error::msg(format!("Unknown SMTP TLS mode: {other}."))),
+        }
+    }
+
+    fn default_port(self) -> u16 {
+        match self {
+            Tls::None => 25,
+            Tls::StartTls => 587,
+            Tls::Wrapper => 465,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: Tls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// The sender, either an address or `Name <address>`.
+    pub from: String,
+}
+
+impl SmtpConfig {
+    /// Reads `SMTP_HOST`, `SMTP_PORT`, `SMTP_TLS` (`none`, `starttls` or `tls`),
+    /// `SMTP_USERNAME`, `SMTP_PASSWORD` and `SMTP_FROM`. Returns `None` when `SMTP_HOST` isn't
+    /// set, i.e. when mail delivery is disabled.
+    pub fn from_env() -> Result<Option<SmtpConfig>> {
+        let Ok(host) = env::var("SMTP_HOST") else {
+            return Ok(None);
+        };
+
+        let tls = match env::var("SMTP_TLS") {
+            Ok(tls) => Tls::parse(&tls)?,
+            Err(_) => Tls::default(),
+        };
+
+        let port = match env::var("SMTP_PORT") {
+            Ok(port) => port.parse()?,
+            Err(_) => tls.default_port(),
+        };
+
+        Ok(Some(SmtpConfig {
+            host,
+            port,
+            tls,
+            username: env::var("SMTP_USERNAME").ok(),
+            password: env::var("SMTP_PASSWORD").ok(),
+            from: env::var("SMTP_FROM")
+                .unwrap_or_else(|_| "SEAP Subscriptions <noreply@localhost>".into()),
+        }))
+    }
+}
+
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl Mailer {
+    pub fn new(config: &SmtpConfig) -> Result<Mailer> {
+        let builder = match config.tls {
+            Tls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+            Tls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+            Tls::Wrapper => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        };
+
+        let builder = match (&config.username, &config.password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+
+        Ok(Mailer {
+            transport: builder.port(config.port).build(),
+            from: config.from.parse()?,
+        })
+    }
+
+    pub async fn send(&self, to: &str, digest: &Digest) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse()?)
+            .subject(digest.subject())
+            .multipart(MultiPart::alternative_plain_html(
+                digest.text(),
+                digest.html(),
+            ))?;
+
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::notify::mailer::Tls;
+
+    #[test]
+    fn test_tls_parse() {
+        let cases = [
+            ("none", Tls::None),
+            ("STARTTLS", Tls::StartTls),
+            (" tls ", Tls::Wrapper),
+            ("wrapper", Tls::Wrapper),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(Tls::parse(text).unwrap(), expected, "{text}");
+        }
+
+        assert_eq!(
+            Tls::parse("ssl").unwrap_err().to_string(),
+            "Unknown SMTP TLS mode: ssl."
+        );
+    }
+}
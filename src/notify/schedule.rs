@@ -0,0 +1,283 @@
+//! When a user's digest is due, according to their [`Preferences`].
+//!
+//! Daily and weekly digests go out at the first run after the delivery time, in the user's
+//! timezone, and only carry the matches found before it. Nothing is sent during quiet hours; a
+//! digest that fell in them goes out once they end.
+
+use crate::models::preferences::{Frequency, Preferences};
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, Time};
+use time_tz::{OffsetDateTimeExt, PrimitiveDateTimeExt, Tz};
+
+/// Whether a digest should be sent at `now` to a user last sent one at `last_sent`, whose
+/// oldest pending match was found at `oldest_pending`.
+pub fn is_due(
+    prefs: &Preferences,
+    now: OffsetDateTime,
+    last_sent: Option<OffsetDateTime>,
+    oldest_pending: OffsetDateTime,
+) -> bool {
+    let tz = prefs.tz();
+    let local = now.to_timezone(tz);
+
+    if let (Some(start), Some(end)) = (prefs.quiet_hours_start, prefs.quiet_hours_end) {
+        if is_quiet(local.time(), start, end) {
+            return false;
+        }
+    }
+
+    match prefs.frequency {
+        Frequency::Instant => true,
+        Frequency::Hourly => last_sent.is_none_or(|last| now - last >= Duration::HOUR),
+        Frequency::Daily | Frequency::Weekly => {
+            let slot = last_slot(prefs, tz, local);
+
+            oldest_pending < slot && last_sent.is_none_or(|last| last < slot)
+        }
+    }
+}
+
+/// The time before which the matches of a digest sent at `now` were found, for daily and
+/// weekly digests; the others carry every pending match.
+pub fn cutoff(prefs: &Preferences, now: OffsetDateTime) -> Option<OffsetDateTime> {
+    let tz = prefs.tz();
+
+    match prefs.frequency {
+        Frequency::Daily | Frequency::Weekly => Some(last_slot(prefs, tz, now.to_timezone(tz))),
+        Frequency::Instant | Frequency::Hourly => None,
+    }
+}
+
+/// Quiet hours from `start` until `end`, which is on the next day when it's before `start`.
+fn is_quiet(time: Time, start: Time, end: Time) -> bool {
+    if start < end {
+        start <= time && time < end
+    } else {
+        start <= time || time < end
+    }
+}
+
+/// The latest delivery time at or before `local`, on a Monday for weekly digests.
+fn last_slot(prefs: &Preferences, tz: &Tz, local: OffsetDateTime) -> OffsetDateTime {
+    let (step, date) = match prefs.frequency {
+        Frequency::Weekly => (
+            Duration::weeks(1),
+            local.date() - Duration::days(local.weekday().number_days_from_monday().into()),
+        ),
+        _ => (Duration::days(1), local.date()),
+    };
+
+    let mut slot = PrimitiveDateTime::new(date, prefs.delivery_time);
+
+    if slot > PrimitiveDateTime::new(local.date(), local.time()) {
+        slot -= step;
+    }
+
+    // a delivery time skipped by a DST change is taken in the offset of `local`
+    slot.assume_timezone(tz)
+        .take_first()
+        .unwrap_or_else(|| slot.assume_offset(local.offset()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::preferences::{Frequency, Preferences},
+        notify::schedule::is_due,
+    };
+    use time::macros::{datetime, time};
+
+    fn prefs(frequency: Frequency) -> Preferences {
+        Preferences {
+            frequency,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_instant() {
+        let now = datetime!(2023-02-15 10:00 UTC);
+
+        assert!(is_due(
+            &prefs(Frequency::Instant),
+            now,
+            Some(now - time::Duration::minutes(1)),
+            now
+        ));
+    }
+
+    #[test]
+    fn test_hourly() {
+        let prefs = prefs(Frequency::Hourly);
+        let now = datetime!(2023-02-15 10:00 UTC);
+        let pending = datetime!(2023-02-15 09:50 UTC);
+
+        assert!(is_due(&prefs, now, None, pending));
+        assert!(is_due(
+            &prefs,
+            now,
+            Some(datetime!(2023-02-15 09:00 UTC)),
+            pending
+        ));
+        assert!(!is_due(
+            &prefs,
+            now,
+            Some(datetime!(2023-02-15 09:30 UTC)),
+            pending
+        ));
+    }
+
+    #[test]
+    fn test_daily() {
+        // 08:00 in Bucharest is 06:00 UTC in winter
+        let prefs = prefs(Frequency::Daily);
+        let pending = datetime!(2023-02-14 12:00 UTC);
+
+        let cases = [
+            // before the delivery time, the previous one was already served
+            (
+                datetime!(2023-02-15 05:59 UTC),
+                Some(datetime!(2023-02-14 06:00 UTC)),
+                false,
+            ),
+            (
+                datetime!(2023-02-15 06:00 UTC),
+                Some(datetime!(2023-02-14 06:00 UTC)),
+                true,
+            ),
+            (datetime!(2023-02-15 18:00 UTC), None, true),
+            // already sent today
+            (
+                datetime!(2023-02-15 18:00 UTC),
+                Some(datetime!(2023-02-15 06:01 UTC)),
+                false,
+            ),
+            // the matches were found after today's delivery time
+            (
+                datetime!(2023-02-14 18:00 UTC),
+                Some(datetime!(2023-02-13 06:00 UTC)),
+                false,
+            ),
+        ];
+
+        for (now, last_sent, expected) in cases {
+            assert_eq!(is_due(&prefs, now, last_sent, pending), expected, "{now}");
+        }
+    }
+
+    #[test]
+    fn test_daily_timezone() {
+        let pending = datetime!(2023-07-01 00:00 UTC);
+        let last_sent = Some(datetime!(2023-07-01 12:00 UTC));
+
+        // 08:00 in Bucharest is 05:00 UTC in summer
+        let bucharest = prefs(Frequency::Daily);
+
+        assert!(!is_due(
+            &bucharest,
+            datetime!(2023-07-02 04:59 UTC),
+            last_sent,
+            pending
+        ));
+        assert!(is_due(
+            &bucharest,
+            datetime!(2023-07-02 05:00 UTC),
+            last_sent,
+            pending
+        ));
+
+        let new_york = Preferences {
+            timezone: "America/New_York".into(),
+            ..bucharest
+        };
+
+        assert!(!is_due(
+            &new_york,
+            datetime!(2023-07-02 11:59 UTC),
+            last_sent,
+            pending
+        ));
+        assert!(is_due(
+            &new_york,
+            datetime!(2023-07-02 12:00 UTC),
+            last_sent,
+            pending
+        ));
+    }
+
+    #[test]
+    fn test_weekly() {
+        let prefs = Preferences {
+            delivery_time: time!(09:30),
+            ..prefs(Frequency::Weekly)
+        };
+        let pending = datetime!(2023-02-14 12:00 UTC);
+        let last_sent = Some(datetime!(2023-02-13 07:30 UTC));
+
+        // Sunday, then Monday before and after 09:30 in Bucharest
+        assert!(!is_due(
+            &prefs,
+            datetime!(2023-02-19 20:00 UTC),
+            last_sent,
+            pending
+        ));
+        assert!(!is_due(
+            &prefs,
+            datetime!(2023-02-20 07:29 UTC),
+            last_sent,
+            pending
+        ));
+        assert!(is_due(
+            &prefs,
+            datetime!(2023-02-20 07:30 UTC),
+            last_sent,
+            pending
+        ));
+        assert!(is_due(
+            &prefs,
+            datetime!(2023-02-22 07:30 UTC),
+            last_sent,
+            pending
+        ));
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let prefs = Preferences {
+            quiet_hours_start: Some(time!(22:00)),
+            quiet_hours_end: Some(time!(07:00)),
+            ..prefs(Frequency::Instant)
+        };
+        let pending = datetime!(2023-02-14 12:00 UTC);
+
+        let cases = [
+            (datetime!(2023-02-14 19:59 UTC), true),
+            (datetime!(2023-02-14 20:00 UTC), false),
+            (datetime!(2023-02-14 23:00 UTC), false),
+            (datetime!(2023-02-15 04:59 UTC), false),
+            (datetime!(2023-02-15 05:00 UTC), true),
+        ];
+
+        for (now, expected) in cases {
+            assert_eq!(is_due(&prefs, now, None, pending), expected, "{now}");
+        }
+
+        let daytime = Preferences {
+            quiet_hours_start: Some(time!(12:00)),
+            quiet_hours_end: Some(time!(14:00)),
+            ..prefs
+        };
+
+        assert!(!is_due(
+            &daytime,
+            datetime!(2023-02-14 10:00 UTC),
+            None,
+            pending
+        ));
+        assert!(is_due(
+            &daytime,
+            datetime!(2023-02-14 12:00 UTC),
+            None,
+            pending
+        ));
+    }
+}
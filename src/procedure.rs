@@ -0,0 +1,85 @@
+//! Award procedure types a subscription can be restricted to.
+//!
+//! Notices only carry the procedure as free text ("Licitație deschisă", "Procedură
+//! simplificată", ...), so [`ProcedureType::classify`] maps the usual SEAP and TED wordings onto
+//! the handful of types users actually filter by.
+
+use crate::matching::normalize::normalize;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "procedure_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ProcedureType {
+    OpenTender,
+    SimplifiedProcedure,
+    Negotiation,
+    DirectPurchase,
+}
+
+impl PgHasArrayType for ProcedureType {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_procedure_type")
+    }
+}
+
+const PATTERNS: [(&str, ProcedureType); 9] = [
+    ("licitatie deschisa", ProcedureType::OpenTender),
+    ("open", ProcedureType::OpenTender),
+    ("simplificata", ProcedureType::SimplifiedProcedure),
+    ("simplified", ProcedureType::SimplifiedProcedure),
+    ("negociere", ProcedureType::Negotiation),
+    ("negotiat", ProcedureType::Negotiation),
+    ("cumparare directa", ProcedureType::DirectPurchase),
+    ("achizitie directa", ProcedureType::DirectPurchase),
+    ("direct", ProcedureType::DirectPurchase),
+];
+
+impl ProcedureType {
+    pub fn classify(procedure: &str) -> Option<ProcedureType> {
+        let procedure = normalize(procedure);
+
+        PATTERNS
+            .iter()
+            .find(|(pattern, _)| procedure.contains(pattern))
+            .map(|(_, procedure_type)| *procedure_type)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::procedure::ProcedureType;
+
+    #[test]
+    fn test_classify() {
+        let cases = [
+            ("Licitație deschisă", Some(ProcedureType::OpenTender)),
+            ("LICITATIE DESCHISA", Some(ProcedureType::OpenTender)),
+            ("Open procedure", Some(ProcedureType::OpenTender)),
+            (
+                "Procedură simplificată",
+                Some(ProcedureType::SimplifiedProcedure),
+            ),
+            (
+                "Negociere fără publicare prealabilă",
+                Some(ProcedureType::Negotiation),
+            ),
+            (
+                "Negotiated without prior publication",
+                Some(ProcedureType::Negotiation),
+            ),
+            ("Cumpărare directă", Some(ProcedureType::DirectPurchase)),
+            (
+                "Achiziție directă din catalog",
+                Some(ProcedureType::DirectPurchase),
+            ),
+            ("Dialog competitiv", None),
+            ("", None),
+        ];
+
+        for (procedure, expected) in cases {
+            assert_eq!(ProcedureType::classify(procedure), expected, "{procedure}");
+        }
+    }
+}
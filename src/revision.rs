@@ -0,0 +1,271 @@
+//! Changes between two publications of the same notice.
+//!
+//! SEAP republishes a notice under the same number whenever it is corrected (erratum, deadline
+//! extension, ...). Ingestion keeps a single tender per notice number and records the fields a
+//! republication changed as a revision. A revision is *material* when it changes something a
+//! bidder plans around, its dates or the value, and only those are worth notifying
+//! subscribers about again. Sources don't all carry every field (the SEAP listing has no
+//! description, for one), so a field missing from a republication is [`merge`]d from the stored
+//! tender rather than recorded as removed.
+
+use crate::models::Tender;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Fields whose change makes a revision material.
+pub const MATERIAL_FIELDS: [&str; 5] = [
+    "submission_deadline",
+    "clarification_deadline",
+    "opening_date",
+    "estimated_value",
+    "currency",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl Change {
+    fn new<T: Serialize>(field: &str, old: &T, new: &T) -> Change {
+        Change {
+            field: field.into(),
+            old: serde_json::to_value(old).unwrap_or_default(),
+            new: serde_json::to_value(new).unwrap_or_default(),
+        }
+    }
+
+    pub fn is_material(&self) -> bool {
+        MATERIAL_FIELDS.contains(&self.field.as_str())
+    }
+}
+
+/// The republication, with the optional fields it lacks taken from the stored tender. The
+/// currency goes along with the estimated value it applies to.
+pub fn merge(stored: &Tender, new: &Tender) -> Tender {
+    let mut merged = new.clone();
+
+    macro_rules! keep {
+        ($($field:ident),*) => {
+            $(
+                if merged.$field.is_none() {
+                    merged.$field = stored.$field.clone();
+                }
+            )*
+        };
+    }
+
+    if merged.estimated_value.is_none() {
+        merged.currency = stored.currency.clone();
+    }
+
+    keep!(
+        description,
+        additional_info,
+        estimated_value,
+        contracting_authority,
+        cpv_code,
+        submission_deadline,
+        procedure_type,
+        county,
+        contracting_authority_cui,
+        clarification_deadline,
+        opening_date
+    );
+
+    merged
+}
+
+/// The fields that differ between the stored tender and its republication, ignoring the id.
+pub fn diff(old: &Tender, new: &Tender) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    macro_rules! compare {
+        ($($field:ident),*) => {
+            $(
+                if old.$field != new.$field {
+                    changes.push(Change::new(stringify!($field), &old.$field, &new.$field));
+                }
+            )*
+        };
+    }
+
+    compare!(
+        notice_number,
+        title,
+        description,
+        additional_info,
+        estimated_value,
+        currency,
+        contracting_authority,
+        cpv_code,
+        publication_date,
+        submission_deadline,
+        procedure_type,
+        county,
+        contracting_authority_cui,
+        clarification_deadline,
+        opening_date
+    );
+
+    changes
+}
+
+pub fn is_material(changes: &[Change]) -> bool {
+    changes.iter().any(Change::is_material)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::{tender, Tender},
+        revision::{diff, is_material, merge, Change},
+    };
+    use serde_json::json;
+    use time::macros::{datetime, offset};
+
+    fn tender() -> Tender {
+        Tender {
+            id: 1,
+            description: None,
+            ..tender::sample()
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        {
+            let new = Tender {
+                id: 0,
+                publication_date: datetime!(2023-02-10 10:00 UTC).to_offset(offset!(+2)),
+                ..tender()
+            };
+
+            assert!(diff(&tender(), &new).is_empty());
+        }
+
+        {
+            let new = Tender {
+                description: Some("Erata: specificatii actualizate".into()),
+                estimated_value: Some(275_000),
+                ..tender()
+            };
+
+            assert_eq!(
+                diff(&tender(), &new),
+                vec![
+                    Change {
+                        field: "description".into(),
+                        old: json!(null),
+                        new: json!("Erata: specificatii actualizate"),
+                    },
+                    Change {
+                        field: "estimated_value".into(),
+                        old: json!(250_000),
+                        new: json!(275_000),
+                    },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_material() {
+        let cases = [
+            (
+                Tender {
+                    submission_deadline: Some(datetime!(2023-03-08 15:00 UTC)),
+                    ..tender()
+                },
+                true,
+            ),
+            (
+                Tender {
+                    submission_deadline: None,
+                    ..tender()
+                },
+                true,
+            ),
+            (
+                Tender {
+                    currency: "EUR".into(),
+                    ..tender()
+                },
+                true,
+            ),
+            (
+                Tender {
+                    opening_date: Some(datetime!(2023-03-01 16:00 UTC)),
+                    ..tender()
+                },
+                true,
+            ),
+            (
+                Tender {
+                    title: "Furnizare laptopuri".into(),
+                    publication_date: datetime!(2023-02-12 10:00 UTC),
+                    ..tender()
+                },
+                false,
+            ),
+            (tender(), false),
+        ];
+
+        for (new, expected) in cases {
+            let changes = diff(&tender(), &new);
+
+            assert_eq!(is_material(&changes), expected, "{changes:?}");
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let stored = Tender {
+            description: Some("Laptopuri pentru unitatile scolare".into()),
+            additional_info: Some("Garantie minima 36 de luni".into()),
+            estimated_value: Some(50_000),
+            currency: "EUR".into(),
+            ..tender()
+        };
+
+        {
+            let listed = Tender {
+                id: 0,
+                description: None,
+                additional_info: None,
+                estimated_value: None,
+                currency: "RON".into(),
+                ..tender()
+            };
+
+            let merged = merge(&stored, &listed);
+
+            assert_eq!(
+                merged,
+                Tender {
+                    id: 0,
+                    ..stored.clone()
+                }
+            );
+            assert!(diff(&stored, &merged).is_empty());
+        }
+
+        {
+            let corrected = Tender {
+                title: "Furnizare laptopuri".into(),
+                description: Some("Laptopuri".into()),
+                estimated_value: Some(250_000),
+                ..tender()
+            };
+
+            let merged = merge(&stored, &corrected);
+
+            assert_eq!(merged.description.as_deref(), Some("Laptopuri"));
+            assert_eq!(merged.additional_info, stored.additional_info);
+            assert_eq!(merged.estimated_value, Some(250_000));
+            assert_eq!(merged.currency, "RON");
+        }
+    }
+}
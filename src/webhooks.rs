@@ -0,0 +1,554 @@
+//! Outgoing webhooks notifying users' own systems about their matches.
+//!
+//! Matches are not sent right away: every event is queued as a delivery for each enabled
+//! webhook of the subscription's owner that listens to it, and a background worker posts the
+//! due deliveries. A payload is a JSON object with the `event`, the `match` and its `tender`,
+//! sent with the event name and delivery id in the `X-Seap-Event` and `X-Seap-Delivery`
+//! headers and signed in `X-Seap-Signature` as `sha256=` followed by the hex HMAC-SHA256 of the
+//! body keyed by the webhook's secret.
+//!
+//! Any response other than a 2xx is a failure. A failed delivery is retried with an
+//! exponential backoff up to [`Retry::max_attempts`] times, and a webhook that keeps failing
+//! is disabled after [`Retry::disable_after`] consecutive failed attempts, until it is updated.
+//!
+//! Deliveries don't follow redirects, and the worker's client only connects to hosts that
+//! resolve to public addresses, so a host re-pointed after the webhook was validated can't
+//! reach the service's own network either.
+
+use crate::models::{
+    delivery::{self, Delivery},
+    tender, webhook,
+    webhook::WebhookEvent,
+    Match, Tender, Webhook,
+};
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header::CONTENT_TYPE,
+    redirect, Client,
+};
+use serde::Serialize;
+use sha2::Sha256;
+use shuttle_service::tracing::{info, warn};
+use sqlx::PgPool;
+use std::{collections::HashMap, env, io, sync::Arc, time::Duration};
+use time::OffsetDateTime;
+use tokio::{net::lookup_host, task::JoinHandle};
+
+pub const EVENT_HEADER: &str = "X-Seap-Event";
+pub const DELIVERY_HEADER: &str = "X-Seap-Delivery";
+pub const SIGNATURE_HEADER: &str = "X-Seap-Signature";
+
+/// Deliveries attempted per run of the worker.
+const BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, Serialize)]
+pub struct Payload<'a> {
+    pub event: WebhookEvent,
+    #[serde(rename = "match")]
+    pub m: &'a Match,
+    pub tender: &'a Tender,
+}
+
+/// The value of the signature header for a body.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Queues the event for the webhooks of the matches' subscribers, returning how many
+/// deliveries were queued.
+pub async fn enqueue(pool: &PgPool, event: WebhookEvent, matches: &[Match]) -> Result<u64> {
+    let mut queued = 0;
+
+    for m in matches {
+        let tender = tender::get_one(pool, m.id_tender.try_into()?).await?;
+
+        let payload = serde_json::to_value(Payload {
+            event,
+            m,
+            tender: &tender,
+        })?;
+
+        queued += delivery::enqueue(pool, m.id_subscription, event, &payload).await?;
+    }
+
+    Ok(queued)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retry {
+    /// Wait after the first failed attempt, doubled after every further one.
+    pub base: Duration,
+    pub max_delay: Duration,
+    /// Attempts after which a delivery is given up on.
+    pub max_attempts: i32,
+    /// Consecutive failed attempts, across deliveries, after which a webhook is disabled.
+    pub disable_after: i32,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            base: Duration::from_secs(30),
+            max_delay: Duration::from_secs(6 * 60 * 60),
+            max_attempts: 10,
+            disable_after: 20,
+        }
+    }
+}
+
+impl Retry {
+    /// The default policy, overridden by the `WEBHOOK_RETRY_SECS`, `WEBHOOK_MAX_DELAY_SECS`,
+    /// `WEBHOOK_MAX_ATTEMPTS` and `WEBHOOK_DISABLE_AFTER` environment variables.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            env::var(name).ok().and_then(|value| value.parse().ok())
+        }
+
+        let default = Retry::default();
+
+        Retry {
+            base: var("WEBHOOK_RETRY_SECS").map_or(default.base, Duration::from_secs),
+            max_delay: var("WEBHOOK_MAX_DELAY_SECS").map_or(default.max_delay, Duration::from_secs),
+            max_attempts: var("WEBHOOK_MAX_ATTEMPTS").unwrap_or(default.max_attempts),
+            disable_after: var("WEBHOOK_DISABLE_AFTER").unwrap_or(default.disable_after),
+        }
+    }
+
+    /// How long to wait before retrying a delivery that failed `attempts` times.
+    pub fn delay(&self, attempts: i32) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 31) as u32;
+
+        self.base
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_delay)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Report {
+    pub delivered: usize,
+    /// Failed attempts that will be retried.
+    pub retried: usize,
+    /// Deliveries given up on.
+    pub failed: usize,
+}
+
+/// Attempts the deliveries that are due.
+pub async fn deliver_due(pool: &PgPool, client: &Client, retry: &Retry) -> Result<Report> {
+    let now = OffsetDateTime::now_utc();
+    let mut report = Report::default();
+    let mut webhooks: HashMap<i32, Webhook> = HashMap::new();
+
+    for d in delivery::get_due(pool, now, BATCH_SIZE).await? {
+        let hook = match webhooks.get(&d.id_webhook) {
+            Some(hook) => hook.clone(),
+            None => webhook::get_one(pool, d.id_webhook.try_into()?).await?,
+        };
+
+        // disabled by an earlier failure of this run
+        if !hook.enabled {
+            continue;
+        }
+
+        let success = match attempt(client, &hook, &d).await {
+            Ok(status_code) => {
+                delivery::record_success(pool, d.id, status_code, OffsetDateTime::now_utc())
+                    .await?;
+                report.delivered += 1;
+
+                true
+            }
+            Err((status_code, error)) => {
+                let attempts = d.attempts + 1;
+                let retry_at = (attempts < retry.max_attempts).then(|| now + retry.delay(attempts));
+
+                match retry_at {
+                    Some(_) => report.retried += 1,
+                    None => report.failed += 1,
+                }
+
+                delivery::record_failure(pool, d.id, status_code, &error, retry_at).await?;
+
+                false
+            }
+        };
+
+        let hook = webhook::record_attempt(pool, hook.id, success, retry.disable_after).await?;
+
+        if !hook.enabled {
+            warn!(
+                "Disabled webhook {} after {} consecutive failed deliveries.",
+                hook.id, hook.consecutive_failures
+            );
+        }
+
+        webhooks.insert(hook.id, hook);
+    }
+
+    Ok(report)
+}
+
+/// Posts the delivery, returning the status code of a successful response, or the status code
+/// if any and an error description.
+async fn attempt(
+    client: &Client,
+    hook: &Webhook,
+    d: &Delivery,
+) -> Result<i32, (Option<i32>, String)> {
+    let body = serde_json::to_vec(&d.payload.0).map_err(|err| (None, err.to_string()))?;
+
+    let res = client
+        .post(&hook.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, d.event.as_str())
+        .header(DELIVERY_HEADER, d.id)
+        .header(SIGNATURE_HEADER, sign(&hook.secret, &body))
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| (None, err.to_string()))?;
+
+    let status = res.status();
+
+    match status.is_success() {
+        true => Ok(i32::from(status.as_u16())),
+        false => Err((Some(i32::from(status.as_u16())), status.to_string())),
+    }
+}
+
+/// How often due deliveries are attempted, `WEBHOOK_INTERVAL_SECS` or every 30 seconds.
+pub fn interval_from_env() -> Duration {
+    env::var("WEBHOOK_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map_or(Duration::from_secs(30), Duration::from_secs)
+}
+
+/// Resolves hosts to their public addresses only, failing when they have none.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<_> = lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| webhook::is_public(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("{} has no public address", name.as_str()),
+                )
+                .into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The client deliveries are posted with.
+pub fn client() -> reqwest::Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(redirect::Policy::none())
+        .no_proxy()
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+}
+
+/// Attempts due deliveries forever, every `interval`.
+pub fn spawn(pool: PgPool, retry: Retry, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = match client() {
+            Ok(client) => client,
+            Err(err) => {
+                warn!("Building the webhook client failed: {err:#}");
+                return;
+            }
+        };
+
+        loop {
+            match deliver_due(&pool, &client, &retry).await {
+                Ok(report) if report != Report::default() => info!(
+                    "Delivered {} webhooks, {} to retry, {} failed.",
+                    report.delivered, report.retried, report.failed
+                ),
+                Ok(_) => {}
+                Err(err) => warn!("Delivering webhooks failed: {err:#}"),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        models::{
+            delivery::{self, DeliveryStatus},
+            load_fixtures, matches,
+            webhook::{self, WebhookEvent, WebhookParams},
+        },
+        utils::Pagination,
+        webhooks::{deliver_due, enqueue, sign, Report, Retry, SIGNATURE_HEADER},
+    };
+    use anyhow::Result;
+    use axum::{
+        body::Bytes,
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use reqwest::Client;
+    use serde_json::Value;
+    use sqlx::{query, PgPool};
+    use std::{
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicU16, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    /// A local endpoint recording the requests it receives and answering with `status`.
+    #[derive(Clone, Default)]
+    struct Receiver {
+        requests: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+        status: Arc<AtomicU16>,
+    }
+
+    impl Receiver {
+        fn start(status: StatusCode) -> Result<(Receiver, String)> {
+            let receiver = Receiver::default();
+            receiver.status.store(status.as_u16(), Ordering::SeqCst);
+
+            let app = Router::new()
+                .route("/hook", post(receive))
+                .with_state(receiver.clone());
+
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let url = format!("http://{}/hook", listener.local_addr()?);
+
+            tokio::spawn(axum::Server::from_tcp(listener)?.serve(app.into_make_service()));
+
+            Ok((receiver, url))
+        }
+
+        fn requests(&self) -> Vec<(HeaderMap, Bytes)> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        receiver.requests.lock().unwrap().push((headers, body));
+
+        StatusCode::from_u16(receiver.status.load(Ordering::SeqCst)).unwrap()
+    }
+
+    fn pagination() -> Pagination {
+        Pagination {
+            start_index: 0,
+            count: 10,
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let retry = Retry {
+            base: Duration::from_secs(30),
+            max_delay: Duration::from_secs(300),
+            ..Default::default()
+        };
+
+        let delays: Vec<u64> = (1..=6).map(|n| retry.delay(n).as_secs()).collect();
+
+        assert_eq!(delays, vec![30, 60, 120, 240, 300, 300]);
+        assert_eq!(retry.delay(i32::MAX).as_secs(), 300);
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_deliver(pool: PgPool) -> Result<()> {
+        load_fixtures(&pool).await?;
+
+        let (receiver, url) = Receiver::start(StatusCode::NO_CONTENT)?;
+
+        let hook = webhook::create(
+            &pool,
+            1,
+            &WebhookParams {
+                url,
+                secret: None,
+                events: vec![WebhookEvent::MatchCreated],
+            },
+        )
+        .await?;
+
+        {
+            let m = matches::get_all_of_user(&pool, 1, &pagination()).await?;
+
+            assert_eq!(enqueue(&pool, WebhookEvent::MatchCreated, &m).await?, 3);
+            assert_eq!(enqueue(&pool, WebhookEvent::MatchUpdated, &m).await?, 0);
+        }
+
+        {
+            let report = deliver_due(&pool, &Client::new(), &Retry::default()).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    delivered: 3,
+                    ..Default::default()
+                }
+            );
+        }
+
+        {
+            let requests = receiver.requests();
+
+            assert_eq!(requests.len(), 3);
+
+            for (headers, body) in &requests {
+                assert_eq!(headers[SIGNATURE_HEADER], sign(&hook.secret, body));
+                assert_eq!(headers["X-Seap-Event"], "match_created");
+                assert_eq!(headers["Content-Type"], "application/json");
+
+                let payload: Value = serde_json::from_slice(body)?;
+
+                assert_eq!(payload["event"], "match_created");
+                assert_eq!(payload["match"]["id_tender"], payload["tender"]["id"]);
+            }
+        }
+
+        {
+            let log = delivery::get_all_of_webhook(&pool, hook.id as usize, &pagination()).await?;
+
+            assert_eq!(log.len(), 3);
+            assert!(log.iter().all(|d| d.status == DeliveryStatus::Delivered));
+            assert!(log.iter().all(|d| d.last_status_code == Some(204)));
+        }
+
+        {
+            let report = deliver_due(&pool, &Client::new(), &Retry::default()).await?;
+
+            assert_eq!(report, Report::default());
+        }
+
+        Ok(())
+    }
+
+    #[ignore]
+    #[sqlx::test]
+    async fn test_retries_and_disabling(pool: PgPool) -> Result<()> {
+        load_fixtures(&pool).await?;
+
+        let (receiver, url) = Receiver::start(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let params = WebhookParams {
+            url,
+            secret: None,
+            events: vec![WebhookEvent::MatchCreated],
+        };
+
+        let hook = webhook::create(&pool, 1, &params).await?;
+
+        let retry = Retry {
+            base: Duration::from_secs(60),
+            max_attempts: 2,
+            disable_after: 4,
+            ..Default::default()
+        };
+
+        let m = matches::get_all_of_user(&pool, 1, &pagination()).await?;
+        enqueue(&pool, WebhookEvent::MatchCreated, &m).await?;
+
+        {
+            let report = deliver_due(&pool, &Client::new(), &retry).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    retried: 3,
+                    ..Default::default()
+                }
+            );
+
+            // none is due before the backoff elapses
+            let report = deliver_due(&pool, &Client::new(), &retry).await?;
+
+            assert_eq!(report, Report::default());
+        }
+
+        query!("UPDATE webhook_deliveries SET next_attempt_at = NOW()")
+            .execute(&pool)
+            .await?;
+
+        {
+            let report = deliver_due(&pool, &Client::new(), &retry).await?;
+
+            assert_eq!(
+                report,
+                Report {
+                    failed: 1,
+                    ..Default::default()
+                }
+            );
+            assert_eq!(receiver.requests().len(), 4);
+
+            let hook = webhook::get_one(&pool, hook.id as usize).await?;
+
+            assert!(!hook.enabled);
+            assert_eq!(hook.consecutive_failures, 4);
+
+            let log = delivery::get_all_of_webhook(&pool, hook.id as usize, &pagination()).await?;
+            let statuses: Vec<DeliveryStatus> = log.iter().rev().map(|d| d.status).collect();
+
+            assert_eq!(
+                statuses,
+                vec![
+                    DeliveryStatus::Failed,
+                    DeliveryStatus::Pending,
+                    DeliveryStatus::Pending
+                ]
+            );
+            assert_eq!(log[2].last_status_code, Some(500));
+            assert_eq!(log[2].attempts, 2);
+        }
+
+        {
+            receiver.status.store(200, Ordering::SeqCst);
+            webhook::update(&pool, hook.id as usize, &params).await?;
+
+            let report = deliver_due(&pool, &Client::new(), &retry).await?;
+
+            assert_eq!(report.delivered, 2);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+//! Regression corpus for the eForms notice parser.
+//!
+//! Every `tests/fixtures/ted/*.xml` notice is parsed and compared to the `.json` file of the same
+//! name; notices whose name starts with `invalid_` must be rejected instead. Set
+//! `UPDATE_TED_CORPUS=1` to rewrite the expected files after an intended change.
+
+use seap_subscription_api::ingest::ted;
+use std::{env, fs, path::PathBuf};
+
+#[test]
+fn test_corpus() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ted");
+    let update = env::var_os("UPDATE_TED_CORPUS").is_some();
+
+    let mut notices: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "xml"))
+        .collect();
+    notices.sort();
+
+    assert!(!notices.is_empty(), "no notices in {}", dir.display());
+
+    for path in notices {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let xml = fs::read_to_string(&path).unwrap();
+
+        let res = ted::parse(&xml);
+
+        if name.starts_with("invalid_") {
+            assert!(res.is_err(), "{name} should be rejected");
+            continue;
+        }
+
+        let notice = match res {
+            Ok(notice) => serde_json::to_value(notice).unwrap(),
+            Err(err) => panic!("{name}: {err}"),
+        };
+
+        let expected_path = path.with_extension("json");
+
+        if update {
+            let json = serde_json::to_string_pretty(&notice).unwrap();
+            fs::write(&expected_path, json + "\n").unwrap();
+            continue;
+        }
+
+        let expected: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&expected_path).unwrap()).unwrap();
+
+        assert_eq!(notice, expected, "{name}");
+    }
+}